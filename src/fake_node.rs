@@ -0,0 +1,254 @@
+//! An in-process stand-in for neptune-core's RPC server, for tests.
+//!
+//! [`FakeNode`] answers requests of the neptune-core RPC service from a
+//! scripted [`MockChain`] over an in-memory transport, and counts the calls it
+//! receives. [`FakeNode::disconnect`] simulates an outage of the node.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures::StreamExt;
+use neptune_cash::api::export::AdditionRecord;
+use neptune_cash::api::export::Announcement;
+use neptune_cash::api::export::NativeCurrencyAmount;
+use neptune_cash::api::export::Timestamp;
+use neptune_cash::application::rpc::auth;
+use neptune_cash::application::rpc::server::proof_of_work_puzzle::ProofOfWorkPuzzle;
+use neptune_cash::application::rpc::server::RPCClient;
+use neptune_cash::application::rpc::server::RPCRequest;
+use neptune_cash::application::rpc::server::RPCResponse;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+use neptune_cash::protocol::consensus::block::block_height::BlockHeight;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::difficulty_control::Difficulty;
+use neptune_cash::protocol::consensus::block::difficulty_control::ProofOfWork;
+use neptune_cash::protocol::consensus::block::pow::PowMastPaths;
+use neptune_cash::protocol::consensus::transaction::transaction_kernel::TransactionKernelProxy;
+use num_traits::Zero;
+use tarpc::server::BaseChannel;
+use tarpc::server::Channel;
+use tarpc::ServerError;
+use tokio::task::AbortHandle;
+
+use crate::model::mock_chain::MockBlock;
+use crate::model::mock_chain::MockChain;
+use crate::model::rpc_fixtures::NodeStub;
+use crate::model::rpc_limits::RpcLimiter;
+use crate::model::rpc_limits::RpcLimitsPolicy;
+use crate::neptune_rpc::AuthenticatedClient;
+
+/// A neptune-core node with a scripted chain.
+#[derive(Debug, Default)]
+pub struct FakeNode {
+    pub chain: MockChain,
+
+    /// How long to take for every answer, so that concurrent calls overlap.
+    pub latency: Duration,
+
+    /// The puzzle handed out to external guessers; solutions to it are
+    /// accepted.
+    pow_puzzle: Mutex<Option<ProofOfWorkPuzzle>>,
+
+    calls: Mutex<HashMap<String, usize>>,
+    connections: Mutex<Vec<AbortHandle>>,
+}
+
+impl FakeNode {
+    /// A node whose chain has blocks `0..=tip`, ten minutes apart, each with
+    /// one output and no announcements.
+    pub fn with_tip(tip: u64) -> Self {
+        let blocks = (0..=tip)
+            .map(|height| MockBlock {
+                info: BlockInfo {
+                    height: BlockHeight::from(height),
+                    size: 1000,
+                    digest: block_digest(height),
+                    prev_block_digest: if height == 0 {
+                        Digest::default()
+                    } else {
+                        block_digest(height - 1)
+                    },
+                    timestamp: Timestamp::minutes(10 * height as usize),
+                    cumulative_proof_of_work: ProofOfWork::zero(),
+                    difficulty: Difficulty::MINIMUM,
+                    num_inputs: 0,
+                    num_outputs: 1,
+                    num_announcements: 0,
+                    coinbase_amount: NativeCurrencyAmount::zero(),
+                    fee: NativeCurrencyAmount::zero(),
+                    is_genesis: height == 0,
+                    is_tip: height == tip,
+                    is_canonical: true,
+                    sibling_blocks: vec![],
+                    lustration_status: None,
+                },
+                announcements: vec![],
+                outputs: vec![output(height)],
+            })
+            .collect();
+        Self {
+            chain: MockChain {
+                blocks,
+                ..MockChain::default()
+            },
+            ..Self::default()
+        }
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn with_utxo_index(mut self) -> Self {
+        self.chain.utxo_index = true;
+        self
+    }
+
+    /// Add `announcement` to the block at `height`.
+    pub fn with_announcement(mut self, height: u64, announcement: Announcement) -> Self {
+        let block = &mut self.chain.blocks[height as usize];
+        block.announcements.push(announcement);
+        block.info.num_announcements = block.announcements.len();
+        self
+    }
+
+    /// Add a transaction with the given outputs to the mempool.
+    pub fn with_mempool_tx(mut self, outputs: Vec<AdditionRecord>) -> Self {
+        let kernel = TransactionKernelProxy {
+            inputs: vec![],
+            outputs,
+            announcements: vec![],
+            fee: NativeCurrencyAmount::zero(),
+            coinbase: None,
+            timestamp: Timestamp::minutes(10 * self.chain.blocks.len()),
+            mutator_set_hash: Digest::default(),
+            merge_bit: false,
+        }
+        .into_kernel();
+        self.chain.mempool.push(kernel);
+        self
+    }
+
+    /// Hand out a puzzle with the given `id` from now on, as when the node
+    /// moves to a new block proposal.
+    pub fn set_pow_puzzle(&self, id: Digest) {
+        *self.pow_puzzle.lock().unwrap() = Some(pow_puzzle(id));
+    }
+
+    /// Number of calls of `method`, named as in the RPC trait, received so far.
+    pub fn calls(&self, method: &str) -> usize {
+        self.calls
+            .lock()
+            .unwrap()
+            .get(method)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// A client connected to this node, with default limits.
+    pub fn client(self: &Arc<Self>) -> AuthenticatedClient {
+        AuthenticatedClient {
+            client: RPCClient::from(NodeStub::Live(self.connect())),
+            token: auth::Cookie::from([0; 32]).into(),
+            network: self.chain.network,
+            limiter: Arc::new(RpcLimiter::new(&RpcLimitsPolicy::default())),
+            in_flight: Default::default(),
+        }
+    }
+
+    /// A new connection to this node.
+    pub fn connect(self: &Arc<Self>) -> tarpc::client::Channel<RPCRequest, RPCResponse> {
+        let (client_transport, server_transport) = tarpc::transport::channel::unbounded();
+        let node = self.clone();
+        let serve = tarpc::server::serve(move |_, request| {
+            let node = node.clone();
+            async move { node.answer(request).await }
+        });
+        let server = tokio::spawn(
+            BaseChannel::with_defaults(server_transport)
+                .execute(serve)
+                .for_each(|response| async {
+                    tokio::spawn(response);
+                }),
+        );
+        self.connections.lock().unwrap().push(server.abort_handle());
+
+        tarpc::client::new(tarpc::client::Config::default(), client_transport).spawn()
+    }
+
+    /// Drop all connections, as if the node went down. Clients get transport
+    /// errors from then on.
+    pub fn disconnect(&self) {
+        for connection in self.connections.lock().unwrap().drain(..) {
+            connection.abort();
+        }
+    }
+
+    async fn answer(&self, request: RPCRequest) -> Result<RPCResponse, ServerError> {
+        tokio::time::sleep(self.latency).await;
+        *self
+            .calls
+            .lock()
+            .unwrap()
+            .entry(method_name(&request))
+            .or_default() += 1;
+        let pow_puzzle = *self.pow_puzzle.lock().unwrap();
+        match request {
+            RPCRequest::PowPuzzleExternalKey { .. } => {
+                Ok(RPCResponse::PowPuzzleExternalKey(Ok(pow_puzzle)))
+            }
+            RPCRequest::ProvidePowSolution { proposal_id, .. } => {
+                Ok(RPCResponse::ProvidePowSolution(Ok(pow_puzzle
+                    .map(|p| p.id)
+                    == Some(proposal_id))))
+            }
+            request => self.chain.answer(request),
+        }
+    }
+}
+
+/// The RPC method `request` calls, e.g. `block_info` for
+/// [`RPCRequest::BlockInfo`].
+fn method_name(request: &RPCRequest) -> String {
+    let variant = serde_json::to_value(request)
+        .ok()
+        .and_then(|value| value.as_object()?.keys().next().cloned())
+        .unwrap_or_default();
+    let mut name = String::new();
+    for c in variant.chars() {
+        if c.is_uppercase() && !name.is_empty() {
+            name.push('_');
+        }
+        name.extend(c.to_lowercase());
+    }
+    name
+}
+
+/// Digest of the fake block at `height`.
+pub fn block_digest(height: u64) -> Digest {
+    Digest::new([BFieldElement::new(height + 1); Digest::LEN])
+}
+
+/// A puzzle with the given `id` and otherwise made-up fields.
+pub fn pow_puzzle(id: Digest) -> ProofOfWorkPuzzle {
+    ProofOfWorkPuzzle {
+        pow_mast_paths: PowMastPaths::default(),
+        threshold: Difficulty::MINIMUM.target(),
+        total_guesser_reward: NativeCurrencyAmount::coins(1),
+        id,
+        prev_block: Digest::default(),
+        lustration_status: None,
+        version: BFieldElement::new(0),
+    }
+}
+
+/// The output of the fake block at `height`.
+pub fn output(height: u64) -> AdditionRecord {
+    AdditionRecord::new(Digest::new(
+        [BFieldElement::new(1000 + height); Digest::LEN],
+    ))
+}
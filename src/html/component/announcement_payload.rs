@@ -0,0 +1,72 @@
+//! The payload section of the announcement page.
+//!
+//! Every [`AnnouncementType`] variant renders through its own partial under
+//! `templates/web/html/components/announcement/`, except for
+//! [`AnnouncementType::Custom`], which renders through its
+//! [`AnnouncementView`](crate::model::announcement_type::AnnouncementView).
+
+use std::collections::HashMap;
+use std::fmt;
+
+use neptune_cash::api::export::TransparentInput;
+use neptune_cash::api::export::TransparentTransactionInfo;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::prelude::triton_vm::prelude::BFieldCodec;
+use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+use neptune_cash::prelude::twenty_first::tip5::Tip5;
+use neptune_cash::util_types::mutator_set::addition_record::AdditionRecord;
+
+use crate::model::announcement_type::AnnouncementType;
+use crate::model::announcement_type::UtxoNotification;
+
+#[derive(Debug, Clone)]
+pub struct AnnouncementPayloadHtml<'a> {
+    pub announcement_type: &'a AnnouncementType,
+
+    /// AOCL leaf indices of transparent outputs, where known, for linking to
+    /// the UTXO page.
+    pub addition_record_indices: &'a HashMap<AdditionRecord, Option<u64>>,
+}
+
+impl fmt::Display for AnnouncementPayloadHtml<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.announcement_type {
+            AnnouncementType::TransparentTxInfo(tx_info) => TransparentTxInfoHtml {
+                tx_info,
+                addition_record_indices: self.addition_record_indices,
+            }
+            .fmt(f),
+            AnnouncementType::Lustration(lustration) => LustrationHtml { lustration }.fmt(f),
+            AnnouncementType::UtxoNotification(notification) => {
+                UtxoNotificationHtml { notification }.fmt(f)
+            }
+            AnnouncementType::Custom(custom) => f.write_str(&custom.view.to_html()),
+            AnnouncementType::Unknown(payload) => UnknownHtml { payload }.fmt(f),
+        }
+    }
+}
+
+#[derive(Debug, Clone, boilerplate::Boilerplate)]
+#[boilerplate(filename = "web/html/components/announcement/transparent_tx_info.html")]
+struct TransparentTxInfoHtml<'a> {
+    tx_info: &'a TransparentTransactionInfo,
+    addition_record_indices: &'a HashMap<AdditionRecord, Option<u64>>,
+}
+
+#[derive(Debug, Clone, boilerplate::Boilerplate)]
+#[boilerplate(filename = "web/html/components/announcement/lustration.html")]
+struct LustrationHtml<'a> {
+    lustration: &'a TransparentInput,
+}
+
+#[derive(Debug, Clone, boilerplate::Boilerplate)]
+#[boilerplate(filename = "web/html/components/announcement/utxo_notification.html")]
+struct UtxoNotificationHtml<'a> {
+    notification: &'a UtxoNotification,
+}
+
+#[derive(Debug, Clone, boilerplate::Boilerplate)]
+#[boilerplate(filename = "web/html/components/announcement/unknown.html")]
+struct UnknownHtml<'a> {
+    payload: &'a Vec<BFieldElement>,
+}
@@ -0,0 +1,15 @@
+use crate::model::app_state::AppStateInner;
+
+/// The footer of every page, with the theme's links.
+#[derive(Debug, Clone, boilerplate::Boilerplate)]
+#[boilerplate(filename = "web/html/components/footer.html")]
+pub struct FooterHtml<'a> {
+    pub state: &'a AppStateInner,
+}
+
+impl FooterHtml<'_> {
+    /// The component, rendered with the operator's override if any.
+    pub fn render(&self) -> String {
+        self.state.templates.render(self)
+    }
+}
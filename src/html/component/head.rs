@@ -0,0 +1,16 @@
+use crate::model::app_state::AppStateInner;
+
+/// The common contents of `<head>`: stylesheets, favicon, viewport and the
+/// theme's additions.
+#[derive(Debug, Clone, boilerplate::Boilerplate)]
+#[boilerplate(filename = "web/html/components/head.html")]
+pub struct HeadHtml<'a> {
+    pub state: &'a AppStateInner,
+}
+
+impl HeadHtml<'_> {
+    /// The component, rendered with the operator's override if any.
+    pub fn render(&self) -> String {
+        self.state.templates.render(self)
+    }
+}
@@ -1,3 +1,5 @@
+use crate::html::component::footer::FooterHtml;
+use crate::html::component::head::HeadHtml;
 use crate::model::app_state::AppStateInner;
 
 #[derive(Debug, Clone, boilerplate::Boilerplate)]
@@ -5,3 +7,20 @@ use crate::model::app_state::AppStateInner;
 pub struct HeaderHtml<'a> {
     pub state: &'a AppStateInner,
 }
+
+impl<'a> HeaderHtml<'a> {
+    /// The component, rendered with the operator's override if any.
+    pub fn render(&self) -> String {
+        self.state.templates.render(self)
+    }
+
+    /// The contents of `<head>` for the same page.
+    pub fn head(&self) -> HeadHtml<'a> {
+        HeadHtml { state: self.state }
+    }
+
+    /// The footer for the same page.
+    pub fn footer(&self) -> FooterHtml<'a> {
+        FooterHtml { state: self.state }
+    }
+}
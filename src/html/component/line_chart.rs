@@ -0,0 +1,198 @@
+//! Server-rendered SVG line charts, so that chart pages work without
+//! javascript.
+
+pub(crate) const WIDTH: f64 = 800.0;
+pub(crate) const HEIGHT: f64 = 320.0;
+pub(crate) const PADDING_LEFT: f64 = 90.0;
+pub(crate) const PADDING_RIGHT: f64 = 20.0;
+pub(crate) const PADDING_TOP: f64 = 20.0;
+pub(crate) const PADDING_BOTTOM: f64 = 40.0;
+
+const NUM_TICKS: usize = 5;
+
+#[derive(Debug, Clone)]
+pub struct ChartSeries {
+    pub name: String,
+
+    /// Any CSS colour.
+    pub color: &'static str,
+
+    /// `(x, y)` pairs, in ascending order of `x`.
+    pub points: Vec<(f64, f64)>,
+}
+
+/// A labelled vertical line, e.g. a generation boundary.
+#[derive(Debug, Clone)]
+pub struct ChartMarker {
+    pub x: f64,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, boilerplate::Boilerplate)]
+#[boilerplate(filename = "web/html/components/line_chart.html")]
+pub struct LineChartHtml {
+    pub title: String,
+    pub x_label: String,
+    pub series: Vec<ChartSeries>,
+    pub markers: Vec<ChartMarker>,
+
+    /// Formats the values shown along the x-axis.
+    pub format_x: fn(f64) -> String,
+
+    /// Formats the values shown along the y-axis.
+    pub format_y: fn(f64) -> String,
+}
+
+impl LineChartHtml {
+    fn points(&self) -> impl Iterator<Item = &(f64, f64)> {
+        self.series.iter().flat_map(|series| series.points.iter())
+    }
+
+    fn x_range(&self) -> (f64, f64) {
+        let min = self.points().map(|(x, _)| *x).fold(f64::INFINITY, f64::min);
+        let max = self
+            .points()
+            .map(|(x, _)| *x)
+            .fold(f64::NEG_INFINITY, f64::max);
+        match (min.is_finite(), max > min) {
+            (true, true) => (min, max),
+            (true, false) => (min, min + 1.0),
+            (false, _) => (0.0, 1.0),
+        }
+    }
+
+    /// The y-axis always starts at zero.
+    fn y_max(&self) -> f64 {
+        let max = self.points().map(|(_, y)| *y).fold(0.0, f64::max);
+        if max > 0.0 {
+            max
+        } else {
+            1.0
+        }
+    }
+
+    pub(crate) fn contains_x(&self, x: f64) -> bool {
+        let (min, max) = self.x_range();
+        (min..=max).contains(&x)
+    }
+
+    pub(crate) fn scale_x(&self, x: f64) -> f64 {
+        let (min, max) = self.x_range();
+        PADDING_LEFT + (x - min) / (max - min) * (WIDTH - PADDING_LEFT - PADDING_RIGHT)
+    }
+
+    pub(crate) fn scale_y(&self, y: f64) -> f64 {
+        HEIGHT - PADDING_BOTTOM - y / self.y_max() * (HEIGHT - PADDING_TOP - PADDING_BOTTOM)
+    }
+
+    pub(crate) fn polyline_points(&self, series: &ChartSeries) -> String {
+        series
+            .points
+            .iter()
+            .map(|(x, y)| format!("{:.1},{:.1}", self.scale_x(*x), self.scale_y(*y)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub(crate) fn x_ticks(&self) -> Vec<(f64, String)> {
+        let (min, max) = self.x_range();
+        (0..NUM_TICKS)
+            .map(|i| min + (max - min) * i as f64 / (NUM_TICKS - 1) as f64)
+            .map(|x| (self.scale_x(x), (self.format_x)(x)))
+            .collect()
+    }
+
+    pub(crate) fn y_ticks(&self) -> Vec<(f64, String)> {
+        let max = self.y_max();
+        (0..NUM_TICKS)
+            .map(|i| max * i as f64 / (NUM_TICKS - 1) as f64)
+            .map(|y| (self.scale_y(y), (self.format_y)(y)))
+            .collect()
+    }
+}
+
+/// Format as a whole number with thousands separators.
+pub fn format_whole(value: f64) -> String {
+    use thousands::Separable;
+    format!("{value:.0}").separate_with_commas()
+}
+
+/// Format with an SI prefix (k, M, G, ...), for quantities spanning many
+/// orders of magnitude such as hashrates.
+pub fn format_si(value: f64) -> String {
+    const PREFIXES: [&str; 7] = ["", "k", "M", "G", "T", "P", "E"];
+    let mut scaled = value;
+    let mut prefix = 0;
+    while scaled.abs() >= 1000.0 && prefix < PREFIXES.len() - 1 {
+        scaled /= 1000.0;
+        prefix += 1;
+    }
+    match prefix {
+        0 => format!("{scaled:.0}"),
+        _ => format!("{scaled:.1} {}", PREFIXES[prefix]),
+    }
+}
+
+/// Format unix seconds as a UTC date.
+pub fn format_date(unix_seconds: f64) -> String {
+    format_unix_seconds(unix_seconds, "%Y-%m-%d")
+}
+
+/// Format unix seconds as a UTC date and hour.
+pub fn format_date_hour(unix_seconds: f64) -> String {
+    format_unix_seconds(unix_seconds, "%m-%d %H:00")
+}
+
+fn format_unix_seconds(unix_seconds: f64, format: &str) -> String {
+    chrono::DateTime::from_timestamp(unix_seconds as i64, 0)
+        .map(|time| time.format(format).to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(points: Vec<(f64, f64)>) -> LineChartHtml {
+        LineChartHtml {
+            title: "test".to_string(),
+            x_label: "x".to_string(),
+            series: vec![ChartSeries {
+                name: "series".to_string(),
+                color: "red",
+                points,
+            }],
+            markers: vec![],
+            format_x: format_whole,
+            format_y: format_whole,
+        }
+    }
+
+    #[test]
+    fn points_are_scaled_into_plot_area() {
+        let chart = chart(vec![(10.0, 0.0), (20.0, 50.0)]);
+        assert_eq!(PADDING_LEFT, chart.scale_x(10.0));
+        assert_eq!(WIDTH - PADDING_RIGHT, chart.scale_x(20.0));
+        assert_eq!(HEIGHT - PADDING_BOTTOM, chart.scale_y(0.0));
+        assert_eq!(PADDING_TOP, chart.scale_y(50.0));
+    }
+
+    #[test]
+    fn si_prefixes() {
+        assert_eq!("999", format_si(999.0));
+        assert_eq!("1.5 k", format_si(1500.0));
+        assert_eq!("2.0 G", format_si(2e9));
+    }
+
+    #[test]
+    fn dates_are_utc() {
+        assert_eq!("2025-08-11", format_date(1_754_913_600.0));
+        assert_eq!("08-11 12:00", format_date_hour(1_754_913_600.0));
+    }
+
+    #[test]
+    fn degenerate_charts_render() {
+        assert!(chart(vec![]).to_string().contains("<svg"));
+        assert!(chart(vec![(5.0, 0.0)]).to_string().contains("<polyline"));
+    }
+}
@@ -1 +1,5 @@
+pub mod announcement_payload;
+pub mod footer;
+pub mod head;
 pub mod header;
+pub mod line_chart;
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use axum::extract::rejection::PathRejection;
+use axum::extract::Path;
+use axum::extract::State;
+use axum::response::Html;
+use axum::response::Response;
+use boilerplate::Trusted;
+
+use crate::html::component::header::HeaderHtml;
+use crate::html::page::not_found::not_found_html_response;
+use crate::model::address::address_qr_svg;
+use crate::model::address::decode_generation_address;
+use crate::model::address::AddressDetails;
+use crate::model::app_state::AppState;
+
+/// HTML page showing what can be learned from a generation address: its
+/// network, receiver identifier and a QR code.
+///
+/// Route: `/address/:bech32m`.
+#[axum::debug_handler]
+pub async fn address_page(
+    user_input_maybe: Result<Path<String>, PathRejection>,
+    State(state_rw): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    #[derive(boilerplate::Boilerplate)]
+    #[boilerplate(filename = "web/html/page/address.html")]
+    pub struct AddressHtmlPage<'a> {
+        header: HeaderHtml<'a>,
+        details: AddressDetails,
+        qr_svg: Option<String>,
+    }
+    let state = &state_rw.load();
+
+    let Path(bech32m) =
+        user_input_maybe.map_err(|e| not_found_html_response(state, Some(e.to_string())))?;
+    let details = decode_generation_address(bech32m.trim(), state.network)
+        .map_err(|e| not_found_html_response(state, Some(e.to_string())))?;
+
+    let header = HeaderHtml { state };
+
+    let page = AddressHtmlPage {
+        header,
+        qr_svg: address_qr_svg(&details.address),
+        details,
+    };
+    Ok(Html(state.templates.render(&page)))
+}
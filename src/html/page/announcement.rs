@@ -9,13 +9,14 @@ use axum::response::Response;
 use boilerplate::Trusted;
 use neptune_cash::api::export::BlockHeight;
 use neptune_cash::prelude::tasm_lib::prelude::Digest;
-use neptune_cash::prelude::triton_vm::prelude::BFieldCodec;
-use neptune_cash::prelude::twenty_first::tip5::Tip5;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
 use neptune_cash::util_types::mutator_set::addition_record::AdditionRecord;
 use tarpc::context;
 
+use crate::html::component::announcement_payload::AnnouncementPayloadHtml;
 use crate::html::component::header::HeaderHtml;
 use crate::html::page::not_found::not_found_html_response;
+use crate::html::page::not_found::rpc_err_html_response;
 use crate::http_util::rpc_method_err;
 use crate::model::announcement_selector::AnnouncementSelector;
 use crate::model::announcement_type::AnnouncementType;
@@ -35,8 +36,8 @@ pub async fn announcement_page(
         num_announcements: usize,
         block_hash: Digest,
         block_height: BlockHeight,
-        announcement_type: AnnouncementType,
-        addition_record_indices: HashMap<AdditionRecord, Option<u64>>,
+        announcement_type: &'a AnnouncementType,
+        payload: AnnouncementPayloadHtml<'a>,
     }
 
     let state = &state_rw.load();
@@ -50,7 +51,7 @@ pub async fn announcement_page(
         .rpc_client
         .block_info(context::current(), state.token(), block_selector)
         .await
-        .map_err(|e| not_found_html_response(state, Some(e.to_string())))?
+        .map_err(|e| rpc_err_html_response(state, e))?
         .map_err(rpc_method_err)?
         .ok_or(not_found_html_response(
             state,
@@ -58,16 +59,21 @@ pub async fn announcement_page(
         ))?;
     let block_hash = block_info.digest;
     let block_height = block_info.height;
+    // by digest, so that a new tip in the meantime cannot change the block
+    let block_selector = BlockSelector::Digest(block_hash);
 
     let announcements = state
         .rpc_client
         .announcements_in_block(context::current(), state.token(), block_selector)
         .await
-        .map_err(|e| not_found_html_response(state, Some(e.to_string())))?
+        .map_err(|e| rpc_err_html_response(state, e))?
         .map_err(rpc_method_err)?
-        .expect(
-            "block guaranteed to exist because we got here; getting its announcements should work",
-        );
+        .ok_or_else(|| {
+            not_found_html_response(
+                state,
+                Some("The requested block does not exist".to_string()),
+            )
+        })?;
     let num_announcements = announcements.len();
     let announcement = announcements
         .get(index)
@@ -76,25 +82,15 @@ pub async fn announcement_page(
             Some("The requested announcement does not exist".to_string()),
         ))?
         .clone();
-    let announcement_type = AnnouncementType::parse(announcement);
+    let announcement_type = state.announcement_decoders.parse(announcement);
 
     let mut addition_record_indices = HashMap::<AdditionRecord, Option<u64>>::new();
-    if let AnnouncementType::TransparentTxInfo(tx_info) = announcement_type.clone() {
-        let addition_records = tx_info
-            .outputs
-            .iter()
-            .map(|output| output.addition_record())
-            .collect::<Vec<_>>();
+    if let AnnouncementType::TransparentTxInfo(tx_info) = &announcement_type {
         addition_record_indices = state
             .rpc_client
-            .addition_record_indices_for_block(
-                context::current(),
-                state.token(),
-                block_selector,
-                &addition_records,
-            )
+            .addition_record_indices_for_block(context::current(), state.token(), block_selector)
             .await
-            .map_err(|e| not_found_html_response(state, Some(e.to_string())))?
+            .map_err(|e| rpc_err_html_response(state, e))?
             .map_err(rpc_method_err)?
             .into_iter()
             .collect::<HashMap<_, _>>();
@@ -145,8 +141,11 @@ pub async fn announcement_page(
         block_hash,
         block_height,
         num_announcements,
-        announcement_type,
-        addition_record_indices,
+        announcement_type: &announcement_type,
+        payload: AnnouncementPayloadHtml {
+            announcement_type: &announcement_type,
+            addition_record_indices: &addition_record_indices,
+        },
     };
-    Ok(Html(utxo_page.to_string()))
+    Ok(Html(state.templates.render(&utxo_page)))
 }
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use axum::extract::Query;
+use axum::extract::State;
+use axum::response::Html;
+use axum::response::Response;
+use boilerplate::Trusted;
+
+use crate::html::component::header::HeaderHtml;
+use crate::html::page::not_found::not_found_page;
+use crate::html::page::not_found::rpc_err_html_response;
+use crate::http_util::rpc_method_err;
+use crate::http_util::service_unavailable_html;
+use crate::model::announcement_search::search_announcements;
+use crate::model::announcement_search::AnnouncementSearchError;
+use crate::model::announcement_search::AnnouncementSearchQuery;
+use crate::model::announcement_search::AnnouncementSearchResults;
+use crate::model::announcement_search::INDEX_REQUIRED_MESSAGE;
+use crate::model::app_state::AppState;
+
+/// HTML page searching for blocks with announcements of a given flag and
+/// receiver identifier. Without a `flag` in the query string, only the search
+/// form is shown.
+///
+/// Route: `/announcements/search`. Shares [`search_announcements`] with the
+/// JSON endpoint.
+#[axum::debug_handler]
+pub async fn announcement_search_page(
+    Query(query): Query<AnnouncementSearchQuery>,
+    State(state_rw): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    #[derive(boilerplate::Boilerplate)]
+    #[boilerplate(filename = "web/html/page/announcement_search.html")]
+    pub struct AnnouncementSearchHtmlPage<'a> {
+        header: HeaderHtml<'a>,
+        query: AnnouncementSearchQuery,
+        /// Names of the registered announcement types that can be searched for.
+        searchable_types: Vec<&'static str>,
+        results: Option<AnnouncementSearchResults>,
+        error: Option<String>,
+    }
+
+    let state = &state_rw.load();
+
+    let index_unavailable = || {
+        service_unavailable_html(not_found_page(
+            state,
+            Some(INDEX_REQUIRED_MESSAGE.to_string()),
+        ))
+    };
+    if !state.maintains_utxo_index {
+        return Err(index_unavailable());
+    }
+
+    let (results, error) = match query.flag {
+        None => (None, None),
+        Some(_) => match search_announcements(state, &query).await {
+            Ok(results) => (Some(results), None),
+            Err(AnnouncementSearchError::Query(e)) => (None, Some(e.to_string())),
+            Err(AnnouncementSearchError::Transport(t)) => {
+                return Err(rpc_err_html_response(state, t))
+            }
+            Err(AnnouncementSearchError::Method(m)) => return Err(rpc_method_err(m)),
+            Err(AnnouncementSearchError::IndexUnavailable) => return Err(index_unavailable()),
+        },
+    };
+
+    let searchable_types = state
+        .announcement_decoders
+        .decoders()
+        .filter(|decoder| decoder.flag().is_some())
+        .map(|decoder| decoder.name())
+        .collect();
+
+    let header = HeaderHtml { state };
+
+    let page = AnnouncementSearchHtmlPage {
+        header,
+        query,
+        searchable_types,
+        results,
+        error,
+    };
+    Ok(Html(state.templates.render(&page)))
+}
@@ -6,16 +6,24 @@ use axum::extract::State;
 use axum::response::Html;
 use axum::response::Response;
 use boilerplate::Trusted;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
 use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
 use num_traits::Zero;
 use tarpc::context;
 use thousands::Separable;
 
 use crate::html::component::header::HeaderHtml;
+use crate::html::page::not_found::node_err_html_response;
 use crate::html::page::not_found::not_found_html_response;
+use crate::html::page::not_found::rpc_err_html_response;
 use crate::http_util::rpc_method_err;
 use crate::model::app_state::AppState;
+use crate::model::app_state::AppStateInner;
 use crate::model::block_selector_extended::BlockSelectorExtended;
+use crate::model::block_selector_extended::BlockSelectorResolveError;
+use crate::model::forks::fork_point;
+use crate::model::node_error::NodeError;
 
 #[axum::debug_handler]
 pub async fn block_page(
@@ -27,17 +35,30 @@ pub async fn block_page(
     pub struct BlockInfoHtmlPage<'a> {
         header: HeaderHtml<'a>,
         block_info: BlockInfo,
+        fork: Option<ForkContext>,
     }
     let state = &state_rw.load();
 
     let Path(block_selector) =
         user_input_maybe.map_err(|e| not_found_html_response(state, Some(e.to_string())))?;
 
+    let block_selector = match block_selector.resolve(state).await {
+        Ok(Some(block_selector)) => block_selector,
+        Ok(None) => {
+            return Err(not_found_html_response(
+                state,
+                Some("Block does not exist".to_string()),
+            ))
+        }
+        Err(BlockSelectorResolveError::Node(e)) => return Err(node_err_html_response(state, e)),
+        Err(e) => return Err(not_found_html_response(state, Some(e.to_string()))),
+    };
+
     let block_info = match state
         .rpc_client
-        .block_info(context::current(), state.token(), block_selector.into())
+        .block_info(context::current(), state.token(), block_selector)
         .await
-        .map_err(|e| not_found_html_response(state, Some(e.to_string())))?
+        .map_err(|e| rpc_err_html_response(state, e))?
         .map_err(rpc_method_err)?
     {
         Some(info) => Ok(info),
@@ -47,8 +68,51 @@ pub async fn block_page(
         )),
     }?;
 
+    let fork = match block_info.is_canonical {
+        true => None,
+        false => Some(
+            fork_context(state, &block_info)
+                .await
+                .map_err(|e| node_err_html_response(state, e))?,
+        ),
+    };
+
     let header = HeaderHtml { state };
 
-    let block_info_page = BlockInfoHtmlPage { header, block_info };
-    Ok(Html(block_info_page.to_string()))
+    let block_info_page = BlockInfoHtmlPage {
+        header,
+        block_info,
+        fork,
+    };
+    Ok(Html(state.templates.render(&block_info_page)))
+}
+
+/// Where a non-canonical block branches off the canonical chain.
+struct ForkContext {
+    /// `None` if the branch is too deep to walk.
+    fork_point: Option<BlockInfo>,
+    /// The canonical block at the same height, if the canonical chain is that
+    /// long.
+    canonical_digest: Option<Digest>,
+}
+
+async fn fork_context(
+    state: &AppStateInner,
+    block_info: &BlockInfo,
+) -> Result<ForkContext, NodeError> {
+    let fork_point = fork_point(state, block_info)
+        .await?
+        .map(|(fork_point, _)| fork_point);
+    let canonical_digest = state
+        .rpc_client
+        .block_digest(
+            context::current(),
+            state.token(),
+            BlockSelector::Height(block_info.height),
+        )
+        .await??;
+    Ok(ForkContext {
+        fork_point,
+        canonical_digest,
+    })
 }
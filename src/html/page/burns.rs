@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::Html;
+use boilerplate::Trusted;
+use neptune_cash::api::export::NativeCurrencyAmount;
+
+use crate::html::component::header::HeaderHtml;
+use crate::model::app_state::AppState;
+use crate::shared::exact_coins_string;
+
+/// HTML page listing the known burns on the connected node's network.
+///
+/// Route: `/burns`.
+#[axum::debug_handler]
+pub async fn burns_page(State(state_rw): State<Arc<AppState>>) -> Html<String> {
+    #[derive(boilerplate::Boilerplate)]
+    #[boilerplate(filename = "web/html/page/burns.html")]
+    pub struct BurnsHtmlPage<'a> {
+        header: HeaderHtml<'a>,
+        total_burned: NativeCurrencyAmount,
+    }
+
+    let state = &state_rw.load();
+
+    let total_burned = state.burns().iter().map(|entry| entry.amount).sum();
+    let header = HeaderHtml { state };
+
+    let page = BurnsHtmlPage {
+        header,
+        total_burned,
+    };
+    Html(state.templates.render(&page))
+}
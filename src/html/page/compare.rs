@@ -0,0 +1,195 @@
+use std::sync::Arc;
+
+use axum::extract::rejection::PathRejection;
+use axum::extract::Path;
+use axum::extract::State;
+use axum::response::Html;
+use axum::response::Response;
+use boilerplate::Trusted;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use num_bigint::BigUint;
+use thousands::Separable;
+
+use crate::html::component::header::HeaderHtml;
+use crate::html::page::not_found::node_err_html_response;
+use crate::html::page::not_found::not_found_html_response;
+use crate::model::app_state::AppState;
+use crate::model::app_state::AppStateInner;
+use crate::model::block_selector_extended::BlockSelectorExtended;
+use crate::model::block_selector_extended::BlockSelectorResolveError;
+use crate::model::forks::common_ancestor;
+use crate::model::node_error::fetch_block_info;
+
+/// Upper bound on the parents fetched to find the common ancestor, so that one
+/// page view costs the node a bounded number of `block_info` calls. Siblings
+/// at the same height need two.
+const MAX_ANCESTOR_STEPS: usize = 20;
+
+/// One field of both blocks, as displayed.
+struct ComparedField {
+    name: &'static str,
+    a: String,
+    b: String,
+}
+
+impl ComparedField {
+    fn differs(&self) -> bool {
+        self.a != self.b
+    }
+}
+
+/// HTML page showing two blocks side by side, typically siblings at the same
+/// height, with differing fields highlighted.
+///
+/// Route: `/compare/:selector_a/:selector_b`, where each selector is a single
+/// path segment: `genesis`, `tip`, a height, a digest, or one of these with a
+/// `+N` or `-N` offset.
+#[axum::debug_handler]
+pub async fn compare_page(
+    user_input_maybe: Result<Path<(BlockSelectorExtended, BlockSelectorExtended)>, PathRejection>,
+    State(state_rw): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    #[derive(boilerplate::Boilerplate)]
+    #[boilerplate(filename = "web/html/page/compare.html")]
+    pub struct CompareHtmlPage<'a> {
+        header: HeaderHtml<'a>,
+        a: BlockInfo,
+        b: BlockInfo,
+        fields: Vec<ComparedField>,
+        common_ancestor: Option<BlockInfo>,
+        /// Cumulative proof-of-work of `a` minus that of `b`, signed.
+        proof_of_work_difference: String,
+    }
+    let state = &state_rw.load();
+
+    let Path((selector_a, selector_b)) =
+        user_input_maybe.map_err(|e| not_found_html_response(state, Some(e.to_string())))?;
+
+    let a = resolve_block_info(state, selector_a).await?;
+    let b = resolve_block_info(state, selector_b).await?;
+
+    let common_ancestor = common_ancestor(state, &a, &b, MAX_ANCESTOR_STEPS)
+        .await
+        .map_err(|e| node_err_html_response(state, e))?;
+
+    let header = HeaderHtml { state };
+
+    let page = CompareHtmlPage {
+        header,
+        fields: compared_fields(&a, &b),
+        common_ancestor,
+        proof_of_work_difference: proof_of_work_difference(&a, &b),
+        a,
+        b,
+    };
+    Ok(Html(state.templates.render(&page)))
+}
+
+/// The [`BlockInfo`] of the block `selector` names, or the page to show if
+/// there is none.
+async fn resolve_block_info(
+    state: &AppStateInner,
+    selector: BlockSelectorExtended,
+) -> Result<BlockInfo, Response> {
+    let block_selector = match selector.resolve(state).await {
+        Ok(Some(block_selector)) => block_selector,
+        Ok(None) => {
+            return Err(not_found_html_response(
+                state,
+                Some("Block does not exist".to_string()),
+            ))
+        }
+        Err(BlockSelectorResolveError::Node(e)) => return Err(node_err_html_response(state, e)),
+        Err(e) => return Err(not_found_html_response(state, Some(e.to_string()))),
+    };
+
+    fetch_block_info(state, block_selector)
+        .await
+        .map_err(|e| node_err_html_response(state, e))?
+        .ok_or_else(|| not_found_html_response(state, Some("Block does not exist".to_string())))
+}
+
+fn compared_fields(a: &BlockInfo, b: &BlockInfo) -> Vec<ComparedField> {
+    let field = |name, value: fn(&BlockInfo) -> String| ComparedField {
+        name,
+        a: value(a),
+        b: value(b),
+    };
+    vec![
+        field("Height", |block| block.height.to_string()),
+        field("Digest", |block| block.digest.to_hex()),
+        field("Parent", |block| block.prev_block_digest.to_hex()),
+        field("Created", |block| block.timestamp.standard_format()),
+        field("Size", |block| block.size.separate_with_commas()),
+        field("Inputs", |block| block.num_inputs.separate_with_commas()),
+        field("Outputs", |block| block.num_outputs.separate_with_commas()),
+        field("Announcements", |block| {
+            block.num_announcements.separate_with_commas()
+        }),
+        field("Difficulty", |block| {
+            block.difficulty.separate_with_commas()
+        }),
+        field("Cumulative Proof-Of-Work", |block| {
+            block.cumulative_proof_of_work.separate_with_commas()
+        }),
+        field("Coinbase", |block| block.coinbase_amount.to_string()),
+        field("Fee", |block| block.fee.to_string()),
+        field("Canonical", |block| yes_no(block.is_canonical)),
+        field("Tip", |block| yes_no(block.is_tip)),
+    ]
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "Yes" } else { "No" }.to_string()
+}
+
+fn proof_of_work_difference(a: &BlockInfo, b: &BlockInfo) -> String {
+    let a = BigUint::from(a.cumulative_proof_of_work);
+    let b = BigUint::from(b.cumulative_proof_of_work);
+    if a >= b {
+        format!("+{}", (a - b).separate_with_commas())
+    } else {
+        format!("-{}", (b - a).separate_with_commas())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use neptune_cash::protocol::consensus::block::difficulty_control::Difficulty;
+
+    use super::*;
+    use crate::fake_node::FakeNode;
+
+    fn siblings() -> (BlockInfo, BlockInfo) {
+        let mut blocks = FakeNode::with_tip(1).chain.blocks;
+        let a = blocks.pop().unwrap().info;
+        let mut b = a.clone();
+        b.digest = blocks[0].info.digest;
+        b.num_outputs = 3;
+        b.is_canonical = false;
+        b.is_tip = false;
+        (a, b)
+    }
+
+    #[test]
+    fn only_differing_fields_are_marked() {
+        let (a, b) = siblings();
+        let differing = compared_fields(&a, &b)
+            .into_iter()
+            .filter(ComparedField::differs)
+            .map(|field| field.name)
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Digest", "Outputs", "Canonical", "Tip"], differing);
+        assert!(compared_fields(&a, &a).iter().all(|field| !field.differs()));
+    }
+
+    #[test]
+    fn proof_of_work_difference_is_signed() {
+        let (mut a, mut b) = siblings();
+        a.cumulative_proof_of_work = a.cumulative_proof_of_work + Difficulty::from(1_500_000u32);
+        b.cumulative_proof_of_work = b.cumulative_proof_of_work + Difficulty::from(500_000u32);
+        assert_eq!("+1,000,000", proof_of_work_difference(&a, &b));
+        assert_eq!("-1,000,000", proof_of_work_difference(&b, &a));
+        assert_eq!("+0", proof_of_work_difference(&a, &a));
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::Html;
+use axum::response::Response;
+use boilerplate::Trusted;
+
+use crate::html::component::header::HeaderHtml;
+use crate::html::page::not_found::node_err_html_response;
+use crate::model::app_state::AppState;
+use crate::model::emission::emission_schedule;
+use crate::model::emission::halving_countdown;
+use crate::model::emission::GenerationEmission;
+use crate::model::emission::HalvingCountdown;
+use crate::model::emission::AVERAGE_BLOCK_INTERVAL_WINDOW;
+use crate::shared::exact_coins_string;
+
+/// HTML page with the block subsidy schedule per generation and a countdown to
+/// the next halving.
+///
+/// Route: `/emission`.
+#[axum::debug_handler]
+pub async fn emission_page(
+    State(state_rw): State<Arc<AppState>>,
+) -> Result<Html<String>, Response> {
+    #[derive(boilerplate::Boilerplate)]
+    #[boilerplate(filename = "web/html/page/emission.html")]
+    pub struct EmissionHtmlPage<'a> {
+        header: HeaderHtml<'a>,
+        countdown: HalvingCountdown,
+        generations: Vec<GenerationEmission>,
+    }
+
+    let state = &state_rw.load();
+
+    let countdown = halving_countdown(state)
+        .await
+        .map_err(|e| node_err_html_response(state, e))?;
+
+    let header = HeaderHtml { state };
+
+    let page = EmissionHtmlPage {
+        header,
+        countdown,
+        generations: emission_schedule(state.network, state.burns()),
+    };
+    Ok(Html(state.templates.render(&page)))
+}
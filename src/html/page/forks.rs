@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::Html;
+use boilerplate::Trusted;
+
+use crate::html::component::header::HeaderHtml;
+use crate::model::app_state::AppState;
+use crate::model::forks::Reorg;
+
+/// HTML page listing the reorganizations this explorer observed, most recent
+/// first.
+///
+/// Route: `/forks`.
+#[axum::debug_handler]
+pub async fn forks_page(State(state_rw): State<Arc<AppState>>) -> Html<String> {
+    #[derive(boilerplate::Boilerplate)]
+    #[boilerplate(filename = "web/html/page/forks.html")]
+    pub struct ForksHtmlPage<'a> {
+        header: HeaderHtml<'a>,
+        reorgs: Vec<Reorg>,
+    }
+
+    let state = &state_rw.load();
+
+    let reorgs = state
+        .fork_log
+        .read()
+        .await
+        .reorgs()
+        .rev()
+        .cloned()
+        .collect();
+    let header = HeaderHtml { state };
+
+    let page = ForksHtmlPage { header, reorgs };
+    Html(state.templates.render(&page))
+}
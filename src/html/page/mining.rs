@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::Html;
+use boilerplate::Trusted;
+
+use crate::html::component::header::HeaderHtml;
+use crate::model::app_state::AppState;
+use crate::model::mining_gateway::Submission;
+use crate::model::mining_gateway::SubmissionCounts;
+
+/// HTML page describing the mining gateway, with the number of proof-of-work
+/// submissions made through it, listed per miner with `--mining-show-miners`.
+///
+/// Route: `/mining`.
+#[axum::debug_handler]
+pub async fn mining_page(State(state_rw): State<Arc<AppState>>) -> Html<String> {
+    #[derive(boilerplate::Boilerplate)]
+    #[boilerplate(filename = "web/html/page/mining.html")]
+    pub struct MiningHtmlPage<'a> {
+        header: HeaderHtml<'a>,
+        enabled: bool,
+        accepted: u64,
+        rejected: u64,
+        counts: Vec<SubmissionCounts>,
+        recent: Vec<Submission>,
+    }
+
+    let state = &state_rw.load();
+
+    let ((accepted, rejected), counts, recent) = match &state.mining_gateway {
+        Some(gateway) => {
+            let log = gateway.log.read().await;
+            match state.config.mining_show_miners {
+                true => (
+                    log.totals(),
+                    log.counts(),
+                    log.recent().rev().cloned().collect(),
+                ),
+                false => (log.totals(), vec![], vec![]),
+            }
+        }
+        None => ((0, 0), vec![], vec![]),
+    };
+    let header = HeaderHtml { state };
+
+    let page = MiningHtmlPage {
+        header,
+        enabled: state.mining_gateway.is_some(),
+        accepted,
+        rejected,
+        counts,
+        recent,
+    };
+    Html(state.templates.render(&page))
+}
+
+/// The first and last characters of a long address.
+fn abbreviated(address: &str) -> String {
+    if address.len() <= 32 {
+        return address.to_string();
+    }
+    format!("{}…{}", &address[..20], &address[address.len() - 8..])
+}
@@ -1,7 +1,16 @@
+pub mod address;
 pub mod announcement;
+pub mod announcement_search;
 pub mod block;
+pub mod burns;
+pub mod compare;
+pub mod emission;
+pub mod forks;
+pub mod mining;
 pub mod not_found;
 pub mod redirect_qs_to_path;
 pub mod root;
+pub mod stats;
+pub mod supply;
 pub mod tx_output;
 pub mod utxo;
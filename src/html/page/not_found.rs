@@ -1,30 +1,65 @@
+use std::sync::Arc;
+
+use axum::extract::State;
 use axum::http::StatusCode;
 use axum::response::Html;
+use axum::response::IntoResponse;
 use axum::response::Response;
+use tarpc::client::RpcError as TransportError;
 
 use crate::http_util::not_found_html_err;
 use crate::http_util::not_found_html_handler;
+use crate::http_util::rpc_html_err;
+use crate::http_util::rpc_method_err;
+use crate::model::app_state::AppState;
 use crate::model::app_state::AppStateInner;
+use crate::model::node_error::NodeError;
 
-pub fn not_found_page(error_msg: Option<String>) -> Html<String> {
+pub fn not_found_page(state: &AppStateInner, error_msg: Option<String>) -> Html<String> {
     #[derive(boilerplate::Boilerplate)]
     #[boilerplate(filename = "web/html/page/not_found.html")]
     #[allow(dead_code)]
-    pub struct NotFoundHtmlPage {
+    pub struct NotFoundHtmlPage<'a> {
+        state: &'a AppStateInner,
         error_msg: String,
     }
 
     let not_found_page = NotFoundHtmlPage {
+        state,
         error_msg: error_msg.unwrap_or_default(),
     };
-    Html(not_found_page.to_string())
+    Html(state.templates.render(&not_found_page))
+}
+
+pub fn not_found_html_response(state: &AppStateInner, error_msg: Option<String>) -> Response {
+    not_found_html_err(not_found_page(state, error_msg))
 }
 
-pub fn not_found_html_response(_state: &AppStateInner, error_msg: Option<String>) -> Response {
-    not_found_html_err(not_found_page(error_msg))
+/// The page for a failed call into neptune-core, with the status codes of
+/// [`rpc_err`](crate::http_util::rpc_err): 503 with `Retry-After` if the call
+/// was shed by admission control, 504 if it missed its deadline, 500
+/// otherwise.
+pub fn rpc_err_html_response(state: &AppStateInner, e: TransportError) -> Response {
+    rpc_html_err(&e, not_found_page(state, Some(e.to_string())))
+}
+
+/// Like [`node_err`](crate::http_util::node_err), with a styled HTML body for
+/// transport failures.
+pub fn node_err_html_response(state: &AppStateInner, e: NodeError) -> Response {
+    match e {
+        NodeError::Transport(e) => rpc_err_html_response(state, e),
+        NodeError::Method(e) => rpc_method_err(e),
+        e @ NodeError::MissingBlock(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            not_found_page(state, Some(e.to_string())),
+        )
+            .into_response(),
+    }
 }
 
 #[axum::debug_handler]
-pub async fn not_found_html_fallback() -> (StatusCode, Html<String>) {
-    not_found_html_handler(not_found_page(None))
+pub async fn not_found_html_fallback(
+    State(state_rw): State<Arc<AppState>>,
+) -> (StatusCode, Html<String>) {
+    not_found_html_handler(not_found_page(&state_rw.load(), None))
 }
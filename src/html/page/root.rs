@@ -7,7 +7,7 @@ use neptune_cash::api::export::BlockHeight;
 use tarpc::context;
 use thousands::Separable;
 
-use crate::html::page::not_found::not_found_html_response;
+use crate::html::page::not_found::rpc_err_html_response;
 use crate::http_util::rpc_method_err;
 use crate::model::app_state::AppState;
 use crate::model::app_state::AppStateInner;
@@ -27,9 +27,9 @@ pub async fn root(State(state_rw): State<Arc<AppState>>) -> Result<Html<String>,
         .rpc_client
         .block_height(context::current(), state.token())
         .await
-        .map_err(|e| not_found_html_response(state, Some(e.to_string())))?
+        .map_err(|e| rpc_err_html_response(state, e))?
         .map_err(rpc_method_err)?;
 
     let root_page = RootHtmlPage { tip_height, state };
-    Ok(Html(root_page.to_string()))
+    Ok(Html(state.templates.render(&root_page)))
 }
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use axum::extract::Query;
+use axum::extract::State;
+use axum::response::Html;
+use boilerplate::Trusted;
+
+use crate::html::component::header::HeaderHtml;
+use crate::html::component::line_chart::format_date;
+use crate::html::component::line_chart::format_date_hour;
+use crate::html::component::line_chart::format_si;
+use crate::html::component::line_chart::format_whole;
+use crate::html::component::line_chart::ChartSeries;
+use crate::html::component::line_chart::LineChartHtml;
+use crate::model::app_state::AppState;
+use crate::model::chain_stats::aggregate;
+use crate::model::chain_stats::StatsBucket;
+use crate::model::chain_stats::StatsQuery;
+use crate::model::chain_stats::StatsResolution;
+use crate::shared::coins_f64;
+
+/// HTML page with charts of block interval, difficulty, hashrate, block size,
+/// fees and activity over time.
+///
+/// Route: `/stats?resolution=hour|day|week&from=..&to=..`. Served from the
+/// local statistics index, like `/rpc/stats`.
+#[axum::debug_handler]
+pub async fn stats_page(
+    Query(query): Query<StatsQuery>,
+    State(state_rw): State<Arc<AppState>>,
+) -> Html<String> {
+    #[derive(boilerplate::Boilerplate)]
+    #[boilerplate(filename = "web/html/page/stats.html")]
+    pub struct StatsHtmlPage<'a> {
+        header: HeaderHtml<'a>,
+        query: StatsQuery,
+        indexed_height: Option<u64>,
+        charts: Vec<LineChartHtml>,
+        error: Option<String>,
+    }
+
+    let state = &state_rw.load();
+    let index = state.stats_index.read().await;
+
+    let latest_unix_seconds = index.last().map_or(0, |b| b.timestamp_ms / 1000);
+    let (charts, error) = match query.range(latest_unix_seconds) {
+        Ok((from, to)) => {
+            let buckets = aggregate(index.blocks(), query.resolution, from, to);
+            (charts(&buckets, query.resolution), None)
+        }
+        Err(e) => (vec![], Some(e.to_string())),
+    };
+
+    let header = HeaderHtml { state };
+
+    let page = StatsHtmlPage {
+        header,
+        query,
+        indexed_height: index.last().map(|b| b.height),
+        charts,
+        error,
+    };
+    Html(state.templates.render(&page))
+}
+
+fn charts(buckets: &[StatsBucket], resolution: StatsResolution) -> Vec<LineChartHtml> {
+    let format_x = match resolution {
+        StatsResolution::Hour => format_date_hour,
+        StatsResolution::Day | StatsResolution::Week => format_date,
+    };
+    let series = |name: &str, color, value: fn(&StatsBucket) -> f64| ChartSeries {
+        name: name.to_string(),
+        color,
+        points: buckets
+            .iter()
+            .map(|bucket| (bucket.start as f64, value(bucket)))
+            .collect(),
+    };
+    let chart = |title: &str, series: Vec<ChartSeries>, format_y| LineChartHtml {
+        title: format!("{title} per {resolution}"),
+        x_label: "time (UTC)".to_string(),
+        series,
+        markers: vec![],
+        format_x,
+        format_y,
+    };
+
+    vec![
+        chart(
+            "Average block interval in seconds",
+            vec![series("interval", "rgb(0, 115, 115)", |b| {
+                b.average_block_interval_secs
+            })],
+            format_whole,
+        ),
+        chart(
+            "Average difficulty",
+            vec![series("difficulty", "rgb(0, 115, 115)", |b| {
+                b.average_difficulty
+            })],
+            format_si,
+        ),
+        chart(
+            "Estimated hashrate in hashes per second",
+            vec![series("hashrate", "rgb(0, 115, 115)", |b| b.hashrate)],
+            format_si,
+        ),
+        chart(
+            "Block size in field elements",
+            vec![series("total size", "rgb(0, 115, 115)", |b| {
+                b.total_size as f64
+            })],
+            format_si,
+        ),
+        chart(
+            "Fees in coins",
+            vec![series("total fees", "orange", |b| coins_f64(b.total_fees))],
+            format_whole,
+        ),
+        chart(
+            "Activity",
+            vec![
+                series("inputs", "rgb(0, 115, 115)", |b| b.num_inputs as f64),
+                series("outputs", "orange", |b| b.num_outputs as f64),
+                series("announcements", "gray", |b| b.num_announcements as f64),
+            ],
+            format_whole,
+        ),
+    ]
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::Html;
+use axum::response::Response;
+use boilerplate::Trusted;
+use num_traits::Zero;
+use tarpc::context;
+
+use crate::html::component::header::HeaderHtml;
+use crate::html::component::line_chart::format_whole;
+use crate::html::component::line_chart::ChartMarker;
+use crate::html::component::line_chart::ChartSeries;
+use crate::html::component::line_chart::LineChartHtml;
+use crate::html::page::not_found::rpc_err_html_response;
+use crate::http_util::rpc_method_err;
+use crate::model::app_state::AppState;
+use crate::shared::coins_f64;
+use crate::shared::exact_coins_string;
+use crate::shared::generation_of;
+use crate::shared::generation_start_height;
+use crate::shared::SupplyAccuracy;
+use crate::shared::SupplyBreakdown;
+
+/// Number of heights at which the supply is evaluated for the chart.
+const NUM_CHART_SAMPLES: u64 = 200;
+
+/// HTML page with the supply breakdown at the tip and a chart of the liquid
+/// and total supply over the whole chain.
+///
+/// Route: `/supply`. The chart is computed from the emission schedule alone,
+/// so it costs a single `block_height` call.
+#[axum::debug_handler]
+pub async fn supply_page(State(state_rw): State<Arc<AppState>>) -> Result<Html<String>, Response> {
+    #[derive(boilerplate::Boilerplate)]
+    #[boilerplate(filename = "web/html/page/supply.html")]
+    pub struct SupplyHtmlPage<'a> {
+        header: HeaderHtml<'a>,
+        breakdown: SupplyBreakdown,
+        chart: LineChartHtml,
+    }
+
+    let state = &state_rw.load();
+
+    let tip_height = state
+        .rpc_client
+        .block_height(context::current(), state.token())
+        .await
+        .map_err(|e| rpc_err_html_response(state, e))?
+        .map_err(rpc_method_err)?;
+    let tip_height = u64::from(tip_height);
+
+    let samples = (0..=NUM_CHART_SAMPLES)
+        .map(|i| tip_height * i / NUM_CHART_SAMPLES)
+        .map(|height| state.supply_breakdown(height.into()))
+        .collect::<Vec<_>>();
+    let series = |name: &str, color, amount: fn(&SupplyBreakdown) -> f64| ChartSeries {
+        name: name.to_string(),
+        color,
+        points: samples
+            .iter()
+            .map(|breakdown| (u64::from(breakdown.block_height) as f64, amount(breakdown)))
+            .collect(),
+    };
+    let markers = (1..=generation_of(tip_height.into()))
+        .map(|generation| ChartMarker {
+            x: u64::from(generation_start_height(generation)) as f64,
+            label: format!("generation {generation}"),
+        })
+        .collect();
+
+    let chart = LineChartHtml {
+        title: "Supply in coins".to_string(),
+        x_label: "block height".to_string(),
+        series: vec![
+            series("total", "rgb(0, 115, 115)", |b| coins_f64(b.total())),
+            series("liquid", "orange", |b| coins_f64(b.liquid())),
+        ],
+        markers,
+        format_x: format_whole,
+        format_y: format_whole,
+    };
+
+    let header = HeaderHtml { state };
+
+    let page = SupplyHtmlPage {
+        header,
+        breakdown: state.supply_breakdown(tip_height.into()),
+        chart,
+    };
+    Ok(Html(state.templates.render(&page)))
+}
@@ -11,6 +11,7 @@ use thousands::Separable;
 use crate::html::component::header::HeaderHtml;
 use crate::html::page::not_found::not_found_html_response;
 use crate::html::page::not_found::not_found_page;
+use crate::html::page::not_found::rpc_err_html_response;
 use crate::http_util::rpc_method_err;
 use crate::http_util::service_unavailable_html;
 use crate::model::app_state::AppState;
@@ -50,8 +51,12 @@ pub async fn tx_output_page(
     // here for a clean early response, and the same condition is also enforced
     // inside `resolve_output_status` (`IndexUnavailable`) so the guard can't be
     // bypassed; see `AuthenticatedClient::maintains_utxo_index`.
-    let index_unavailable =
-        || service_unavailable_html(not_found_page(Some(INDEX_REQUIRED_MESSAGE.to_string())));
+    let index_unavailable = || {
+        service_unavailable_html(not_found_page(
+            state,
+            Some(INDEX_REQUIRED_MESSAGE.to_string()),
+        ))
+    };
     if !state.maintains_utxo_index {
         return Err(index_unavailable());
     }
@@ -65,7 +70,7 @@ pub async fn tx_output_page(
     let resolved = resolve_output_status(state, addition_record_hex.addition_record())
         .await
         .map_err(|e| match e {
-            OutputStatusError::Transport(t) => not_found_html_response(state, Some(t.to_string())),
+            OutputStatusError::Transport(t) => rpc_err_html_response(state, t),
             OutputStatusError::Method(m) => rpc_method_err(m),
             OutputStatusError::IndexUnavailable => index_unavailable(),
         })?;
@@ -92,5 +97,5 @@ pub async fn tx_output_page(
         mined_block_digest_hex,
         mined_height,
     };
-    Ok(Html(page.to_string()))
+    Ok(Html(state.templates.render(&page)))
 }
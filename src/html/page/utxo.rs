@@ -12,6 +12,7 @@ use tarpc::context;
 
 use crate::html::component::header::HeaderHtml;
 use crate::html::page::not_found::not_found_html_response;
+use crate::html::page::not_found::rpc_err_html_response;
 use crate::http_util::rpc_method_err;
 use crate::model::app_state::AppState;
 use crate::model::transparent_utxo_tuple::TransparentUtxoTuple;
@@ -31,16 +32,15 @@ pub async fn utxo_page(
     }
 
     let state = &state_rw.load();
-    let cache = state.transparent_utxos_cache.clone();
 
     let Path(index) =
         index_maybe.map_err(|e| not_found_html_response(state, Some(e.to_string())))?;
 
     let digest = match state
         .rpc_client
-        .utxo_digest(context::current(), state.token(), index, cache)
+        .utxo_digest(context::current(), state.token(), index)
         .await
-        .map_err(|e| not_found_html_response(state, Some(e.to_string())))?
+        .map_err(|e| rpc_err_html_response(state, e))?
         .map_err(rpc_method_err)?
     {
         Some(digest) => digest,
@@ -68,5 +68,5 @@ pub async fn utxo_page(
         digest,
         transparent_utxo_info,
     };
-    Ok(Html(utxo_page.to_string()))
+    Ok(Html(state.templates.render(&utxo_page)))
 }
@@ -1,3 +1,5 @@
+use axum::http::header;
+use axum::http::HeaderValue;
 use axum::http::StatusCode;
 use axum::response::Html;
 use axum::response::IntoResponse;
@@ -5,6 +7,13 @@ use axum::response::Response;
 use neptune_cash::application::rpc::server::error::RpcError;
 use tarpc::client::RpcError as TarpcError;
 
+use crate::model::block_selector_extended::BlockSelectorResolveError;
+use crate::model::mining_gateway::AuthorizeError;
+use crate::model::node_error::NodeError;
+use crate::model::rate_limit::Rejection;
+use crate::model::rpc_limits::Overloaded;
+use crate::model::rpc_limits::OVERLOADED_RETRY_AFTER;
+
 // note: http StatusCodes are defined at:
 // https://docs.rs/http/1.1.0/http/status/struct.StatusCode.html
 
@@ -20,8 +29,41 @@ pub fn not_found_html_handler(html: Html<String>) -> (StatusCode, Html<String>)
     (StatusCode::NOT_FOUND, html)
 }
 
+/// 401 with a plain-text message, for requests without valid credentials.
+pub fn unauthorized_err(message: &str) -> Response {
+    (StatusCode::UNAUTHORIZED, message.to_string()).into_response()
+}
+
+/// 503 with `Retry-After` if the call was shed by admission control, 504 if it
+/// missed its deadline, 500 otherwise.
 pub fn rpc_err(e: TarpcError) -> Response {
-    (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:?}")).into_response()
+    let message = match Overloaded::cause_of(&e) {
+        Some(overloaded) => overloaded.to_string(),
+        None => format!("{e:?}"),
+    };
+    rpc_err_with_body(&e, message)
+}
+
+/// Like [`rpc_err`], with a styled HTML body.
+pub fn rpc_html_err(e: &TarpcError, html: Html<String>) -> Response {
+    rpc_err_with_body(e, html)
+}
+
+fn rpc_err_with_body(e: &TarpcError, body: impl IntoResponse) -> Response {
+    let overloaded = Overloaded::cause_of(e).is_some();
+    let status_code = match e {
+        _ if overloaded => StatusCode::SERVICE_UNAVAILABLE,
+        TarpcError::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    let mut response = (status_code, body).into_response();
+    if overloaded {
+        response.headers_mut().insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(OVERLOADED_RETRY_AFTER.as_secs()),
+        );
+    }
+    response
 }
 
 /// 503 with a styled HTML body, for a feature that is disabled because of the
@@ -42,3 +84,54 @@ pub fn rpc_method_err(e: RpcError) -> Response {
     };
     (status_code, format!("{e:?}")).into_response()
 }
+
+/// Like [`rpc_err`] or [`rpc_method_err`], depending on which part of a call
+/// into neptune-core failed, or 500 if the node lacks a block it must have.
+pub fn node_err(e: NodeError) -> Response {
+    match e {
+        NodeError::Transport(e) => rpc_err(e),
+        NodeError::Method(e) => rpc_method_err(e),
+        e @ NodeError::MissingBlock(_) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+/// Like [`node_err`] if neptune-core could not resolve a
+/// block selector, or 404 with the reason if the selector is out of range.
+pub fn block_selector_err(e: BlockSelectorResolveError) -> Response {
+    match e {
+        BlockSelectorResolveError::Node(e) => node_err(e),
+        e @ BlockSelectorResolveError::BelowGenesis { .. } => {
+            (StatusCode::NOT_FOUND, e.to_string()).into_response()
+        }
+    }
+}
+
+/// 503 if the mining gateway is disabled, 401 if the API key is unknown.
+pub fn mining_gateway_err(e: AuthorizeError) -> Response {
+    match e {
+        AuthorizeError::Disabled => service_unavailable_err(&e.to_string()),
+        AuthorizeError::UnknownKey => unauthorized_err(&e.to_string()),
+        AuthorizeError::Limited { retry_after } => {
+            Rejection::Limited { retry_after }.into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::rpc_limits::RpcClass;
+
+    #[test]
+    fn shed_calls_are_answered_with_retry_after() {
+        let shed = TarpcError::Send(Box::new(Overloaded(RpcClass::Block)));
+        let response = rpc_err(shed);
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, response.status());
+        assert_eq!("1", response.headers()[header::RETRY_AFTER]);
+
+        let response = rpc_err(TarpcError::DeadlineExceeded);
+        assert_eq!(StatusCode::GATEWAY_TIMEOUT, response.status());
+    }
+}
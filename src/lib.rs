@@ -1,4 +1,6 @@
 pub mod alert_email;
+#[cfg(test)]
+pub mod fake_node;
 pub mod html;
 pub mod http_util;
 pub mod model;
@@ -6,5 +8,6 @@ pub mod neptune_rpc;
 
 #[cfg(feature = "attacks")]
 pub mod path;
+pub mod routes;
 pub mod rpc;
 pub mod shared;
@@ -0,0 +1,153 @@
+//! Decoding generation addresses for display and validation.
+
+use neptune_cash::api::export::Network;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+use neptune_cash::state::wallet::address::generation_address::GenerationReceivingAddress;
+use neptune_cash::state::wallet::address::generation_address::GENERATION_FLAG;
+use qrcode::render::svg;
+use qrcode::EcLevel;
+use qrcode::QrCode;
+use serde::Serialize;
+
+/// Networks with distinct address prefixes; all testnets share one.
+const ADDRESS_NETWORKS: [Network; 4] = [
+    Network::Main,
+    Network::Testnet(0),
+    Network::TestnetMock,
+    Network::RegTest,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AddressError {
+    #[error("this is a {address_network} address, but this explorer follows {network}")]
+    WrongNetwork {
+        address_network: String,
+        network: Network,
+    },
+    #[error("not a valid generation address: {0}")]
+    Invalid(String),
+}
+
+/// The public parts of a generation address.
+#[derive(Debug, Clone, Serialize)]
+pub struct AddressDetails {
+    /// The address, re-encoded in canonical (lowercase) form.
+    pub address: String,
+    pub network: Network,
+
+    /// Public fingerprint, in plain text in every announcement sent to the
+    /// address.
+    pub receiver_identifier: BFieldElement,
+    /// The flag of announcements sent to generation addresses.
+    pub announcement_flag: BFieldElement,
+    pub receiver_postimage: Digest,
+    pub spending_lock: Digest,
+}
+
+/// Decode `bech32m` as a generation address on `network`.
+///
+/// Addresses of another network are rejected with
+/// [`AddressError::WrongNetwork`], naming the network they belong to.
+pub fn decode_generation_address(
+    bech32m: &str,
+    network: Network,
+) -> Result<AddressDetails, AddressError> {
+    let address = match GenerationReceivingAddress::from_bech32m(bech32m, network) {
+        Ok(address) => address,
+        Err(e) => {
+            let address_network = ADDRESS_NETWORKS
+                .into_iter()
+                .find(|other| GenerationReceivingAddress::from_bech32m(bech32m, *other).is_ok());
+            return Err(match address_network {
+                Some(Network::Testnet(_)) => AddressError::WrongNetwork {
+                    address_network: "testnet".to_string(),
+                    network,
+                },
+                Some(address_network) => AddressError::WrongNetwork {
+                    address_network: address_network.to_string(),
+                    network,
+                },
+                None => AddressError::Invalid(e.to_string()),
+            });
+        }
+    };
+
+    Ok(AddressDetails {
+        address: address
+            .to_bech32m(network)
+            .map_err(|e| AddressError::Invalid(e.to_string()))?,
+        network,
+        receiver_identifier: address.receiver_identifier(),
+        announcement_flag: GENERATION_FLAG,
+        receiver_postimage: address.receiver_postimage(),
+        spending_lock: address.spending_lock(),
+    })
+}
+
+/// An SVG QR code of `address`, or `None` if it does not fit in one.
+///
+/// Encoded in upper case: bech32m is case-insensitive, and QR codes store
+/// upper-case alphanumerics more compactly than bytes. Generation addresses
+/// are some 3500 characters long, which only fits at the lowest error
+/// correction level.
+pub fn address_qr_svg(address: &str) -> Option<String> {
+    let code = QrCode::with_error_correction_level(address.to_uppercase(), EcLevel::L).ok()?;
+    Some(
+        code.render::<svg::Color>()
+            .min_dimensions(320, 320)
+            .quiet_zone(true)
+            .build(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(network: Network) -> String {
+        GenerationReceivingAddress::derive_from_seed(Digest::default())
+            .to_bech32m(network)
+            .unwrap()
+    }
+
+    #[test]
+    fn decodes_addresses_of_the_followed_network() {
+        let details = decode_generation_address(&address(Network::Main), Network::Main).unwrap();
+        assert_eq!(address(Network::Main), details.address);
+        assert_eq!(
+            GenerationReceivingAddress::derive_from_seed(Digest::default()).receiver_identifier(),
+            details.receiver_identifier
+        );
+
+        // bech32m is case-insensitive
+        let upper = address(Network::Main).to_uppercase();
+        assert!(decode_generation_address(&upper, Network::Main).is_ok());
+    }
+
+    #[test]
+    fn names_the_network_of_foreign_addresses() {
+        assert_eq!(
+            Err(AddressError::WrongNetwork {
+                address_network: "testnet".to_string(),
+                network: Network::Main,
+            }),
+            decode_generation_address(&address(Network::Testnet(0)), Network::Main)
+                .map(|details| details.address)
+        );
+        assert!(matches!(
+            decode_generation_address(&address(Network::Main), Network::RegTest),
+            Err(AddressError::WrongNetwork { address_network, .. }) if address_network == "main"
+        ));
+        assert!(matches!(
+            decode_generation_address("nolgam1qqqq", Network::Main),
+            Err(AddressError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn renders_qr_codes_of_addresses() {
+        let svg = address_qr_svg(&address(Network::Main)).unwrap();
+        assert!(svg.contains("<svg"));
+    }
+}
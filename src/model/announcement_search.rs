@@ -0,0 +1,359 @@
+//! Searching the chain for announcements by flag.
+//!
+//! neptune-core's UTXO index maps every [`AnnouncementFlag`] -- the pair of the
+//! first two field elements of an announcement, its flag and receiver
+//! identifier -- to the heights of the blocks containing such announcements.
+//! This module exposes that lookup to users, shared by the HTML page and the
+//! JSON endpoint:
+//!
+//!   * [`AnnouncementSearchQuery`], the query string of both routes, and
+//!   * [`search_announcements`], which resolves the query against the node,
+//!     paginates the block heights, and optionally finds the matching
+//!     announcement indices within each block on the requested page.
+//!
+//! The index is keyed by the *pair*, so a receiver identifier is required;
+//! searching by flag alone would need a full-chain scan and is not offered.
+
+use neptune_cash::api::export::BlockHeight;
+use neptune_cash::application::rpc::server::error::RpcError;
+use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use neptune_cash::state::wallet::address::announcement_flag::AnnouncementFlag;
+use serde::Deserialize;
+use serde::Serialize;
+use tarpc::client::RpcError as TransportError;
+use tarpc::context;
+
+use crate::model::announcement_type::AnnouncementDecoderRegistry;
+use crate::model::app_state::AppStateInner;
+
+/// Number of blocks per page unless the query asks for another page size.
+pub const DEFAULT_PAGE_SIZE: usize = 25;
+
+/// Upper bound on the page size.
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// Upper bound on the page size, and its default, if the query asks for
+/// announcement indices. Each block on the page then costs one
+/// `announcements_in_block` call, so this bounds the calls a single request
+/// can cause.
+pub const MAX_PAGE_SIZE_WITH_INDICES: usize = 10;
+
+/// User-facing reason the search is unavailable without a UTXO index.
+pub const INDEX_REQUIRED_MESSAGE: &str =
+    "Announcement search requires the connected neptune-core node to run with --utxo-index.";
+
+/// Query string of `/announcements/search` and `/rpc/announcements/search`.
+///
+/// `flag` is either a decimal field element or the name of a registered
+/// announcement type that has a flag, e.g. `generation UTXO notification`.
+/// `receiver_id` is a decimal or `0x`-prefixed hexadecimal field element.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AnnouncementSearchQuery {
+    pub flag: Option<String>,
+    pub receiver_id: Option<String>,
+
+    /// Also report the indices of the matching announcements within each block.
+    #[serde(default)]
+    pub indices: bool,
+
+    /// Zero-based page number.
+    #[serde(default)]
+    pub page: usize,
+    pub page_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum AnnouncementSearchQueryError {
+    #[error("missing flag")]
+    MissingFlag,
+    #[error("flag {0} is neither a field element nor a known announcement type")]
+    UnknownFlag(String),
+    #[error("announcement type {0} has no flag and cannot be searched for")]
+    FlaglessType(String),
+    #[error("missing receiver identifier: the node indexes announcements by flag and receiver identifier together")]
+    MissingReceiverId,
+    #[error("invalid receiver identifier: {0}")]
+    InvalidReceiverId(String),
+    #[error("page size must be between 1 and {0}")]
+    PageSize(usize),
+}
+
+impl AnnouncementSearchQuery {
+    /// The [`AnnouncementFlag`] to look up, with type names resolved through
+    /// the given registry.
+    pub fn announcement_flag(
+        &self,
+        decoders: &AnnouncementDecoderRegistry,
+    ) -> Result<AnnouncementFlag, AnnouncementSearchQueryError> {
+        let flag = self
+            .flag
+            .as_deref()
+            .map(str::trim)
+            .filter(|flag| !flag.is_empty())
+            .ok_or(AnnouncementSearchQueryError::MissingFlag)?;
+        let flag = match flag.parse::<u64>() {
+            Ok(value) => BFieldElement::new(value),
+            Err(_) => decoders
+                .by_name(flag)
+                .ok_or_else(|| AnnouncementSearchQueryError::UnknownFlag(flag.to_string()))?
+                .flag()
+                .ok_or_else(|| AnnouncementSearchQueryError::FlaglessType(flag.to_string()))?,
+        };
+
+        let receiver_id = self
+            .receiver_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|receiver_id| !receiver_id.is_empty())
+            .ok_or(AnnouncementSearchQueryError::MissingReceiverId)?;
+        let receiver_id = match receiver_id.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => receiver_id.parse::<u64>(),
+        }
+        .map(BFieldElement::new)
+        .map_err(|_| AnnouncementSearchQueryError::InvalidReceiverId(receiver_id.to_string()))?;
+
+        Ok(AnnouncementFlag { flag, receiver_id })
+    }
+
+    pub fn page_size(&self) -> Result<usize, AnnouncementSearchQueryError> {
+        let (default, max) = match self.indices {
+            true => (MAX_PAGE_SIZE_WITH_INDICES, MAX_PAGE_SIZE_WITH_INDICES),
+            false => (DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE),
+        };
+        match self.page_size.unwrap_or(default) {
+            n if n == 0 || n > max => Err(AnnouncementSearchQueryError::PageSize(max)),
+            n => Ok(n),
+        }
+    }
+
+    /// This query's string for another page, for pagination links.
+    pub fn query_string_for_page(&self, page: usize) -> String {
+        let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(flag) = &self.flag {
+            serializer.append_pair("flag", flag);
+        }
+        if let Some(receiver_id) = &self.receiver_id {
+            serializer.append_pair("receiver_id", receiver_id);
+        }
+        if self.indices {
+            serializer.append_pair("indices", "true");
+        }
+        if let Some(page_size) = self.page_size {
+            serializer.append_pair("page_size", &page_size.to_string());
+        }
+        serializer.append_pair("page", &page.to_string());
+        serializer.finish()
+    }
+}
+
+/// One page of search results.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnouncementSearchResults {
+    pub flag: u64,
+    pub receiver_id: u64,
+
+    /// Total number of matching blocks, over all pages.
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub num_pages: usize,
+
+    /// Matching blocks on this page, in ascending height order.
+    pub hits: Vec<AnnouncementSearchHit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnouncementSearchHit {
+    pub block_height: BlockHeight,
+
+    /// Indices of the matching announcements within the canonical block at
+    /// `block_height`. Only present if the query asked for indices.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub announcement_indices: Option<Vec<usize>>,
+}
+
+/// Error while searching, mirroring
+/// [`OutputStatusError`](crate::model::output_status::OutputStatusError) so
+/// callers can map each case to the right HTTP status.
+#[derive(Debug)]
+pub enum AnnouncementSearchError {
+    /// The query string is invalid.
+    Query(AnnouncementSearchQueryError),
+    /// Could not reach / talk to neptune-core (tarpc transport).
+    Transport(TransportError),
+    /// neptune-core rejected or failed the RPC call.
+    Method(RpcError),
+    /// The connected node does not maintain a UTXO index.
+    IndexUnavailable,
+}
+
+/// Look up the blocks containing announcements with the queried flag and
+/// receiver identifier, and return the requested page.
+///
+/// The node caps the number of blocks it stores per [`AnnouncementFlag`], so
+/// for very frequently used receiver identifiers the result can be incomplete.
+pub async fn search_announcements(
+    state: &AppStateInner,
+    query: &AnnouncementSearchQuery,
+) -> Result<AnnouncementSearchResults, AnnouncementSearchError> {
+    if !state.maintains_utxo_index {
+        return Err(AnnouncementSearchError::IndexUnavailable);
+    }
+
+    let announcement_flag = query
+        .announcement_flag(&state.announcement_decoders)
+        .map_err(AnnouncementSearchError::Query)?;
+    let page_size = query.page_size().map_err(AnnouncementSearchError::Query)?;
+
+    let mut block_heights = state
+        .rpc_client
+        .block_heights_by_announcement_flags(
+            context::current(),
+            state.token(),
+            vec![announcement_flag],
+        )
+        .await
+        .map_err(AnnouncementSearchError::Transport)?
+        .map_err(|e| match e {
+            RpcError::UtxoIndexNotPresent => AnnouncementSearchError::IndexUnavailable,
+            e => AnnouncementSearchError::Method(e),
+        })?;
+    block_heights.sort_unstable();
+    block_heights.dedup();
+
+    let total = block_heights.len();
+    let mut hits = vec![];
+    for block_height in block_heights
+        .into_iter()
+        .skip(query.page.saturating_mul(page_size))
+        .take(page_size)
+    {
+        let announcement_indices = match query.indices {
+            true => {
+                Some(matching_announcement_indices(state, block_height, announcement_flag).await?)
+            }
+            false => None,
+        };
+        hits.push(AnnouncementSearchHit {
+            block_height,
+            announcement_indices,
+        });
+    }
+
+    Ok(AnnouncementSearchResults {
+        flag: announcement_flag.flag.value(),
+        receiver_id: announcement_flag.receiver_id.value(),
+        total,
+        page: query.page,
+        page_size,
+        num_pages: total.div_ceil(page_size),
+        hits,
+    })
+}
+
+async fn matching_announcement_indices(
+    state: &AppStateInner,
+    block_height: BlockHeight,
+    announcement_flag: AnnouncementFlag,
+) -> Result<Vec<usize>, AnnouncementSearchError> {
+    let announcements = state
+        .rpc_client
+        .announcements_in_block(
+            context::current(),
+            state.token(),
+            BlockSelector::Height(block_height),
+        )
+        .await
+        .map_err(AnnouncementSearchError::Transport)?
+        .map_err(AnnouncementSearchError::Method)?
+        .unwrap_or_default();
+
+    let prefix = [announcement_flag.flag, announcement_flag.receiver_id];
+    Ok(announcements
+        .iter()
+        .enumerate()
+        .filter(|(_, announcement)| announcement.message.starts_with(&prefix))
+        .map(|(index, _)| index)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use neptune_cash::state::wallet::address::generation_address::GENERATION_FLAG;
+
+    use super::*;
+
+    fn query(flag: &str, receiver_id: &str) -> AnnouncementSearchQuery {
+        AnnouncementSearchQuery {
+            flag: Some(flag.to_string()),
+            receiver_id: Some(receiver_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flag_parses_as_decimal_field_element() {
+        let decoders = AnnouncementDecoderRegistry::builtin();
+        let announcement_flag = query("79", "1337").announcement_flag(&decoders).unwrap();
+        assert_eq!(BFieldElement::new(79), announcement_flag.flag);
+        assert_eq!(BFieldElement::new(1337), announcement_flag.receiver_id);
+    }
+
+    #[test]
+    fn flag_resolves_known_type_name() {
+        let decoders = AnnouncementDecoderRegistry::builtin();
+        let announcement_flag = query("generation UTXO notification", "0xff")
+            .announcement_flag(&decoders)
+            .unwrap();
+        assert_eq!(GENERATION_FLAG, announcement_flag.flag);
+        assert_eq!(BFieldElement::new(255), announcement_flag.receiver_id);
+    }
+
+    #[test]
+    fn flagless_and_unknown_types_are_rejected() {
+        let decoders = AnnouncementDecoderRegistry::builtin();
+        assert!(matches!(
+            query("transparent transaction info", "1").announcement_flag(&decoders),
+            Err(AnnouncementSearchQueryError::FlaglessType(_))
+        ));
+        assert!(matches!(
+            query("no such type", "1").announcement_flag(&decoders),
+            Err(AnnouncementSearchQueryError::UnknownFlag(_))
+        ));
+        assert!(matches!(
+            query("79", "").announcement_flag(&decoders),
+            Err(AnnouncementSearchQueryError::MissingReceiverId)
+        ));
+    }
+
+    #[test]
+    fn page_size_is_bounded() {
+        let mut q = query("79", "1");
+        assert_eq!(DEFAULT_PAGE_SIZE, q.page_size().unwrap());
+        q.page_size = Some(0);
+        assert!(q.page_size().is_err());
+        q.page_size = Some(MAX_PAGE_SIZE + 1);
+        assert!(q.page_size().is_err());
+
+        q.indices = true;
+        q.page_size = None;
+        assert_eq!(MAX_PAGE_SIZE_WITH_INDICES, q.page_size().unwrap());
+        q.page_size = Some(MAX_PAGE_SIZE_WITH_INDICES + 1);
+        assert!(q.page_size().is_err());
+    }
+
+    #[test]
+    fn query_string_round_trips() {
+        let mut q = query("generation UTXO notification", "0xff");
+        q.indices = true;
+        let uri = format!("/?{}", q.query_string_for_page(3)).parse().unwrap();
+        let axum::extract::Query(parsed) =
+            axum::extract::Query::<AnnouncementSearchQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(q.flag, parsed.flag);
+        assert_eq!(q.receiver_id, parsed.receiver_id);
+        assert!(parsed.indices);
+        assert_eq!(3, parsed.page);
+    }
+}
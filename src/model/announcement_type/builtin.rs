@@ -0,0 +1,191 @@
+//! The announcement decoders that ship with the explorer.
+
+use neptune_cash::api::export::Announcement;
+use neptune_cash::api::export::NativeCurrencyAmount;
+use neptune_cash::api::export::TransparentInput;
+use neptune_cash::api::export::TransparentTransactionInfo;
+use neptune_cash::api::export::UtxoTriple;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::prelude::triton_vm::prelude::BFieldCodec;
+use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+use neptune_cash::prelude::twenty_first::tip5::Tip5;
+use neptune_cash::state::wallet::address::elliptic_curve_hybrid::ELLIPTIC_CURVE_HYBRID_ADDRESS_FLAG;
+use neptune_cash::state::wallet::address::generation_address::GENERATION_FLAG;
+use neptune_cash::state::wallet::address::symmetric_key::SYMMETRIC_KEY_FLAG;
+use neptune_cash::state::wallet::address::viewing_address::VIEWING_ADDRESS_FLAG;
+use serde::Serialize;
+
+use super::AnnouncementDecoder;
+use super::AnnouncementType;
+
+/// Decodes lustration announcements: the lustration flag followed by the
+/// BFieldCodec encoding of the revealed [`TransparentInput`].
+#[derive(Debug, Clone, Copy)]
+pub struct LustrationDecoder;
+
+impl AnnouncementDecoder for LustrationDecoder {
+    fn name(&self) -> &'static str {
+        "lustration"
+    }
+
+    // The lustration flag is `pub(crate)` in neptune-core; recognize it through
+    // the public predicate instead of duplicating the constant.
+    fn claims(&self, announcement: &Announcement) -> bool {
+        announcement.looks_like_lustration()
+    }
+
+    fn is_catch_all(&self) -> bool {
+        false
+    }
+
+    fn decode(&self, announcement: &Announcement) -> Option<AnnouncementType> {
+        let payload = announcement.message.get(1..)?;
+        let transparent_input = *TransparentInput::decode(payload).ok()?;
+        Some(AnnouncementType::Lustration(transparent_input))
+    }
+}
+
+/// Decodes [`TransparentTransactionInfo`] announcements. These carry no flag,
+/// so this is a catch-all decoder: every announcement no other decoder decodes
+/// is tried.
+#[derive(Debug, Clone, Copy)]
+pub struct TransparentTxInfoDecoder;
+
+impl AnnouncementDecoder for TransparentTxInfoDecoder {
+    fn name(&self) -> &'static str {
+        "transparent transaction info"
+    }
+
+    fn decode(&self, announcement: &Announcement) -> Option<AnnouncementType> {
+        TransparentTransactionInfo::try_from_announcement(announcement)
+            .ok()
+            .map(AnnouncementType::TransparentTxInfo)
+    }
+}
+
+/// Decodes the public header of an encrypted UTXO notification:
+/// `[flag, receiver_identifier, ciphertext..]`.
+#[derive(Debug, Clone, Copy)]
+pub struct UtxoNotificationDecoder {
+    name: &'static str,
+    flag: BFieldElement,
+}
+
+impl UtxoNotificationDecoder {
+    /// One decoder per address type that sends UTXO notifications.
+    pub fn all() -> [Self; 4] {
+        [
+            Self {
+                name: "generation UTXO notification",
+                flag: GENERATION_FLAG,
+            },
+            Self {
+                name: "symmetric-key UTXO notification",
+                flag: SYMMETRIC_KEY_FLAG,
+            },
+            Self {
+                name: "elliptic-curve-hybrid UTXO notification",
+                flag: ELLIPTIC_CURVE_HYBRID_ADDRESS_FLAG,
+            },
+            Self {
+                name: "viewing-address UTXO notification",
+                flag: VIEWING_ADDRESS_FLAG,
+            },
+        ]
+    }
+}
+
+impl AnnouncementDecoder for UtxoNotificationDecoder {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn flag(&self) -> Option<BFieldElement> {
+        Some(self.flag)
+    }
+
+    fn decode(&self, announcement: &Announcement) -> Option<AnnouncementType> {
+        let [flag, receiver_identifier, ciphertext @ ..] = announcement.message.as_slice() else {
+            return None;
+        };
+        Some(AnnouncementType::UtxoNotification(UtxoNotification {
+            kind: self.name,
+            flag: *flag,
+            receiver_identifier: *receiver_identifier,
+            ciphertext_len: ciphertext.len(),
+        }))
+    }
+}
+
+/// The publicly visible part of an encrypted UTXO notification.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct UtxoNotification {
+    #[serde(skip)]
+    pub kind: &'static str,
+    pub flag: BFieldElement,
+    pub receiver_identifier: BFieldElement,
+
+    /// Number of field elements of ciphertext following the header.
+    pub ciphertext_len: usize,
+}
+
+/// JSON view of a [`TransparentInput`], which itself only implements
+/// BFieldCodec.
+#[derive(Debug, Clone, Serialize)]
+pub(super) struct TransparentInputJson {
+    addition_record: String,
+    aocl_leaf_index: u64,
+    utxo_digest: String,
+    sender_randomness: String,
+    receiver_preimage: String,
+    amount: String,
+    amount_nau: String,
+}
+
+impl From<&TransparentInput> for TransparentInputJson {
+    fn from(input: &TransparentInput) -> Self {
+        let amount = input.utxo.get_native_currency_amount();
+        Self {
+            addition_record: input.addition_record().canonical_commitment.to_hex(),
+            aocl_leaf_index: input.aocl_leaf_index,
+            utxo_digest: Tip5::hash(&input.utxo).to_hex(),
+            sender_randomness: input.sender_randomness.to_hex(),
+            receiver_preimage: input.receiver_preimage.to_hex(),
+            amount: amount.to_string(),
+            amount_nau: amount.to_nau().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UtxoTripleJson {
+    addition_record: String,
+    utxo_digest: String,
+    sender_randomness: String,
+    receiver_digest: String,
+    amount: String,
+    amount_nau: String,
+    burned: bool,
+}
+
+impl From<&UtxoTriple> for UtxoTripleJson {
+    fn from(output: &UtxoTriple) -> Self {
+        let amount: NativeCurrencyAmount = output.utxo.get_native_currency_amount();
+        Self {
+            addition_record: output.addition_record().canonical_commitment.to_hex(),
+            utxo_digest: Tip5::hash(&output.utxo).to_hex(),
+            sender_randomness: output.sender_randomness.to_hex(),
+            receiver_digest: output.receiver_digest.to_hex(),
+            amount: amount.to_string(),
+            amount_nau: amount.to_nau().to_string(),
+            burned: output.receiver_digest == Digest::default(),
+        }
+    }
+}
+
+pub(super) fn transparent_tx_info_json(tx_info: &TransparentTransactionInfo) -> serde_json::Value {
+    serde_json::json!({
+        "inputs": tx_info.inputs.iter().map(TransparentInputJson::from).collect::<Vec<_>>(),
+        "outputs": tx_info.outputs.iter().map(UtxoTripleJson::from).collect::<Vec<_>>(),
+    })
+}
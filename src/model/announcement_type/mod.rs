@@ -0,0 +1,446 @@
+//! Classification and decoding of announcements.
+//!
+//! An [`Announcement`] is an opaque list of field elements. What it means is
+//! decided by an [`AnnouncementDecoder`]: each decoder claims announcements by
+//! their flag (first field element) or some other prefix, and turns the claimed
+//! ones into a typed [`AnnouncementType`]. Decoders are collected in an
+//! [`AnnouncementDecoderRegistry`]; the explorer's own decoders (lustrations,
+//! transparent transaction info, encrypted UTXO notifications) are registered
+//! by [`AnnouncementDecoderRegistry::builtin`], and applications building on
+//! Neptune announcements can register their own so that their announcements
+//! are no longer shown as opaque `Unknown` field elements.
+//!
+//! Every [`AnnouncementType`] has a serializable view ([`AnnouncementType::to_json`])
+//! and an HTML partial (see [`crate::html::component::announcement_payload`]).
+
+mod builtin;
+
+use std::sync::Arc;
+
+use neptune_cash::api::export::Announcement;
+use neptune_cash::api::export::TransparentInput;
+use neptune_cash::api::export::TransparentTransactionInfo;
+use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+use serde::Serialize;
+use serde::Serializer;
+
+pub use self::builtin::LustrationDecoder;
+pub use self::builtin::TransparentTxInfoDecoder;
+pub use self::builtin::UtxoNotification;
+pub use self::builtin::UtxoNotificationDecoder;
+
+#[derive(Debug, Clone)]
+pub enum AnnouncementType {
+    Unknown(Vec<BFieldElement>),
+    TransparentTxInfo(TransparentTransactionInfo),
+    /// A lustration announcement. The carried [`TransparentInput`] is the
+    /// plaintext of the spent input that was lustrated (publicly revealed).
+    Lustration(TransparentInput),
+    /// An encrypted UTXO notification. Only the flag and the receiver
+    /// identifier are public; the payload is ciphertext.
+    UtxoNotification(UtxoNotification),
+    /// An announcement decoded by a decoder registered outside of this crate.
+    Custom(CustomAnnouncement),
+}
+
+impl AnnouncementType {
+    /// Classify an announcement using the built-in decoders only.
+    ///
+    /// Prefer [`AnnouncementDecoderRegistry::parse`] with the registry held in
+    /// the app state, which also knows about application-registered decoders.
+    pub fn parse(announcement: Announcement) -> Self {
+        AnnouncementDecoderRegistry::builtin().parse(announcement)
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            AnnouncementType::Unknown(_) => "unknown",
+            AnnouncementType::TransparentTxInfo(_) => TransparentTxInfoDecoder.name(),
+            AnnouncementType::Lustration(_) => LustrationDecoder.name(),
+            AnnouncementType::UtxoNotification(notification) => notification.kind,
+            AnnouncementType::Custom(custom) => custom.name,
+        }
+        .to_string()
+    }
+
+    /// Serializable view of the announcement: `{"type": <name>, "payload": ..}`.
+    pub fn to_json(&self) -> serde_json::Value {
+        let payload = match self {
+            AnnouncementType::Unknown(message) => {
+                serde_json::json!(message.iter().map(|bfe| bfe.value()).collect::<Vec<_>>())
+            }
+            AnnouncementType::TransparentTxInfo(tx_info) => {
+                builtin::transparent_tx_info_json(tx_info)
+            }
+            AnnouncementType::Lustration(transparent_input) => {
+                serde_json::json!(builtin::TransparentInputJson::from(transparent_input))
+            }
+            AnnouncementType::UtxoNotification(notification) => serde_json::json!(notification),
+            AnnouncementType::Custom(custom) => custom.view.to_json(),
+        };
+
+        serde_json::json!({
+            "type": self.name(),
+            "payload": payload,
+        })
+    }
+}
+
+impl Serialize for AnnouncementType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        self.to_json().serialize(serializer)
+    }
+}
+
+/// Decodes announcements of one particular kind.
+///
+/// The registry asks each decoder in turn whether it [`claims`] an
+/// announcement, and if so, tries to [`decode`] it. The first decoder that both
+/// claims and successfully decodes the announcement determines its type.
+/// Decoders that recognize their announcements by a prefix are asked first, in
+/// the order they were registered, and [catch-all] decoders last.
+///
+/// [`claims`]: AnnouncementDecoder::claims
+/// [`decode`]: AnnouncementDecoder::decode
+/// [catch-all]: AnnouncementDecoder::is_catch_all
+pub trait AnnouncementDecoder: std::fmt::Debug + Send + Sync {
+    /// Human-readable name of the announcement type this decoder produces.
+    fn name(&self) -> &'static str;
+
+    /// The flag (first field element) that identifies announcements of this
+    /// type, if there is a single one. `None` for decoders that recognize
+    /// announcements by their content instead.
+    fn flag(&self) -> Option<BFieldElement> {
+        None
+    }
+
+    /// Whether this decoder wants to handle the announcement. By default, an
+    /// announcement is claimed iff it starts with [`Self::flag`], or always if
+    /// there is no flag.
+    fn claims(&self, announcement: &Announcement) -> bool {
+        match self.flag() {
+            Some(flag) => announcement.message.first() == Some(&flag),
+            None => true,
+        }
+    }
+
+    /// Whether this decoder claims every announcement and recognizes its own
+    /// only by attempting to decode them. By default, that is the case iff
+    /// there is no flag; decoders that override [`Self::claims`] to match some
+    /// other prefix should return `false`.
+    fn is_catch_all(&self) -> bool {
+        self.flag().is_none()
+    }
+
+    /// Decode a claimed announcement. Returns `None` if the payload does not
+    /// decode, in which case the registry moves on to the next decoder.
+    fn decode(&self, announcement: &Announcement) -> Option<AnnouncementType>;
+}
+
+/// Typed, serializable view of an announcement decoded by a decoder registered
+/// outside of this crate.
+pub trait AnnouncementView: std::fmt::Debug + Send + Sync {
+    /// JSON representation, used as the `payload` of [`AnnouncementType::to_json`].
+    fn to_json(&self) -> serde_json::Value;
+
+    /// HTML partial rendered in the payload section of the announcement page.
+    ///
+    /// The returned markup is inserted verbatim, so implementations must escape
+    /// any content taken from the announcement itself.
+    fn to_html(&self) -> String;
+}
+
+/// An announcement decoded by an application-registered decoder.
+#[derive(Debug, Clone)]
+pub struct CustomAnnouncement {
+    pub name: &'static str,
+    pub view: Arc<dyn AnnouncementView>,
+}
+
+/// Ordered collection of [`AnnouncementDecoder`]s.
+#[derive(Debug)]
+pub struct AnnouncementDecoderRegistry {
+    decoders: Vec<Box<dyn AnnouncementDecoder>>,
+}
+
+impl Default for AnnouncementDecoderRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl AnnouncementDecoderRegistry {
+    /// A registry without any decoders. Every announcement parses as
+    /// [`AnnouncementType::Unknown`].
+    pub fn empty() -> Self {
+        Self { decoders: vec![] }
+    }
+
+    /// A registry with the explorer's built-in decoders.
+    ///
+    /// Lustrations and UTXO notifications are identified by their flags;
+    /// transparent transaction info has no flag and is recognized by attempting
+    /// to decode, so it is registered last.
+    pub fn builtin() -> Self {
+        let mut registry = Self::empty();
+        registry.register(LustrationDecoder);
+        for decoder in UtxoNotificationDecoder::all() {
+            registry.register(decoder);
+        }
+        registry.register(TransparentTxInfoDecoder);
+        registry
+    }
+
+    /// Append a decoder. It is consulted after all previously registered ones,
+    /// except that [catch-all](AnnouncementDecoder::is_catch_all) decoders are
+    /// consulted after all others.
+    pub fn register(&mut self, decoder: impl AnnouncementDecoder + 'static) {
+        self.decoders.push(Box::new(decoder));
+    }
+
+    pub fn decoders(&self) -> impl Iterator<Item = &dyn AnnouncementDecoder> {
+        self.decoders.iter().map(|decoder| decoder.as_ref())
+    }
+
+    /// Look up a registered decoder by its [`AnnouncementDecoder::name`].
+    pub fn by_name(&self, name: &str) -> Option<&dyn AnnouncementDecoder> {
+        self.decoders().find(|decoder| decoder.name() == name)
+    }
+
+    pub fn parse(&self, announcement: Announcement) -> AnnouncementType {
+        let (catch_all, by_prefix): (Vec<_>, Vec<_>) =
+            self.decoders().partition(|decoder| decoder.is_catch_all());
+        by_prefix
+            .into_iter()
+            .chain(catch_all)
+            .filter(|decoder| decoder.claims(&announcement))
+            .find_map(|decoder| decoder.decode(&announcement))
+            .unwrap_or(AnnouncementType::Unknown(announcement.message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use neptune_cash::api::export::Announcement;
+    use neptune_cash::api::export::TransparentInput;
+    use neptune_cash::api::export::Utxo;
+    use neptune_cash::prelude::tasm_lib::prelude::Digest;
+    use neptune_cash::prelude::triton_vm::prelude::BFieldCodec;
+    use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+    use neptune_cash::state::wallet::address::generation_address::GENERATION_FLAG;
+
+    use super::*;
+
+    /// Value of `LUSTRATION_FLAG` in neptune-core's `transaction_kernel`. It is
+    /// `pub(crate)` there, so the explorer relies on
+    /// [`Announcement::looks_like_lustration`] instead and only needs the value
+    /// to build fixtures.
+    const LUSTRATION_FLAG: BFieldElement = BFieldElement::new(51022176260);
+
+    /// Build a lustration announcement's message: the flag followed by the
+    /// BFieldCodec encoding of a `TransparentInput`, exactly as neptune-core's
+    /// `UnlockedUtxo::lustration()` constructs it.
+    fn lustration_message(transparent_input: &TransparentInput) -> Vec<BFieldElement> {
+        let mut message = vec![LUSTRATION_FLAG];
+        message.extend(transparent_input.encode());
+        message
+    }
+
+    fn sample_transparent_input() -> TransparentInput {
+        TransparentInput {
+            utxo: Utxo::new(Digest::default(), vec![]),
+            aocl_leaf_index: 42,
+            sender_randomness: Digest::default(),
+            receiver_preimage: Digest::default(),
+        }
+    }
+
+    #[test]
+    fn parse_decodes_lustration_announcement() {
+        let transparent_input = sample_transparent_input();
+        let announcement = Announcement::new(lustration_message(&transparent_input));
+
+        // `TransparentInput` does not derive `PartialEq` outside of
+        // neptune-core's own test build, so verify the round-trip by comparing
+        // the re-encoded sequence and a known field.
+        match AnnouncementType::parse(announcement) {
+            AnnouncementType::Lustration(decoded) => {
+                assert_eq!(transparent_input.encode(), decoded.encode());
+                assert_eq!(42, decoded.aocl_leaf_index);
+            }
+            other => panic!("expected Lustration, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn lustration_name_is_lustration() {
+        let announcement = Announcement::new(lustration_message(&sample_transparent_input()));
+        let parsed = AnnouncementType::parse(announcement);
+        assert_eq!("lustration", parsed.name());
+    }
+
+    #[test]
+    fn parse_falls_back_to_unknown_when_payload_does_not_decode() {
+        // Flag is present but the remainder is not a valid `TransparentInput`
+        // encoding. Must not panic and must not be classified as Lustration.
+        let announcement = Announcement::new(vec![LUSTRATION_FLAG, BFieldElement::new(7)]);
+        match AnnouncementType::parse(announcement) {
+            AnnouncementType::Unknown(_) => {}
+            other => panic!("expected Unknown, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_falls_back_to_unknown_for_flag_only_announcement() {
+        // Flag with no payload at all (empty slice after the flag).
+        let announcement = Announcement::new(vec![LUSTRATION_FLAG]);
+        match AnnouncementType::parse(announcement) {
+            AnnouncementType::Unknown(_) => {}
+            other => panic!("expected Unknown, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_leaves_non_lustration_announcements_unchanged() {
+        // An announcement whose first element is not the lustration flag must
+        // never be classified as a lustration, regardless of its payload.
+        let announcement = Announcement::new(vec![
+            BFieldElement::new(7), // not the lustration flag
+            BFieldElement::new(13),
+        ]);
+        let parsed = AnnouncementType::parse(announcement);
+        assert!(
+            !matches!(parsed, AnnouncementType::Lustration(_)),
+            "non-lustration announcement must not parse as Lustration, got {parsed:?}"
+        );
+    }
+
+    #[test]
+    fn parse_decodes_utxo_notification_header() {
+        let receiver_identifier = BFieldElement::new(1337);
+        let announcement = Announcement::new(vec![
+            GENERATION_FLAG,
+            receiver_identifier,
+            BFieldElement::new(1),
+            BFieldElement::new(2),
+        ]);
+        match AnnouncementType::parse(announcement) {
+            AnnouncementType::UtxoNotification(notification) => {
+                assert_eq!(GENERATION_FLAG, notification.flag);
+                assert_eq!(receiver_identifier, notification.receiver_identifier);
+                assert_eq!(2, notification.ciphertext_len);
+            }
+            other => panic!("expected UtxoNotification, got {other:?}"),
+        }
+    }
+
+    #[derive(Debug)]
+    struct PingView(u64);
+
+    impl AnnouncementView for PingView {
+        fn to_json(&self) -> serde_json::Value {
+            serde_json::json!({ "sequence": self.0 })
+        }
+
+        fn to_html(&self) -> String {
+            format!("<p>ping #{}</p>", self.0)
+        }
+    }
+
+    #[derive(Debug)]
+    struct PingDecoder;
+
+    impl AnnouncementDecoder for PingDecoder {
+        fn name(&self) -> &'static str {
+            "ping"
+        }
+
+        fn flag(&self) -> Option<BFieldElement> {
+            Some(BFieldElement::new(999))
+        }
+
+        fn decode(&self, announcement: &Announcement) -> Option<AnnouncementType> {
+            let sequence = announcement.message.get(1)?.value();
+            Some(AnnouncementType::Custom(CustomAnnouncement {
+                name: self.name(),
+                view: Arc::new(PingView(sequence)),
+            }))
+        }
+    }
+
+    #[test]
+    fn registered_decoder_claims_by_flag() {
+        let mut registry = AnnouncementDecoderRegistry::builtin();
+        registry.register(PingDecoder);
+
+        let ping = Announcement::new(vec![BFieldElement::new(999), BFieldElement::new(5)]);
+        let parsed = registry.parse(ping);
+        assert_eq!("ping", parsed.name());
+        assert_eq!(
+            serde_json::json!({ "type": "ping", "payload": { "sequence": 5 } }),
+            parsed.to_json()
+        );
+
+        // claimed, but fails to decode: falls through to unknown
+        let truncated = Announcement::new(vec![BFieldElement::new(999)]);
+        assert!(matches!(
+            registry.parse(truncated),
+            AnnouncementType::Unknown(_)
+        ));
+
+        // built-in decoders still take precedence over later registrations
+        let lustration = Announcement::new(lustration_message(&sample_transparent_input()));
+        assert_eq!("lustration", registry.parse(lustration).name());
+    }
+
+    #[derive(Debug)]
+    struct AnythingDecoder;
+
+    impl AnnouncementDecoder for AnythingDecoder {
+        fn name(&self) -> &'static str {
+            "anything"
+        }
+
+        fn decode(&self, announcement: &Announcement) -> Option<AnnouncementType> {
+            Some(AnnouncementType::Unknown(announcement.message.clone()))
+        }
+    }
+
+    #[test]
+    fn catch_all_decoders_are_consulted_last() {
+        let mut registry = AnnouncementDecoderRegistry::empty();
+        registry.register(AnythingDecoder);
+        registry.register(PingDecoder);
+
+        let ping = Announcement::new(vec![BFieldElement::new(999), BFieldElement::new(5)]);
+        assert_eq!("ping", registry.parse(ping).name());
+        let builtin = AnnouncementDecoderRegistry::builtin();
+        assert!(builtin.decoders().last().unwrap().is_catch_all());
+        assert!(builtin
+            .decoders()
+            .filter(|decoder| decoder.name() != TransparentTxInfoDecoder.name())
+            .all(|decoder| !decoder.is_catch_all()));
+    }
+
+    #[test]
+    fn empty_registry_parses_everything_as_unknown() {
+        let registry = AnnouncementDecoderRegistry::empty();
+        let announcement = Announcement::new(lustration_message(&sample_transparent_input()));
+        assert!(matches!(
+            registry.parse(announcement),
+            AnnouncementType::Unknown(_)
+        ));
+    }
+
+    #[test]
+    fn unknown_serializes_as_decimal_field_elements() {
+        let parsed = AnnouncementType::Unknown(vec![BFieldElement::new(7), BFieldElement::new(13)]);
+        assert_eq!(
+            serde_json::json!({ "type": "unknown", "payload": [7, 13] }),
+            serde_json::to_value(&parsed).unwrap()
+        );
+    }
+}
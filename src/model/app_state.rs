@@ -3,18 +3,36 @@ use std::sync::Arc;
 use anyhow::Context;
 use arc_swap::ArcSwap;
 use clap::Parser;
+use neptune_cash::api::export::BlockHeight;
 use neptune_cash::api::export::Network;
 use neptune_cash::application::rpc::auth;
 use neptune_cash::prelude::twenty_first::tip5::Digest;
 use neptune_cash::protocol::consensus::block::block_selector::{
     BlockSelector, BlockSelectorLiteral,
 };
+use tokio::sync::watch;
 use tokio::sync::Mutex;
+use tokio::sync::RwLock;
 
+use crate::model::announcement_type::AnnouncementDecoderRegistry;
+use crate::model::assets::Assets;
+use crate::model::burn_registry::BurnEntry;
+use crate::model::burn_registry::BurnRegistry;
+use crate::model::chain_stats::StatsIndex;
 use crate::model::config::Config;
+use crate::model::forks::ForkLog;
+use crate::model::mining_gateway::MiningGateway;
 use crate::model::output_status::MempoolOutputsCache;
+use crate::model::rpc_limits::RpcLimiter;
+use crate::model::rpc_limits::RpcLimitsPolicy;
+use crate::model::supply_cache::SupplyCache;
+use crate::model::template_overrides::TemplateOverrides;
+use crate::model::theme::Theme;
+use crate::model::tip_watcher::ChainHead;
 use crate::model::transparent_utxo_tuple::TransparentUtxoTuple;
 use crate::neptune_rpc;
+use crate::shared;
+use crate::shared::SupplyBreakdown;
 
 #[derive(Debug, Clone)]
 pub struct AppStateInner {
@@ -41,12 +59,58 @@ pub struct AppStateInner {
     /// tx-output endpoint can answer "is this output in the mempool?" in O(1)
     /// instead of an O(mempool-size) RPC scan on every request.
     pub mempool_outputs_cache: Arc<Mutex<MempoolOutputsCache>>,
+
+    /// Decoders used to classify announcements. Contains the built-in
+    /// decoders unless the explorer was started with
+    /// [`AppState::init_with_announcement_decoders`].
+    pub announcement_decoders: Arc<AnnouncementDecoderRegistry>,
+
+    /// Known burns, from `--burns-file` or the bundled registry.
+    pub burn_registry: Arc<BurnRegistry>,
+
+    /// Headline supply figures per tip height, for polling clients.
+    pub supply_cache: Arc<Mutex<SupplyCache>>,
+
+    /// Per-block chain statistics, kept up to date by
+    /// [`run_stats_indexer`](crate::model::chain_stats::run_stats_indexer).
+    pub stats_index: Arc<RwLock<StatsIndex>>,
+
+    /// Observed reorganizations, recorded by
+    /// [`run_fork_watcher`](crate::model::forks::run_fork_watcher).
+    pub fork_log: Arc<RwLock<ForkLog>>,
+
+    /// API keys and submissions of miners, if started with `--mining-gateway`.
+    pub mining_gateway: Option<Arc<MiningGateway>>,
+
+    /// The tip and, with the mining gateway, the best block proposal, published
+    /// by [`run_tip_watcher`](crate::model::tip_watcher::run_tip_watcher).
+    pub chain_head: Arc<watch::Sender<ChainHead>>,
+
+    /// Static files, bundled or from `--assets-dir`.
+    pub assets: Arc<Assets>,
+
+    /// Branding, from `--theme-file` or the default.
+    pub theme: Arc<Theme>,
+
+    /// Replacements of bundled templates, from `--templates-dir`.
+    pub templates: Arc<TemplateOverrides>,
 }
 
 impl AppStateInner {
     pub fn token(&self) -> auth::Token {
         self.rpc_client.token
     }
+
+    /// The known burns on the connected node's network.
+    pub fn burns(&self) -> &[BurnEntry] {
+        self.burn_registry.burns(self.network)
+    }
+
+    /// The supply breakdown at `block_height` under the rules of the connected
+    /// node's network.
+    pub(crate) fn supply_breakdown(&self, block_height: BlockHeight) -> SupplyBreakdown {
+        shared::supply_breakdown(self.network, block_height, self.burns())
+    }
 }
 
 #[derive(Clone)]
@@ -68,9 +132,34 @@ impl std::ops::Deref for AppState {
 
 impl AppState {
     pub async fn init() -> Result<Self, anyhow::Error> {
-        let rpc_client = neptune_rpc::gen_authenticated_rpc_client()
-            .await
-            .with_context(|| "Failed to create RPC client")?;
+        Self::init_with_announcement_decoders(AnnouncementDecoderRegistry::builtin()).await
+    }
+
+    /// Like [`AppState::init`], but classify announcements with the given
+    /// registry, typically the built-in one extended with application-specific
+    /// decoders.
+    pub async fn init_with_announcement_decoders(
+        announcement_decoders: AnnouncementDecoderRegistry,
+    ) -> Result<Self, anyhow::Error> {
+        let config = Config::parse();
+        let rpc_limits = match &config.rpc_limits_file {
+            Some(path) => RpcLimitsPolicy::from_file(path)?,
+            None => RpcLimitsPolicy::default(),
+        };
+        let rpc_client =
+            neptune_rpc::gen_authenticated_rpc_client(Arc::new(RpcLimiter::new(&rpc_limits)))
+                .await
+                .with_context(|| "Failed to create RPC client")?;
+        Self::with_client(config, rpc_client, announcement_decoders).await
+    }
+
+    /// Set up the state of an explorer that talks to neptune-core through
+    /// `rpc_client`, e.g. a client of an in-process node.
+    pub async fn with_client(
+        config: Config,
+        rpc_client: neptune_rpc::AuthenticatedClient,
+        announcement_decoders: AnnouncementDecoderRegistry,
+    ) -> Result<Self, anyhow::Error> {
         let genesis_digest = rpc_client
             .block_digest(
                 tarpc::context::current(),
@@ -87,14 +176,45 @@ impl AppState {
             .await
             .with_context(|| "Failed to determine whether neptune-core maintains a UTXO index")?;
 
+        let burn_registry = match &config.burns_file {
+            Some(path) => BurnRegistry::from_file(path)?,
+            None => BurnRegistry::bundled(),
+        };
+        let stats_index = StatsIndex::open(config.stats_index_file.as_deref(), genesis_digest)?;
+        let fork_log = ForkLog::open(config.forks_file.as_deref())?;
+        let assets = Assets::load(config.assets_dir.as_deref())?;
+        let theme = match &config.theme_file {
+            Some(path) => Theme::from_file(path)?,
+            None => Theme::default(),
+        };
+        let templates = TemplateOverrides::load(config.templates_dir.as_deref())?;
+        let mining_gateway = match (config.mining_gateway, &config.mining_api_keys_file) {
+            (true, Some(keys_file)) => Some(Arc::new(MiningGateway::open(
+                keys_file,
+                config.mining_rate_limit(),
+                config.mining_log_file.as_deref(),
+            )?)),
+            _ => None,
+        };
+
         Ok(AppState::new(AppStateInner {
             network: rpc_client.network,
-            config: Config::parse(),
+            config,
             rpc_client,
             genesis_digest,
             maintains_utxo_index,
             transparent_utxos_cache: Arc::new(Mutex::new(vec![])),
             mempool_outputs_cache: Arc::new(Mutex::new(MempoolOutputsCache::default())),
+            announcement_decoders: Arc::new(announcement_decoders),
+            burn_registry: Arc::new(burn_registry),
+            supply_cache: Arc::new(Mutex::new(SupplyCache::default())),
+            stats_index: Arc::new(RwLock::new(stats_index)),
+            fork_log: Arc::new(RwLock::new(fork_log)),
+            mining_gateway,
+            chain_head: Arc::new(watch::Sender::new(ChainHead::default())),
+            assets: Arc::new(assets),
+            theme: Arc::new(theme),
+            templates: Arc::new(templates),
         }))
     }
 
@@ -126,6 +246,17 @@ impl AppState {
             // Fresh snapshot on reconnect: the mempool belongs to the (possibly
             // different) node we just reconnected to.
             mempool_outputs_cache: Arc::new(Mutex::new(MempoolOutputsCache::default())),
+            announcement_decoders: inner.announcement_decoders.clone(),
+            burn_registry: inner.burn_registry.clone(),
+            // The reconnected node may be on another network.
+            supply_cache: Arc::new(Mutex::new(SupplyCache::default())),
+            stats_index: inner.stats_index.clone(),
+            fork_log: inner.fork_log.clone(),
+            mining_gateway: inner.mining_gateway.clone(),
+            chain_head: inner.chain_head.clone(),
+            assets: inner.assets.clone(),
+            theme: inner.theme.clone(),
+            templates: inner.templates.clone(),
         };
         self.0.store(Arc::new(new_inner));
     }
@@ -0,0 +1,293 @@
+//! Static files served by the explorer: stylesheets and images.
+//!
+//! The files under `templates/web/{css,image}` are compiled into the binary,
+//! so that it can be deployed on its own. Pages link to them at
+//! `/assets/<dir>/<name>.<hash>.<ext>`, where `<hash>` is taken from the
+//! content; these URLs never change meaning and are served with an
+//! `immutable` cache policy. The plain paths (`/css/styles.css`, ...) keep
+//! working for external links, with a short cache policy and an `ETag`.
+//!
+//! Operators can replace or add files without rebuilding with
+//! `--assets-dir`: a file at `<dir>/css/styles.css` takes the place of the
+//! bundled `css/styles.css`, and new files are served alongside the bundled
+//! ones. The directory is read once on startup.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::header;
+use axum::http::HeaderMap;
+use axum::http::HeaderValue;
+use axum::http::StatusCode;
+use axum::http::Uri;
+use axum::response::IntoResponse;
+use axum::response::Response;
+
+use crate::model::app_state::AppState;
+
+macro_rules! bundled {
+    ($path:literal) => {
+        (
+            $path,
+            include_bytes!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/templates/web/",
+                $path
+            )) as &[u8],
+        )
+    };
+}
+
+const BUNDLED_ASSETS: &[(&str, &[u8])] = &[
+    bundled!("css/pico.min.css"),
+    bundled!("css/pico.cyan.min.css"),
+    bundled!("css/styles.css"),
+    bundled!("image/neptune-favicon.png"),
+    bundled!("image/neptune-logo-circle-small.png"),
+    bundled!("image/neptune-logo-circle.png"),
+    bundled!("image/neptune-logo.png"),
+];
+
+/// Prefix of the content-addressed URLs.
+const HASHED_PREFIX: &str = "/assets/";
+
+/// Number of hex digits of the content hash in an asset URL.
+const HASH_LEN: usize = 16;
+
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+const REVALIDATE: &str = "public, max-age=300";
+
+#[derive(Debug, Clone)]
+pub struct Asset {
+    pub content_type: HeaderValue,
+    pub bytes: Bytes,
+
+    /// Hex prefix of the blake3 hash of `bytes`.
+    pub hash: String,
+}
+
+impl Asset {
+    fn new(path: &str, bytes: Bytes) -> Self {
+        let mime = mime_guess::from_path(path).first_or_octet_stream();
+        let hash = blake3::hash(&bytes).to_hex()[..HASH_LEN].to_string();
+        Self {
+            content_type: HeaderValue::from_str(mime.as_ref())
+                .expect("mime types are valid headers"),
+            bytes,
+            hash,
+        }
+    }
+}
+
+/// The static files, by path relative to the assets directory, e.g.
+/// `css/styles.css`.
+#[derive(Clone, Default)]
+pub struct Assets {
+    assets: HashMap<String, Asset>,
+
+    /// Path relative to [`HASHED_PREFIX`] -> path of the asset.
+    hashed_paths: HashMap<String, String>,
+}
+
+impl std::fmt::Debug for Assets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.assets.keys()).finish()
+    }
+}
+
+impl Assets {
+    /// The files compiled into the binary.
+    pub fn bundled() -> Self {
+        let mut assets = Self::default();
+        for (path, bytes) in BUNDLED_ASSETS {
+            assets.insert(path.to_string(), Bytes::from_static(bytes));
+        }
+        assets
+    }
+
+    /// The bundled files, replaced or extended by those in `dir`, if any.
+    pub fn load(dir: Option<&Path>) -> Result<Self, anyhow::Error> {
+        let mut assets = Self::bundled();
+        if let Some(dir) = dir {
+            assets
+                .insert_dir(dir, "")
+                .with_context(|| format!("Failed to read assets directory {}", dir.display()))?;
+        }
+        Ok(assets)
+    }
+
+    fn insert_dir(&mut self, dir: &Path, prefix: &str) -> Result<(), anyhow::Error> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name
+                .to_str()
+                .with_context(|| format!("{} is not valid UTF-8", entry.path().display()))?;
+            let path = format!("{prefix}{name}");
+            if entry.file_type()?.is_dir() {
+                self.insert_dir(&entry.path(), &format!("{path}/"))?;
+            } else {
+                self.insert(path, Bytes::from(std::fs::read(entry.path())?));
+            }
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, path: String, bytes: Bytes) {
+        let asset = Asset::new(&path, bytes);
+        if let Some(old) = self.assets.get(&path) {
+            self.hashed_paths.remove(&hashed_path(&path, &old.hash));
+        }
+        self.hashed_paths
+            .insert(hashed_path(&path, &asset.hash), path.clone());
+        self.assets.insert(path, asset);
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Asset> {
+        self.assets.get(path)
+    }
+
+    /// The asset at `hashed_path`, relative to `/assets/`.
+    pub fn get_hashed(&self, hashed_path: &str) -> Option<&Asset> {
+        self.hashed_paths
+            .get(hashed_path)
+            .and_then(|path| self.assets.get(path))
+    }
+
+    /// The content-addressed URL of the asset at `path`, or its plain URL if
+    /// there is no such asset. URLs, e.g. of a theme's logo, are returned as
+    /// they are.
+    pub fn url(&self, path: &str) -> String {
+        if path.starts_with('/') || path.contains("://") {
+            return path.to_string();
+        }
+        match self.assets.get(path) {
+            Some(asset) => format!("{HASHED_PREFIX}{}", hashed_path(path, &asset.hash)),
+            None => format!("/{path}"),
+        }
+    }
+}
+
+/// `css/styles.css` -> `css/styles.<hash>.css`
+fn hashed_path(path: &str, hash: &str) -> String {
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let name = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem}.{hash}.{ext}"),
+        _ => format!("{name}.{hash}"),
+    };
+    match dir {
+        "" => name,
+        dir => format!("{dir}/{name}"),
+    }
+}
+
+/// Serves `/assets/*`, which may be cached forever.
+pub async fn hashed_asset(State(state_rw): State<Arc<AppState>>, uri: Uri) -> Response {
+    let state = state_rw.load();
+    let Some(asset) = uri
+        .path()
+        .strip_prefix(HASHED_PREFIX)
+        .and_then(|path| state.assets.get_hashed(path))
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    (
+        [
+            (header::CONTENT_TYPE, asset.content_type.clone()),
+            (header::CACHE_CONTROL, HeaderValue::from_static(IMMUTABLE)),
+        ],
+        asset.bytes.clone(),
+    )
+        .into_response()
+}
+
+/// Serves an asset at its plain path, e.g. `/css/styles.css`.
+pub async fn plain_asset(
+    State(state_rw): State<Arc<AppState>>,
+    uri: Uri,
+    headers: HeaderMap,
+) -> Response {
+    let state = state_rw.load();
+    let Some(asset) = state.assets.get(uri.path().trim_start_matches('/')) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let etag =
+        HeaderValue::from_str(&format!("\"{}\"", asset.hash)).expect("hex is a valid header");
+    let cache_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, HeaderValue::from_static(REVALIDATE)),
+    ];
+    if headers.get(header::IF_NONE_MATCH) == Some(&etag) {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    (
+        cache_headers,
+        [(header::CONTENT_TYPE, asset.content_type.clone())],
+        asset.bytes.clone(),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_file_is_bundled() {
+        for dir in ["css", "image"] {
+            let path = format!("{}/templates/web/{dir}", env!("CARGO_MANIFEST_DIR"));
+            for entry in std::fs::read_dir(path).unwrap() {
+                let path = format!("{dir}/{}", entry.unwrap().file_name().to_str().unwrap());
+                assert!(BUNDLED_ASSETS.iter().any(|(p, _)| *p == path), "{path}");
+            }
+        }
+    }
+
+    #[test]
+    fn hash_goes_before_the_extension() {
+        assert_eq!("css/styles.0123.css", hashed_path("css/styles.css", "0123"));
+        assert_eq!("LICENSE.0123", hashed_path("LICENSE", "0123"));
+        assert_eq!(".hidden.0123", hashed_path(".hidden", "0123"));
+    }
+
+    #[test]
+    fn bundled_assets_resolve_by_hashed_url() {
+        let assets = Assets::bundled();
+        let url = assets.url("css/styles.css");
+        let asset = assets
+            .get_hashed(url.strip_prefix(HASHED_PREFIX).unwrap())
+            .unwrap();
+        assert_eq!("text/css", asset.content_type);
+        assert_eq!("/unknown.css", assets.url("unknown.css"));
+        assert_eq!(
+            "https://example.org/a.png",
+            assets.url("https://example.org/a.png")
+        );
+    }
+
+    #[test]
+    fn directory_overrides_and_extends_bundled_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("css")).unwrap();
+        std::fs::write(dir.path().join("css/styles.css"), "body {}").unwrap();
+        std::fs::write(dir.path().join("css/extra.css"), "main {}").unwrap();
+
+        let bundled = Assets::bundled();
+        let assets = Assets::load(Some(dir.path())).unwrap();
+        assert_eq!(&b"body {}"[..], assets.get("css/styles.css").unwrap().bytes);
+        assert_eq!(&b"main {}"[..], assets.get("css/extra.css").unwrap().bytes);
+        assert_ne!(bundled.url("css/styles.css"), assets.url("css/styles.css"));
+        let old_url = bundled.url("css/styles.css");
+        assert!(assets
+            .get_hashed(old_url.strip_prefix(HASHED_PREFIX).unwrap())
+            .is_none());
+        assert_eq!(
+            bundled.url("css/pico.min.css"),
+            assets.url("css/pico.min.css")
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Resolving a point in time to the canonical block that was the tip then.
+
+use neptune_cash::api::export::BlockHeight;
+use neptune_cash::api::export::Digest;
+use neptune_cash::api::export::Timestamp;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelectorLiteral;
+
+use crate::model::app_state::AppStateInner;
+use crate::model::node_error::fetch_block_info;
+use crate::model::node_error::NodeError;
+
+/// The last canonical block with a timestamp at or before `timestamp`, or
+/// `None` if `timestamp` precedes the genesis block.
+///
+/// Binary search over the canonical chain by height, so it costs about
+/// log2(tip height) `block_info` calls. Relies on block timestamps increasing
+/// with height, which consensus enforces.
+pub async fn block_at_time(
+    state: &AppStateInner,
+    timestamp: Timestamp,
+) -> Result<Option<BlockInfo>, NodeError> {
+    let tip = fetch_block_info(state, BlockSelector::Special(BlockSelectorLiteral::Tip))
+        .await?
+        .ok_or(NodeError::MissingBlock("tip"))?;
+    if tip.timestamp <= timestamp {
+        return Ok(Some(tip));
+    }
+
+    let genesis = fetch_block_info(state, BlockSelector::Special(BlockSelectorLiteral::Genesis))
+        .await?
+        .ok_or(NodeError::MissingBlock("genesis"))?;
+    if genesis.timestamp > timestamp {
+        return Ok(None);
+    }
+
+    // Invariant: lower.timestamp <= timestamp < upper timestamp.
+    let mut lower = genesis;
+    let mut upper_height = u64::from(tip.height);
+    while upper_height - u64::from(lower.height) > 1 {
+        let middle_height = u64::from(lower.height) + (upper_height - u64::from(lower.height)) / 2;
+        let Some(middle) =
+            fetch_block_info(state, BlockSelector::Height(middle_height.into())).await?
+        else {
+            // reorganized to a shorter chain in the meantime
+            upper_height = middle_height;
+            continue;
+        };
+        if middle.timestamp <= timestamp {
+            lower = middle;
+        } else {
+            upper_height = middle_height;
+        }
+    }
+
+    Ok(Some(lower))
+}
+
+/// Like [`block_at_time`], but for a unix timestamp in seconds and returning
+/// only the height.
+pub async fn block_height_at_unix_time(
+    state: &AppStateInner,
+    unix_seconds: u64,
+) -> Result<Option<BlockHeight>, NodeError> {
+    let timestamp = Timestamp::millis(unix_seconds.saturating_mul(1000));
+    Ok(block_at_time(state, timestamp)
+        .await?
+        .map(|block_info| block_info.height))
+}
+
+/// The digest of the last canonical block with a timestamp at or before
+/// `timestamp`, or `None` if `timestamp` precedes the genesis block.
+///
+/// Answered from the local [`StatsIndex`](crate::model::chain_stats::StatsIndex)
+/// when it covers `timestamp`, without querying neptune-core; otherwise like
+/// [`block_at_time`]. The index trails the chain by up to one tip check, so
+/// shortly after a reorganization it may name a block that just left the
+/// canonical chain.
+pub async fn block_digest_at_time(
+    state: &AppStateInner,
+    timestamp: Timestamp,
+) -> Result<Option<Digest>, NodeError> {
+    if let Some(stats) = state
+        .stats_index
+        .read()
+        .await
+        .block_at_time(timestamp.to_millis())
+    {
+        return Ok(Some(stats.digest));
+    }
+
+    Ok(block_at_time(state, timestamp)
+        .await?
+        .map(|block_info| block_info.digest))
+}
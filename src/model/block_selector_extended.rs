@@ -1,67 +1,279 @@
 use std::num::ParseIntError;
 use std::str::FromStr;
 
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
 use neptune_cash::api::export::BlockHeight;
 use neptune_cash::api::export::Digest;
+use neptune_cash::api::export::Timestamp;
 use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+use neptune_cash::prelude::twenty_first::error::TryFromHexDigestError;
 use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelectorLiteral;
 use neptune_cash::protocol::consensus::block::block_selector::BlockSelectorParseError;
 use serde::de::Error;
 use serde::Deserialize;
 use serde::Deserializer;
+use tarpc::context;
 
+use super::app_state::AppStateInner;
+use super::block_at_time::block_digest_at_time;
 use super::height_or_digest::HeightOrDigest;
+use super::node_error::fetch_block_info;
+use super::node_error::NodeError;
 
-/// newtype for `BlockSelector` that provides ability to parse `height_or_digest/value`.
+/// The largest height a block can have: heights are field elements.
+pub const MAX_BLOCK_HEIGHT: u64 = BFieldElement::P - 1;
+
+/// extends `BlockSelector` with the ability to parse `height_or_digest/value`,
+/// `time/value` and relative selectors.
 ///
-/// This is useful for HTML form(s) that allow user to enter either height or
-/// digest into the same text input field.
+/// `height_or_digest` is useful for HTML form(s) that allow user to enter
+/// either height or digest into the same text input field.
 ///
 /// In particular it is necessary to support javascript-free website with such
 /// an html form.
-#[derive(Debug, Clone)]
-pub struct BlockSelectorExtended(BlockSelector);
+///
+/// `time` takes an RFC 3339 date-time or a unix timestamp in seconds, and
+/// selects the last canonical block with a timestamp at or before it.
+///
+/// Any other selector can be followed by `+N` or `-N` to select the canonical
+/// block `N` heights above or below it, e.g. `tip-10`, `genesis+100` or
+/// `digest/<hex>+3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockSelectorExtended {
+    Selector(BlockSelector),
+    Time(Timestamp),
+    /// Relative to a block whose height is only known to neptune-core: the tip
+    /// or a digest. Relative heights and genesis are resolved while parsing.
+    Relative {
+        base: BlockSelector,
+        offset: i64,
+    },
+}
 
-impl std::fmt::Display for BlockSelectorExtended {
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BlockSelectorExtendedParseError {
+    #[error(transparent)]
+    Selector(#[from] BlockSelectorParseError),
+    #[error("invalid block height {0}: {1}")]
+    Height(String, ParseIntError),
+    #[error("block height {0} is out of range; the largest block height is {MAX_BLOCK_HEIGHT}")]
+    HeightOutOfRange(i128),
+    #[error("invalid digest {0}: {1}")]
+    Digest(String, TryFromHexDigestError),
+    #[error("invalid time {0}: {1}")]
+    Time(String, String),
+    #[error("invalid block offset {0}: {1}")]
+    Offset(String, ParseIntError),
+    #[error("{0} is below the genesis block")]
+    BelowGenesis(String),
+}
+
+/// Error while resolving a [`BlockSelectorExtended`] to a `BlockSelector`.
+#[derive(Debug)]
+pub enum BlockSelectorResolveError {
+    /// A call into neptune-core failed.
+    Node(NodeError),
+    /// A relative selector reached below the genesis block, e.g. `tip-10`
+    /// while the tip is at height 5.
+    BelowGenesis {
+        selector: BlockSelectorExtended,
+        base_height: u64,
+    },
+}
+
+impl std::fmt::Display for BlockSelectorResolveError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        match self {
+            Self::Node(e) => write!(f, "{e}"),
+            Self::BelowGenesis {
+                selector,
+                base_height,
+            } => write!(
+                f,
+                "{selector} is below the genesis block: its base is at height {base_height}"
+            ),
+        }
     }
 }
 
-impl FromStr for BlockSelectorExtended {
-    type Err = BlockSelectorParseError;
+impl From<NodeError> for BlockSelectorResolveError {
+    fn from(e: NodeError) -> Self {
+        Self::Node(e)
+    }
+}
 
-    // note: this parses BlockSelector, plus height_or_digest/<value>
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let res = match BlockSelector::from_str(s) {
-            Ok(bs) => Ok(Self::from(bs)),
-            Err(e) => {
-                let parts: Vec<_> = s.split('/').collect();
-                if parts.len() == 2 {
-                    if parts[0] == "height_or_digest" {
-                        Ok(Self::from(HeightOrDigest::from_str(parts[1])?))
-                    } else if parts[0] == "digest" {
-                        Ok(Self(BlockSelector::Digest(
-                            Digest::try_from_hex(parts[1]).map_err(|tfhde| {
-                                BlockSelectorParseError::InvalidSelector(tfhde.to_string())
-                            })?,
-                        )))
-                    } else if parts[0] == "height" {
-                        Ok(Self(BlockSelector::Height(BlockHeight::new(
-                            BFieldElement::new(parts[1].parse().map_err(|e: ParseIntError| {
-                                BlockSelectorParseError::InvalidSelector(e.to_string())
-                            })?),
-                        ))))
-                    } else {
-                        Err(e)
+impl BlockSelectorExtended {
+    /// The `BlockSelector` of the selected block, or `None` if a selected
+    /// time precedes the genesis block or the base of a relative selector
+    /// does not exist.
+    ///
+    /// `time/<value>` and relative selectors need neptune-core (or the local
+    /// statistics index) to resolve. Times resolve to the digest, so the
+    /// answer stays put if the chain is reorganized while the request is
+    /// handled; relative selectors resolve to a height.
+    pub async fn resolve(
+        self,
+        state: &AppStateInner,
+    ) -> Result<Option<BlockSelector>, BlockSelectorResolveError> {
+        match self {
+            Self::Selector(block_selector) => Ok(Some(block_selector)),
+            Self::Time(timestamp) => Ok(block_digest_at_time(state, timestamp)
+                .await?
+                .map(BlockSelector::Digest)),
+            Self::Relative { base, offset } => {
+                let base_height = match base {
+                    BlockSelector::Special(BlockSelectorLiteral::Tip) => state
+                        .rpc_client
+                        .block_height(context::current(), state.token())
+                        .await
+                        .map_err(NodeError::from)?
+                        .map_err(NodeError::from)?,
+                    _ => {
+                        let Some(block_info) = fetch_block_info(state, base).await? else {
+                            return Ok(None);
+                        };
+                        block_info.height
                     }
-                } else {
-                    Err(e)
+                };
+                let base_height = u64::from(base_height);
+                match offset_height(base_height, offset) {
+                    Ok(height) => Ok(Some(BlockSelector::Height(height.into()))),
+                    // above any block that exists
+                    Err(BlockSelectorExtendedParseError::HeightOutOfRange(_)) => Ok(None),
+                    Err(_) => Err(BlockSelectorResolveError::BelowGenesis {
+                        selector: self,
+                        base_height,
+                    }),
                 }
             }
+        }
+    }
+}
+
+impl std::fmt::Display for BlockSelectorExtended {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Selector(block_selector) => write!(f, "{block_selector}"),
+            Self::Time(timestamp) => {
+                match i64::try_from(timestamp.to_millis())
+                    .ok()
+                    .and_then(DateTime::<Utc>::from_timestamp_millis)
+                {
+                    Some(date_time) => write!(
+                        f,
+                        "time/{}",
+                        date_time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+                    ),
+                    None => write!(f, "time/{}", timestamp.to_millis() / 1000),
+                }
+            }
+            Self::Relative { base, offset } => {
+                match base {
+                    BlockSelector::Digest(digest) => write!(f, "digest/{digest:x}")?,
+                    _ => write!(f, "{base}")?,
+                }
+                write!(f, "{offset:+}")
+            }
+        }
+    }
+}
+
+impl FromStr for BlockSelectorExtended {
+    type Err = BlockSelectorExtendedParseError;
+
+    // note: this parses BlockSelector, plus height_or_digest/<value>,
+    // digest/<value>, height/<value>, time/<value>, and any of these but time
+    // followed by +N or -N.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(time) = s.strip_prefix("time/") {
+            return Ok(Self::Time(parse_time(time)?));
+        }
+
+        let Some(sign) = s.rfind(['+', '-']) else {
+            return Ok(Self::Selector(parse_absolute(s)?));
+        };
+        let base = parse_absolute(&s[..sign])?;
+        let offset = s[sign..]
+            .parse::<i64>()
+            .map_err(|e| Self::Err::Offset(s[sign..].to_string(), e))?;
+
+        let base_height = match base {
+            BlockSelector::Height(height) => u64::from(height),
+            BlockSelector::Special(BlockSelectorLiteral::Genesis) => 0,
+            _ => return Ok(Self::Relative { base, offset }),
         };
-        res
+        let height = offset_height(base_height, offset).map_err(|e| match e {
+            Self::Err::BelowGenesis(_) => Self::Err::BelowGenesis(s.to_string()),
+            e => e,
+        })?;
+        Ok(Self::Selector(BlockSelector::Height(height.into())))
+    }
+}
+
+/// Parses a selector without offset: genesis, tip, `<height>`, `<digest>`,
+/// `height/<height>`, `digest/<digest>` or `height_or_digest/<value>`.
+fn parse_absolute(s: &str) -> Result<BlockSelector, BlockSelectorExtendedParseError> {
+    let parts: Vec<_> = s.split('/').collect();
+    match parts.as_slice() {
+        ["height_or_digest", value] => match value.parse::<u64>() {
+            Ok(_) => parse_height(value).map(BlockSelector::Height),
+            Err(_) => Ok(HeightOrDigest::from_str(value)?.into()),
+        },
+        ["digest", hex] => Digest::try_from_hex(hex)
+            .map(BlockSelector::Digest)
+            .map_err(|e| BlockSelectorExtendedParseError::Digest(hex.to_string(), e)),
+        ["height", height] => parse_height(height).map(BlockSelector::Height),
+        [value] if value.parse::<u64>().is_ok() => parse_height(value).map(BlockSelector::Height),
+        [_] => Ok(BlockSelector::from_str(s)?),
+        _ => Err(BlockSelectorParseError::InvalidSelector(s.to_string()).into()),
+    }
+}
+
+pub(crate) fn parse_height(s: &str) -> Result<BlockHeight, BlockSelectorExtendedParseError> {
+    let height = s
+        .parse::<u64>()
+        .map_err(|e| BlockSelectorExtendedParseError::Height(s.to_string(), e))?;
+    if height > MAX_BLOCK_HEIGHT {
+        return Err(BlockSelectorExtendedParseError::HeightOutOfRange(
+            height.into(),
+        ));
     }
+    Ok(BlockHeight::new(BFieldElement::new(height)))
+}
+
+/// `base_height + offset`, if it is a valid block height.
+fn offset_height(base_height: u64, offset: i64) -> Result<u64, BlockSelectorExtendedParseError> {
+    let height = i128::from(base_height) + i128::from(offset);
+    if height < 0 {
+        Err(BlockSelectorExtendedParseError::BelowGenesis(format!(
+            "{base_height}{offset:+}"
+        )))
+    } else if height > i128::from(MAX_BLOCK_HEIGHT) {
+        Err(BlockSelectorExtendedParseError::HeightOutOfRange(height))
+    } else {
+        Ok(height as u64)
+    }
+}
+
+/// Parses an RFC 3339 date-time, e.g. `2025-02-01T12:00:00Z`, or a unix
+/// timestamp in seconds.
+fn parse_time(s: &str) -> Result<Timestamp, BlockSelectorExtendedParseError> {
+    let invalid =
+        |message: &str| BlockSelectorExtendedParseError::Time(s.to_string(), message.to_string());
+
+    let millis = match s.parse::<u64>() {
+        Ok(unix_seconds) => unix_seconds
+            .checked_mul(1000)
+            .ok_or_else(|| invalid("out of range"))?,
+        Err(_) => {
+            let date_time = DateTime::parse_from_rfc3339(s).map_err(|e| invalid(&e.to_string()))?;
+            u64::try_from(date_time.timestamp_millis()).map_err(|_| invalid("before 1970"))?
+        }
+    };
+    Ok(Timestamp::millis(millis))
 }
 
 // note: axum uses serde Deserialize for Path elements.
@@ -77,18 +289,162 @@ impl<'de> Deserialize<'de> for BlockSelectorExtended {
 
 impl From<HeightOrDigest> for BlockSelectorExtended {
     fn from(hd: HeightOrDigest) -> Self {
-        Self(hd.into())
+        Self::Selector(hd.into())
     }
 }
 
 impl From<BlockSelector> for BlockSelectorExtended {
     fn from(v: BlockSelector) -> Self {
-        Self(v)
+        Self::Selector(v)
     }
 }
 
-impl From<BlockSelectorExtended> for BlockSelector {
-    fn from(v: BlockSelectorExtended) -> Self {
-        v.0
+#[cfg(test)]
+mod tests {
+    use proptest::prop_assert;
+    use proptest::prop_assert_eq;
+    use proptest_arbitrary_interop::arb;
+    use test_strategy::proptest;
+
+    use super::*;
+
+    fn time(s: &str) -> Option<u64> {
+        match BlockSelectorExtended::from_str(s) {
+            Ok(BlockSelectorExtended::Time(timestamp)) => Some(timestamp.to_millis()),
+            _ => None,
+        }
+    }
+
+    fn height(s: &str) -> Option<u64> {
+        match BlockSelectorExtended::from_str(s) {
+            Ok(BlockSelectorExtended::Selector(BlockSelector::Height(height))) => {
+                Some(height.into())
+            }
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parses_unix_and_rfc3339_times() {
+        assert_eq!(Some(1_738_411_200_000), time("time/1738411200"));
+        assert_eq!(Some(1_738_411_200_000), time("time/2025-02-01T12:00:00Z"));
+        assert_eq!(
+            Some(1_738_411_200_500),
+            time("time/2025-02-01T13:00:00.5+01:00")
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_times() {
+        assert!(BlockSelectorExtended::from_str("time/yesterday").is_err());
+        assert!(BlockSelectorExtended::from_str("time/1969-12-31T23:59:59Z").is_err());
+        assert!(BlockSelectorExtended::from_str("time/-1").is_err());
+        assert!(BlockSelectorExtended::from_str(&format!("time/{}", u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn times_display_as_rfc3339_and_roundtrip() {
+        let selector = BlockSelectorExtended::from_str("time/1738411200").unwrap();
+        assert_eq!("time/2025-02-01T12:00:00Z", selector.to_string());
+        assert_eq!(Some(1_738_411_200_000), time(&selector.to_string()));
+    }
+
+    #[test]
+    fn other_selectors_are_unchanged() {
+        assert_eq!(Some(5), height("height/5"));
+        assert_eq!(Some(5), height("5"));
+        assert_eq!(Some(5), height("height_or_digest/5"));
+        assert!(matches!(
+            BlockSelectorExtended::from_str("tip"),
+            Ok(BlockSelectorExtended::Selector(BlockSelector::Special(_)))
+        ));
+    }
+
+    #[test]
+    fn relative_heights_resolve_while_parsing() {
+        assert_eq!(Some(100), height("genesis+100"));
+        assert_eq!(Some(7), height("height/10-3"));
+        assert_eq!(Some(13), height("height_or_digest/10+3"));
+        assert!(matches!(
+            BlockSelectorExtended::from_str("tip-10"),
+            Ok(BlockSelectorExtended::Relative { offset: -10, .. })
+        ));
+    }
+
+    #[test]
+    fn relative_selectors_report_clear_errors() {
+        let err = |s: &str| BlockSelectorExtended::from_str(s).unwrap_err().to_string();
+        assert_eq!("genesis-1 is below the genesis block", err("genesis-1"));
+        assert_eq!("height/3-4 is below the genesis block", err("height/3-4"));
+        assert!(err("tip-ten").starts_with("invalid block offset -ten"));
+        assert!(err(&format!("height/{MAX_BLOCK_HEIGHT}+1")).contains("out of range"));
+        assert!(err(&format!("height/{}", BFieldElement::P)).contains("out of range"));
+    }
+
+    fn arbitrary_base(digest: Digest, height: u64, variant: u8) -> BlockSelector {
+        match variant % 4 {
+            0 => BlockSelector::Digest(digest),
+            1 => BlockSelector::Height(height.into()),
+            2 => BlockSelector::Special(BlockSelectorLiteral::Genesis),
+            _ => BlockSelector::Special(BlockSelectorLiteral::Tip),
+        }
+    }
+
+    #[proptest]
+    fn display_roundtrip(
+        #[strategy(arb())] digest: Digest,
+        #[strategy(0..=MAX_BLOCK_HEIGHT)] height: u64,
+        variant: u8,
+        offset: i64,
+    ) {
+        let selector = match arbitrary_base(digest, height, variant) {
+            base @ (BlockSelector::Digest(_)
+            | BlockSelector::Special(BlockSelectorLiteral::Tip)) => {
+                BlockSelectorExtended::Relative { base, offset }
+            }
+            base => BlockSelectorExtended::Selector(base),
+        };
+        let parsed = BlockSelectorExtended::from_str(&selector.to_string()).unwrap();
+        prop_assert_eq!(selector, parsed);
+    }
+
+    #[proptest]
+    fn height_offsets_add_up(
+        #[strategy(0..=MAX_BLOCK_HEIGHT)] base: u64,
+        #[strategy(-1_000_000i64..1_000_000)] offset: i64,
+    ) {
+        let selected = i128::from(base) + i128::from(offset);
+        let parsed = BlockSelectorExtended::from_str(&format!("height/{base}{offset:+}"));
+        if selected < 0 {
+            prop_assert!(matches!(
+                parsed,
+                Err(BlockSelectorExtendedParseError::BelowGenesis(_))
+            ));
+        } else if selected > i128::from(MAX_BLOCK_HEIGHT) {
+            prop_assert!(matches!(
+                parsed,
+                Err(BlockSelectorExtendedParseError::HeightOutOfRange(_))
+            ));
+        } else {
+            prop_assert_eq!(
+                Some(selected as u64),
+                height(&format!("height/{base}{offset:+}"))
+            );
+        }
+    }
+
+    #[proptest]
+    fn digest_offsets_stay_relative(#[strategy(arb())] digest: Digest, offset: i64) {
+        let parsed = BlockSelectorExtended::from_str(&format!("digest/{digest:x}{offset:+}"));
+        let expected = BlockSelectorExtended::Relative {
+            base: BlockSelector::Digest(digest),
+            offset,
+        };
+        prop_assert_eq!(expected, parsed.unwrap());
+    }
+
+    #[proptest]
+    fn parsing_never_panics(s: String) {
+        let _ = BlockSelectorExtended::from_str(&s);
     }
 }
@@ -0,0 +1,183 @@
+//! Known burns of coins, per network.
+//!
+//! Burned coins are unspendable but remain in the UTXO set, so the supply
+//! computation must be told about them. The registry is a JSON file mapping
+//! network names (as in `--network` of neptune-core, e.g. `main`) to a list of
+//! burns:
+//!
+//! ```json
+//! {
+//!     "main": [
+//!         {
+//!             "height": 17000,
+//!             "amount": "1526642.2",
+//!             "description": "All burns known up to block 17000, combined.",
+//!             "evidence": "https://talk.neptune.cash/t/list-of-known-burns/187"
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! `amount` is a decimal number of coins. A burn counts towards the supply at
+//! every height at or above its `height`. The registry bundled with the
+//! explorer lives in `data/burns.json`; operators can substitute their own with
+//! `--burns-file`, so that newly known burns need no release.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+use neptune_cash::api::export::BlockHeight;
+use neptune_cash::api::export::NativeCurrencyAmount;
+use neptune_cash::api::export::Network;
+use num_traits::Zero;
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Deserializer;
+
+use crate::shared;
+
+const BUNDLED_BURNS: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/data/burns.json"));
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BurnEntry {
+    pub height: BlockHeight,
+    #[serde(deserialize_with = "deserialize_coins")]
+    pub amount: NativeCurrencyAmount,
+    pub description: String,
+
+    /// Link to where the burn is documented.
+    pub evidence: String,
+}
+
+fn deserialize_coins<'de, D: Deserializer<'de>>(d: D) -> Result<NativeCurrencyAmount, D::Error> {
+    let coins = String::deserialize(d)?;
+    NativeCurrencyAmount::coins_from_str(&coins).map_err(D::Error::custom)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BurnRegistry {
+    /// Keyed by the network's display name, since [`Network`] is not `Ord`.
+    burns: BTreeMap<String, Vec<BurnEntry>>,
+}
+
+impl BurnRegistry {
+    /// The registry bundled with the explorer.
+    pub fn bundled() -> Self {
+        Self::from_json(BUNDLED_BURNS).expect("bundled burn registry is valid")
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read burn registry {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("Invalid burn registry {}", path.display()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, anyhow::Error> {
+        let mut burns: BTreeMap<String, Vec<BurnEntry>> = serde_json::from_str(json)?;
+        for (network, entries) in burns.iter_mut() {
+            let parsed = Network::from_str(network).map_err(anyhow::Error::msg)?;
+            anyhow::ensure!(
+                parsed.to_string() == *network,
+                "network {network} must be written as {parsed}"
+            );
+            anyhow::ensure!(
+                entries.iter().all(|entry| !entry.amount.is_negative()),
+                "burn amounts on {network} must not be negative"
+            );
+            entries.sort_by_key(|entry| entry.height);
+            // The liquid supply only grows with height, so checking it at the
+            // height of every burn covers all heights.
+            for entry in entries.iter() {
+                let supply = shared::supply_breakdown(parsed, entry.height, entries);
+                anyhow::ensure!(
+                    supply.burned <= supply.unburned_liquid(),
+                    "burns on {network} up to height {} exceed the liquid supply of {}",
+                    entry.height,
+                    supply.unburned_liquid()
+                );
+            }
+        }
+        Ok(Self { burns })
+    }
+
+    /// The known burns on the given network, in order of height.
+    pub fn burns(&self, network: Network) -> &[BurnEntry] {
+        self.burns
+            .get(&network.to_string())
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// The sum of all burns at or below the given height.
+pub fn burned_at(burns: &[BurnEntry], block_height: BlockHeight) -> NativeCurrencyAmount {
+    burns
+        .iter()
+        .filter(|entry| entry.height <= block_height)
+        .fold(NativeCurrencyAmount::zero(), |sum, entry| {
+            sum + entry.amount
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_registry_parses() {
+        let registry = BurnRegistry::bundled();
+        assert!(!registry.burns(Network::Main).is_empty());
+        assert!(registry.burns(Network::RegTest).is_empty());
+    }
+
+    #[test]
+    fn burns_count_from_their_height_on() {
+        let registry = BurnRegistry::from_json(
+            r#"{"testnet-1": [
+                {"height": 20, "amount": "2.5", "description": "b", "evidence": ""},
+                {"height": 10, "amount": "1", "description": "a", "evidence": ""}
+            ]}"#,
+        )
+        .unwrap();
+        let burns = registry.burns(Network::Testnet(1));
+        assert_eq!("a", burns[0].description);
+
+        assert!(burned_at(burns, 9u64.into()).is_zero());
+        assert_eq!(
+            NativeCurrencyAmount::coins(1),
+            burned_at(burns, 10u64.into())
+        );
+        assert_eq!(
+            NativeCurrencyAmount::coins_from_str("3.5").unwrap(),
+            burned_at(burns, 20u64.into())
+        );
+        assert!(registry.burns(Network::Main).is_empty());
+    }
+
+    #[test]
+    fn invalid_registries_are_rejected() {
+        assert!(BurnRegistry::from_json(r#"{"mainnet": []}"#).is_err());
+        assert!(BurnRegistry::from_json(r#"{"testnet": []}"#).is_err());
+        assert!(BurnRegistry::from_json(
+            r#"{"main": [{"height": 1, "amount": "-1", "description": "", "evidence": ""}]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn burns_beyond_the_liquid_supply_are_rejected() {
+        // 831488 premine + 64 liquid coins of block 1 on testnet
+        let registry = |amount: &str| {
+            BurnRegistry::from_json(&format!(
+                r#"{{"testnet-0": [
+                    {{"height": 1, "amount": "{amount}", "description": "", "evidence": ""}},
+                    {{"height": 1, "amount": "1", "description": "", "evidence": ""}}
+                ]}}"#
+            ))
+        };
+        assert!(registry("831551").is_ok());
+        assert!(registry("831552").is_err());
+    }
+}
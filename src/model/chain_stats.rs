@@ -0,0 +1,763 @@
+//! Per-block chain statistics, indexed locally and aggregated into time series
+//! per hour, day or week.
+//!
+//! A background task ([`run_stats_indexer`]) walks the canonical chain once,
+//! one `block_info` call per block, and then follows the tip as published by
+//! the [tip watcher](crate::model::tip_watcher). Queries are answered from the
+//! index alone, so charting a year of data costs the node nothing. The index
+//! is kept in memory and, with `--stats-index-file`, appended to a JSON-lines
+//! file so that restarts resume where they left off.
+//!
+//! Neptune merges all transactions of a block into a single one, so the number
+//! of transactions per block is not recoverable. The numbers of inputs, outputs
+//! and announcements stand in for transaction counts.
+
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::path::PathBuf;
+
+use neptune_cash::api::export::NativeCurrencyAmount;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use num_traits::Zero;
+use serde::Deserialize;
+use serde::Serialize;
+use tarpc::context;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+
+use crate::model::app_state::AppState;
+use crate::model::app_state::AppStateInner;
+use crate::model::jsonl;
+use crate::model::node_error::fetch_block_info;
+use crate::model::node_error::NodeError;
+use crate::model::tip_watcher::next_tip;
+use crate::model::tip_watcher::tip_check_interval;
+
+/// Number of blocks fetched between two writes to the index.
+const INDEX_BATCH_SIZE: u64 = 100;
+
+/// Upper bound on the number of buckets in one query.
+pub const MAX_STATS_BUCKETS: u64 = 2000;
+
+/// The statistics of one canonical block.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockStats {
+    pub height: u64,
+    pub digest: Digest,
+
+    /// Unix time in milliseconds.
+    pub timestamp_ms: u64,
+
+    /// Milliseconds since the previous block; zero for genesis.
+    pub interval_ms: u64,
+
+    pub difficulty: f64,
+
+    /// Proof-of-work this block added to the chain: the difference in
+    /// cumulative proof-of-work to its predecessor.
+    pub proof_of_work: f64,
+
+    /// In number of `BFieldElement`s.
+    pub size: usize,
+
+    pub fee: NativeCurrencyAmount,
+    pub num_inputs: usize,
+    pub num_outputs: usize,
+    pub num_announcements: usize,
+}
+
+impl BlockStats {
+    fn new(block_info: &BlockInfo, previous: Option<&BlockInfo>) -> Self {
+        let timestamp_ms = block_info.timestamp.to_millis();
+        let cumulative_proof_of_work = limbs_to_f64(block_info.cumulative_proof_of_work);
+        let (interval_ms, proof_of_work) = match previous {
+            Some(previous) => (
+                timestamp_ms.saturating_sub(previous.timestamp.to_millis()),
+                cumulative_proof_of_work - limbs_to_f64(previous.cumulative_proof_of_work),
+            ),
+            None => (0, cumulative_proof_of_work),
+        };
+        Self {
+            height: block_info.height.into(),
+            digest: block_info.digest,
+            timestamp_ms,
+            interval_ms,
+            difficulty: limbs_to_f64(block_info.difficulty),
+            proof_of_work,
+            size: block_info.size,
+            fee: block_info.fee,
+            num_inputs: block_info.num_inputs,
+            num_outputs: block_info.num_outputs,
+            num_announcements: block_info.num_announcements,
+        }
+    }
+}
+
+/// A big unsigned integer given as little-endian `u32` limbs, like
+/// `Difficulty` and `ProofOfWork`, as a float.
+fn limbs_to_f64<I>(limbs: I) -> f64
+where
+    I: IntoIterator<Item = u32>,
+    I::IntoIter: DoubleEndedIterator,
+{
+    limbs
+        .into_iter()
+        .rev()
+        .fold(0.0, |acc, limb| acc * 4_294_967_296.0 + f64::from(limb))
+}
+
+/// The statistics of the canonical chain from genesis up to some height, in
+/// order of height.
+///
+/// Only [`run_stats_indexer`] changes the index. It writes the file first and
+/// then updates the memory under the lock, so that readers never wait for
+/// disk I/O.
+#[derive(Debug, Default)]
+pub struct StatsIndex {
+    blocks: Vec<BlockStats>,
+    file: Option<PathBuf>,
+
+    /// Length of the file up to and including the line of each block.
+    ends: Vec<u64>,
+}
+
+impl StatsIndex {
+    /// An index of the chain starting at `genesis_digest`, backed by `file`,
+    /// resuming from its contents if it exists.
+    ///
+    /// Reading stops at the first block that does not continue the chain, and
+    /// the file is cut back to the blocks read. A file of another chain is
+    /// started over.
+    pub fn open(file: Option<&Path>, genesis_digest: Digest) -> Result<Self, anyhow::Error> {
+        let mut index = Self {
+            file: file.map(Path::to_path_buf),
+            ..Self::default()
+        };
+        let Some(file) = file else {
+            warn!(
+                "Without --stats-index-file, chain statistics are rebuilt with one \
+                 block_info call per block on every start"
+            );
+            return Ok(index);
+        };
+        if !file.exists() {
+            return Ok(index);
+        }
+
+        for (stats, end) in jsonl::load::<BlockStats>(file)? {
+            if stats.height != index.blocks.len() as u64 {
+                break;
+            }
+            index.blocks.push(stats);
+            index.ends.push(end);
+        }
+        if index
+            .blocks
+            .first()
+            .is_some_and(|genesis| genesis.digest != genesis_digest)
+        {
+            warn!(
+                "Chain statistics in {} are of another chain; starting over",
+                file.display()
+            );
+            index.truncate(0);
+        }
+        cut_back(file, index.file_len(index.blocks.len() as u64))?;
+        info!(
+            "Loaded chain statistics of {} blocks from {}",
+            index.blocks.len(),
+            file.display()
+        );
+        Ok(index)
+    }
+
+    pub fn blocks(&self) -> &[BlockStats] {
+        &self.blocks
+    }
+
+    pub fn last(&self) -> Option<&BlockStats> {
+        self.blocks.last()
+    }
+
+    /// The last indexed block with a timestamp at or before `timestamp_ms`,
+    /// provided the index settles it: the time is not before genesis and a
+    /// later block is indexed, so the tip cannot have moved past it.
+    pub fn block_at_time(&self, timestamp_ms: u64) -> Option<&BlockStats> {
+        if self.last()?.timestamp_ms <= timestamp_ms {
+            return None;
+        }
+        let after = self
+            .blocks
+            .partition_point(|stats| stats.timestamp_ms <= timestamp_ms);
+        after.checked_sub(1).map(|i| &self.blocks[i])
+    }
+
+    /// Length of the file holding the blocks below `height`.
+    fn file_len(&self, height: u64) -> u64 {
+        height
+            .checked_sub(1)
+            .and_then(|last| self.ends.get(last as usize))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Add `new_blocks`, whose lines end at `ends` in the file, if there is
+    /// one.
+    fn extend(&mut self, new_blocks: Vec<BlockStats>, ends: Vec<u64>) {
+        self.blocks.extend(new_blocks);
+        self.ends.extend(ends);
+    }
+
+    /// Drop the blocks at `height` and above, after a reorganization.
+    fn truncate(&mut self, height: u64) {
+        self.blocks.truncate(height as usize);
+        self.ends.truncate(height as usize);
+    }
+}
+
+/// Cut `file` back to its first `len` bytes.
+fn cut_back(file: &Path, len: u64) -> std::io::Result<()> {
+    OpenOptions::new().write(true).open(file)?.set_len(len)
+}
+
+/// Error while updating the [`StatsIndex`].
+#[derive(Debug, thiserror::Error)]
+pub enum StatsIndexError {
+    #[error(transparent)]
+    Node(#[from] NodeError),
+    #[error("could not write the index file: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Keep the [`StatsIndex`] up to date with the canonical chain, forever.
+pub async fn run_stats_indexer(app_state: AppState) {
+    debug!("chain statistics indexer started");
+
+    let mut chain_head = app_state.load().chain_head.subscribe();
+    loop {
+        let state = app_state.load();
+        let tip = chain_head.borrow_and_update().tip;
+        match update_stats_index(&state).await {
+            Ok(()) => {
+                next_tip(&mut chain_head, tip).await;
+            }
+            Err(e) => {
+                warn!("Failed to update chain statistics: {e}");
+                tokio::time::sleep(tip_check_interval(state.network)).await;
+            }
+        }
+    }
+}
+
+/// Rewind the index past any block that is no longer canonical, then append
+/// the blocks up to the current tip.
+async fn update_stats_index(state: &AppStateInner) -> Result<(), StatsIndexError> {
+    // only this task writes to the index, so holding the read lock across the
+    // calls keeps no one waiting
+    let index = state.stats_index.read().await;
+    let fork_height = first_non_canonical(index.blocks(), |height| async move {
+        Ok(state
+            .rpc_client
+            .block_digest(
+                context::current(),
+                state.token(),
+                BlockSelector::Height(height.into()),
+            )
+            .await??)
+    })
+    .await?;
+    let file = index.file.clone();
+    let file_len = fork_height.map(|height| index.file_len(height));
+    drop(index);
+    if let Some(fork_height) = fork_height {
+        info!("Blocks from {fork_height} on left the canonical chain; rewinding chain statistics");
+        if let (Some(file), Some(len)) = (file.clone(), file_len) {
+            tokio::task::spawn_blocking(move || cut_back(&file, len))
+                .await
+                .map_err(std::io::Error::other)??;
+        }
+        state.stats_index.write().await.truncate(fork_height);
+    }
+
+    let tip_height: u64 = state
+        .rpc_client
+        .block_height(context::current(), state.token())
+        .await
+        .map_err(NodeError::from)?
+        .map_err(NodeError::from)?
+        .into();
+
+    let mut previous = match state.stats_index.read().await.last() {
+        Some(last) => fetch_block_info(state, BlockSelector::Height(last.height.into())).await?,
+        None => None,
+    };
+    let mut next_height = previous
+        .as_ref()
+        .map_or(0, |block_info| u64::from(block_info.height) + 1);
+
+    while next_height <= tip_height {
+        let batch_end = tip_height.min(next_height + INDEX_BATCH_SIZE - 1);
+        let mut batch = vec![];
+        for height in next_height..=batch_end {
+            let Some(block_info) =
+                fetch_block_info(state, BlockSelector::Height(height.into())).await?
+            else {
+                // reorganized to a shorter chain in the meantime
+                break;
+            };
+            batch.push(BlockStats::new(&block_info, previous.as_ref()));
+            previous = Some(block_info);
+        }
+        if batch.is_empty() {
+            break;
+        }
+        next_height += batch.len() as u64;
+        let ends = match &file {
+            Some(file) => jsonl::append_blocking(file.clone(), batch.clone()).await?,
+            None => vec![],
+        };
+        state.stats_index.write().await.extend(batch, ends);
+    }
+
+    Ok(())
+}
+
+/// The height of the first of `blocks` that is no longer canonical, given the
+/// canonical digest at each height. Finds it in a logarithmic number of
+/// lookups, as all blocks below a non-canonical one are canonical and all
+/// above it are not.
+async fn first_non_canonical<F, Fut>(
+    blocks: &[BlockStats],
+    canonical_digest: F,
+) -> Result<Option<u64>, NodeError>
+where
+    F: Fn(u64) -> Fut,
+    Fut: std::future::Future<Output = Result<Option<Digest>, NodeError>>,
+{
+    let is_canonical = |stats: &BlockStats| {
+        let digest = stats.digest;
+        let lookup = canonical_digest(stats.height);
+        async move { Ok::<_, NodeError>(lookup.await? == Some(digest)) }
+    };
+    let Some(last) = blocks.last() else {
+        return Ok(None);
+    };
+    if is_canonical(last).await? {
+        return Ok(None);
+    }
+    // blocks[..low] are canonical, blocks[high..] are not
+    let (mut low, mut high) = (0, blocks.len() - 1);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if is_canonical(&blocks[middle]).await? {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    Ok(Some(blocks[high].height))
+}
+
+/// The width of the buckets a time series is aggregated into.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsResolution {
+    Hour,
+    #[default]
+    Day,
+    Week,
+}
+
+impl StatsResolution {
+    pub const ALL: [Self; 3] = [Self::Hour, Self::Day, Self::Week];
+
+    pub fn seconds(&self) -> u64 {
+        match self {
+            Self::Hour => 3600,
+            Self::Day => 24 * 3600,
+            Self::Week => 7 * 24 * 3600,
+        }
+    }
+
+    /// The range shown when the query does not specify one.
+    pub fn default_span_seconds(&self) -> u64 {
+        match self {
+            Self::Hour => 2 * 24 * 3600,
+            Self::Day => 90 * 24 * 3600,
+            Self::Week => 2 * 365 * 24 * 3600,
+        }
+    }
+
+    /// The start of the bucket containing the given unix time. Weeks start on
+    /// Monday, 00:00 UTC.
+    pub fn bucket_start(&self, unix_seconds: u64) -> u64 {
+        // 1970-01-01 was a Thursday; the first Monday was 4 days later.
+        let offset = match self {
+            Self::Week => 4 * 24 * 3600,
+            Self::Hour | Self::Day => 0,
+        };
+        let shifted = unix_seconds.saturating_sub(offset);
+        shifted - shifted % self.seconds() + offset
+    }
+}
+
+impl std::fmt::Display for StatsResolution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Week => "week",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Query parameters of `/stats` and `/rpc/stats`. Times are unix seconds.
+/// Empty values, as submitted by blank form fields, count as absent.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct StatsQuery {
+    #[serde(default)]
+    pub resolution: StatsResolution,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub from: Option<u64>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub to: Option<u64>,
+}
+
+fn empty_as_none<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    match value.trim() {
+        "" => Ok(None),
+        value => value.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum StatsQueryError {
+    #[error("`from` must not be after `to`")]
+    EmptyRange,
+    #[error("too many buckets; use a shorter range or a coarser resolution (at most {MAX_STATS_BUCKETS} buckets)")]
+    TooManyBuckets,
+}
+
+impl StatsQuery {
+    /// The inclusive range of unix seconds to aggregate, given the time of the
+    /// latest indexed block.
+    pub fn range(&self, latest_unix_seconds: u64) -> Result<(u64, u64), StatsQueryError> {
+        let to = self.to.unwrap_or(latest_unix_seconds);
+        let from = self
+            .from
+            .unwrap_or_else(|| to.saturating_sub(self.resolution.default_span_seconds()));
+        if from > to {
+            return Err(StatsQueryError::EmptyRange);
+        }
+        if (to - from) / self.resolution.seconds() >= MAX_STATS_BUCKETS {
+            return Err(StatsQueryError::TooManyBuckets);
+        }
+        Ok((from, to))
+    }
+}
+
+/// Aggregated statistics of the blocks in one bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsBucket {
+    /// Unix seconds.
+    pub start: u64,
+    pub num_blocks: usize,
+    pub average_block_interval_secs: f64,
+    pub average_difficulty: f64,
+
+    /// Hashes per second: the proof-of-work added in the bucket over the time
+    /// it took.
+    pub hashrate: f64,
+
+    pub total_size: usize,
+    pub total_fees: NativeCurrencyAmount,
+    pub num_inputs: usize,
+    pub num_outputs: usize,
+    pub num_announcements: usize,
+}
+
+/// Aggregate the blocks with timestamps in `from..=to` (unix seconds) into
+/// buckets of the given resolution. Buckets without blocks are omitted. The
+/// genesis block is skipped, as it was not mined.
+pub fn aggregate(
+    blocks: &[BlockStats],
+    resolution: StatsResolution,
+    from: u64,
+    to: u64,
+) -> Vec<StatsBucket> {
+    let start = blocks.partition_point(|b| b.timestamp_ms / 1000 < from);
+    let end = blocks.partition_point(|b| b.timestamp_ms / 1000 <= to);
+
+    let mut buckets: Vec<StatsBucket> = vec![];
+    for block in blocks[start..end].iter().filter(|b| b.height > 0) {
+        let bucket_start = resolution.bucket_start(block.timestamp_ms / 1000);
+        if buckets
+            .last()
+            .is_none_or(|bucket| bucket.start != bucket_start)
+        {
+            buckets.push(StatsBucket {
+                start: bucket_start,
+                num_blocks: 0,
+                average_block_interval_secs: 0.0,
+                average_difficulty: 0.0,
+                hashrate: 0.0,
+                total_size: 0,
+                total_fees: NativeCurrencyAmount::zero(),
+                num_inputs: 0,
+                num_outputs: 0,
+                num_announcements: 0,
+            });
+        }
+        let bucket = buckets.last_mut().expect("pushed above");
+
+        // accumulate sums; turned into averages below
+        bucket.num_blocks += 1;
+        bucket.average_block_interval_secs += block.interval_ms as f64 / 1000.0;
+        bucket.average_difficulty += block.difficulty;
+        bucket.hashrate += block.proof_of_work;
+        bucket.total_size += block.size;
+        bucket.total_fees += block.fee;
+        bucket.num_inputs += block.num_inputs;
+        bucket.num_outputs += block.num_outputs;
+        bucket.num_announcements += block.num_announcements;
+    }
+
+    for bucket in &mut buckets {
+        let total_interval_secs = bucket.average_block_interval_secs;
+        bucket.hashrate = if total_interval_secs > 0.0 {
+            bucket.hashrate / total_interval_secs
+        } else {
+            0.0
+        };
+        bucket.average_block_interval_secs = total_interval_secs / bucket.num_blocks as f64;
+        bucket.average_difficulty /= bucket.num_blocks as f64;
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+
+    use super::*;
+
+    fn block(height: u64, timestamp_secs: u64, interval_secs: u64) -> BlockStats {
+        BlockStats {
+            height,
+            digest: Digest::default(),
+            timestamp_ms: timestamp_secs * 1000,
+            interval_ms: interval_secs * 1000,
+            difficulty: 1000.0,
+            proof_of_work: 1000.0,
+            size: 10,
+            fee: NativeCurrencyAmount::coins(1),
+            num_inputs: 1,
+            num_outputs: 2,
+            num_announcements: 3,
+        }
+    }
+
+    #[test]
+    fn limbs_are_little_endian() {
+        assert_eq!(5.0, limbs_to_f64([5, 0, 0]));
+        assert_eq!(4_294_967_296.0, limbs_to_f64([0, 1, 0]));
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // 2025-08-11 12:00 UTC was a Monday.
+        let monday_noon = 1_754_913_600;
+        let monday_midnight = monday_noon - 12 * 3600;
+        assert_eq!(
+            monday_midnight,
+            StatsResolution::Week.bucket_start(monday_noon)
+        );
+        assert_eq!(
+            monday_midnight,
+            StatsResolution::Week.bucket_start(monday_midnight + 6 * 24 * 3600)
+        );
+        assert_eq!(
+            monday_midnight,
+            StatsResolution::Day.bucket_start(monday_noon)
+        );
+    }
+
+    #[test]
+    fn aggregates_per_bucket_and_skips_genesis() {
+        let blocks = vec![
+            block(0, 3600, 0),
+            block(1, 3700, 100),
+            block(2, 4000, 300),
+            block(3, 7300, 3300),
+        ];
+        let buckets = aggregate(&blocks, StatsResolution::Hour, 0, 10_000);
+        assert_eq!(2, buckets.len());
+
+        assert_eq!(3600, buckets[0].start);
+        assert_eq!(2, buckets[0].num_blocks);
+        assert_eq!(200.0, buckets[0].average_block_interval_secs);
+        assert_eq!(2000.0 / 400.0, buckets[0].hashrate);
+        assert_eq!(NativeCurrencyAmount::coins(2), buckets[0].total_fees);
+        assert_eq!(6, buckets[0].num_announcements);
+
+        assert_eq!(7200, buckets[1].start);
+        assert_eq!(1, buckets[1].num_blocks);
+    }
+
+    #[test]
+    fn aggregation_respects_range() {
+        let blocks = (0..10)
+            .map(|h| block(h, h * 3600, 3600))
+            .collect::<Vec<_>>();
+        let buckets = aggregate(&blocks, StatsResolution::Hour, 2 * 3600, 4 * 3600);
+        assert_eq!(
+            vec![2 * 3600, 3 * 3600, 4 * 3600],
+            buckets.iter().map(|b| b.start).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn query_range_defaults_and_limits() {
+        let query = StatsQuery::default();
+        let latest = 100 * 24 * 3600;
+        assert_eq!(Ok((10 * 24 * 3600, latest)), query.range(latest));
+
+        let reversed = StatsQuery {
+            from: Some(2),
+            to: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(Err(StatsQueryError::EmptyRange), reversed.range(latest));
+
+        let too_long = StatsQuery {
+            resolution: StatsResolution::Hour,
+            from: Some(0),
+            to: Some(MAX_STATS_BUCKETS * 3600),
+        };
+        assert_eq!(Err(StatsQueryError::TooManyBuckets), too_long.range(latest));
+    }
+
+    #[test]
+    fn blank_query_values_are_absent() {
+        let uri = "/stats?resolution=week&from=&to=100".parse().unwrap();
+        let axum::extract::Query(query) =
+            axum::extract::Query::<StatsQuery>::try_from_uri(&uri).unwrap();
+        assert_eq!(StatsResolution::Week, query.resolution);
+        assert_eq!(None, query.from);
+        assert_eq!(Some(100), query.to);
+    }
+
+    #[test]
+    fn index_settles_times_strictly_before_its_last_block() {
+        let mut index = StatsIndex::default();
+        assert_eq!(None, index.block_at_time(0));
+
+        index.extend((0..4).map(|h| block(h, 100 + h * 60, 60)).collect(), vec![]);
+        let height_at = |secs: u64| index.block_at_time(secs * 1000).map(|b| b.height);
+        assert_eq!(None, height_at(99));
+        assert_eq!(Some(0), height_at(100));
+        assert_eq!(Some(0), height_at(159));
+        assert_eq!(Some(1), height_at(160));
+        assert_eq!(Some(2), height_at(279));
+        // the tip may have advanced since the last indexed block
+        assert_eq!(None, height_at(280));
+        assert_eq!(None, height_at(1000));
+    }
+
+    /// Append `blocks` to the file and the index, as the indexer does.
+    fn extend(index: &mut StatsIndex, blocks: Vec<BlockStats>) {
+        let ends = jsonl::append(index.file.as_deref().unwrap(), &blocks).unwrap();
+        index.extend(blocks, ends);
+    }
+
+    /// Drop the blocks at `height` and above from the file and the index, as
+    /// the indexer does.
+    fn truncate(index: &mut StatsIndex, height: u64) {
+        cut_back(index.file.as_deref().unwrap(), index.file_len(height)).unwrap();
+        index.truncate(height);
+    }
+
+    #[test]
+    fn index_file_roundtrips_and_is_cut_back_at_gaps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.jsonl");
+        let genesis = Digest::default();
+
+        let mut index = StatsIndex::open(Some(&path), genesis).unwrap();
+        extend(&mut index, (0..5).map(|h| block(h, h * 600, 600)).collect());
+        truncate(&mut index, 3);
+        assert_eq!(3, std::fs::read_to_string(&path).unwrap().lines().count());
+        extend(&mut index, vec![block(3, 1800, 600)]);
+        // not continuing the chain; cut off on reload
+        extend(&mut index, vec![block(7, 4200, 600)]);
+
+        let mut reloaded = StatsIndex::open(Some(&path), genesis).unwrap();
+        assert_eq!(&index.blocks()[..4], reloaded.blocks());
+        assert_eq!(&index.ends[..4], reloaded.ends);
+
+        // so that blocks appended later survive the next reload
+        extend(&mut reloaded, vec![block(4, 2400, 600)]);
+        let blocks = StatsIndex::open(Some(&path), genesis)
+            .unwrap()
+            .blocks()
+            .to_vec();
+        assert_eq!(reloaded.blocks(), blocks);
+        assert_eq!(5, blocks.len());
+    }
+
+    #[test]
+    fn index_file_of_another_chain_is_started_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.jsonl");
+        let mut index = StatsIndex::open(Some(&path), Digest::default()).unwrap();
+        extend(&mut index, (0..3).map(|h| block(h, h * 600, 600)).collect());
+
+        let other_genesis = Digest::new([BFieldElement::new(1); Digest::LEN]);
+        let other = StatsIndex::open(Some(&path), other_genesis).unwrap();
+        assert!(other.blocks().is_empty());
+        assert_eq!("", std::fs::read_to_string(&path).unwrap());
+    }
+
+    #[tokio::test]
+    async fn fork_point_is_found_by_bisection() {
+        let digest = |n: u64| Digest::new([BFieldElement::new(n); Digest::LEN]);
+        let blocks = (0..100)
+            .map(|h| BlockStats {
+                digest: digest(h),
+                ..block(h, h * 600, 600)
+            })
+            .collect::<Vec<_>>();
+        let lookups = std::sync::atomic::AtomicUsize::new(0);
+        let fork_at = |fork_height: u64| {
+            let lookups = &lookups;
+            move |height: u64| async move {
+                lookups.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(Some(if height < fork_height {
+                    digest(height)
+                } else {
+                    digest(1000 + height)
+                }))
+            }
+        };
+
+        assert_eq!(
+            None,
+            first_non_canonical(&blocks, fork_at(100)).await.unwrap()
+        );
+        assert_eq!(1, lookups.swap(0, std::sync::atomic::Ordering::SeqCst));
+        for fork_height in [0, 1, 37, 99] {
+            let found = first_non_canonical(&blocks, fork_at(fork_height)).await;
+            assert_eq!(Some(fork_height), found.unwrap());
+            assert!(lookups.swap(0, std::sync::atomic::Ordering::SeqCst) <= 8);
+        }
+        assert_eq!(None, first_non_canonical(&[], fork_at(0)).await.unwrap());
+    }
+}
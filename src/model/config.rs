@@ -42,6 +42,93 @@ pub struct Config {
     #[clap(long, default_value = "3600", value_name = "seconds")]
     pub neptune_blockchain_watchdog_secs: u64,
 
+    /// JSON file listing known burns per network, replacing the bundled list.
+    /// See `data/burns.json` for the format.
+    #[clap(long, value_name = "path")]
+    pub burns_file: Option<std::path::PathBuf>,
+
+    /// JSON-lines file in which to keep the chain statistics index across
+    /// restarts. Recommended: without it, the index is rebuilt from the node
+    /// on startup, at one `block_info` call per block.
+    #[clap(long, value_name = "path")]
+    pub stats_index_file: Option<std::path::PathBuf>,
+
+    /// JSON-lines file in which to record observed reorganizations across
+    /// restarts. Without it, they are only kept in memory.
+    #[clap(long, value_name = "path")]
+    pub forks_file: Option<std::path::PathBuf>,
+
+    /// Serve the proof-of-work endpoints (`/rpc/pow_puzzle`,
+    /// `/rpc/provide_pow_solution`) to miners with an API key. Without it,
+    /// those endpoints are disabled.
+    #[clap(long, requires = "mining_api_keys_file")]
+    pub mining_gateway: bool,
+
+    /// JSON file mapping miner names to their API keys, for the mining gateway.
+    #[clap(long, value_name = "path")]
+    pub mining_api_keys_file: Option<std::path::PathBuf>,
+
+    /// Sets the interval in milliseconds at which each mining gateway API key
+    /// regains a request.
+    #[clap(long, default_value = "1000", value_name = "millis", value_parser = clap::value_parser!(u64).range(1..))]
+    pub mining_rate_limit_ms: u64,
+
+    /// Sets the number of mining gateway requests an API key may make at once.
+    #[clap(long, default_value = "5", value_name = "requests", value_parser = clap::value_parser!(u64).range(1..))]
+    pub mining_rate_limit_burst: u64,
+
+    /// JSON-lines file in which to record proof-of-work submissions made
+    /// through the mining gateway. Without it, they are only kept in memory.
+    #[clap(long, value_name = "path")]
+    pub mining_log_file: Option<std::path::PathBuf>,
+
+    /// List miner names, payout addresses and recent submissions on `/mining`
+    /// and `/rpc/mining`. Without it, those show only totals.
+    #[clap(long)]
+    pub mining_show_miners: bool,
+
+    /// JSON file with the rate-limit policy: quotas per route class, trusted
+    /// reverse proxies, and allowed and denied clients. See
+    /// `data/rate_limit.example.json` for the format. Without it, every client
+    /// may make one request every 10 ms.
+    #[clap(long, value_name = "path")]
+    pub rate_limit_file: Option<std::path::PathBuf>,
+
+    /// Record every distinct RPC request to neptune-core, with the answer, to
+    /// fixture files in this directory, for replay with `--rpc-replay-dir`.
+    #[clap(long, value_name = "path", conflicts_with = "rpc_replay_dir")]
+    pub rpc_record_dir: Option<std::path::PathBuf>,
+
+    /// Do not connect to neptune-core, but answer from the fixtures recorded
+    /// in this directory with `--rpc-record-dir`.
+    #[clap(long, value_name = "path")]
+    pub rpc_replay_dir: Option<std::path::PathBuf>,
+
+    /// JSON file with limits on concurrent calls into neptune-core, per class
+    /// of RPC method: how many may be in flight, how many more may wait, and
+    /// the deadline of each call. See `data/rpc_limits.example.json` for the
+    /// format and defaults.
+    #[clap(long, value_name = "path")]
+    pub rpc_limits_file: Option<std::path::PathBuf>,
+
+    /// Directory of static files (`css/...`, `image/...`) that replace or add
+    /// to the ones bundled with the explorer. Read once on startup.
+    #[clap(long, value_name = "path")]
+    pub assets_dir: Option<std::path::PathBuf>,
+
+    /// JSON file with the branding of the site: logo, accent colour, footer
+    /// links, extra `<head>` HTML and a custom stylesheet. See
+    /// `data/theme.example.json` for the format.
+    #[clap(long, value_name = "path")]
+    pub theme_file: Option<std::path::PathBuf>,
+
+    /// Directory of replacements for the bundled page templates, laid out as
+    /// `templates/web/html` (e.g. `page/block_info.html`). Each must keep the
+    /// code blocks of the template it replaces. Read once on startup. Needs the
+    /// `template-overrides` cargo feature.
+    #[clap(long, value_name = "path")]
+    pub templates_dir: Option<std::path::PathBuf>,
+
     /// admin email for receiving alert emails
     #[arg(long, value_name = "email")]
     pub admin_email: Option<String>,
@@ -102,6 +189,16 @@ impl Config {
             _ => None,
         }
     }
+
+    /// The rate limit of each mining gateway API key.
+    pub fn mining_rate_limit(&self) -> axum_gcra::gcra::Quota {
+        axum_gcra::gcra::Quota::new(
+            std::time::Duration::from_millis(self.mining_rate_limit_ms),
+            self.mining_rate_limit_burst
+                .try_into()
+                .unwrap_or(std::num::NonZeroU64::MIN),
+        )
+    }
 }
 
 #[derive(Debug, Clone, clap::Args)]
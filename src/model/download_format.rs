@@ -0,0 +1,80 @@
+//! Encodings offered by the raw data download endpoints.
+
+use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+use serde::Deserialize;
+
+/// Encoding of a downloaded sequence of field elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadFormat {
+    /// JSON, with field elements as decimal numbers.
+    #[default]
+    Json,
+
+    /// Plain text, with field elements as 16-digit hexadecimal numbers
+    /// separated by spaces.
+    Hex,
+
+    /// The `BFieldCodec` encoding, each field element as 8 little-endian bytes.
+    Bin,
+}
+
+impl DownloadFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DownloadFormat::Json => "json",
+            DownloadFormat::Hex => "hex",
+            DownloadFormat::Bin => "bin",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            DownloadFormat::Json => "application/json",
+            DownloadFormat::Hex => "text/plain; charset=utf-8",
+            DownloadFormat::Bin => "application/octet-stream",
+        }
+    }
+}
+
+/// Query string of the download endpoints, e.g. `?format=hex`.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct DownloadQuery {
+    #[serde(default)]
+    pub format: DownloadFormat,
+}
+
+pub fn hex_line(elements: &[BFieldElement]) -> String {
+    elements
+        .iter()
+        .map(|bfe| format!("{:016x}", bfe.value()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn to_le_bytes(elements: &[BFieldElement]) -> Vec<u8> {
+    elements
+        .iter()
+        .flat_map(|bfe| bfe.value().to_le_bytes())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_line_pads_each_element() {
+        let elements = [BFieldElement::new(1), BFieldElement::new(0xabc)];
+        assert_eq!("0000000000000001 0000000000000abc", hex_line(&elements));
+    }
+
+    #[test]
+    fn le_bytes_has_eight_bytes_per_element() {
+        let elements = [BFieldElement::new(1), BFieldElement::new(258)];
+        assert_eq!(
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 2, 1, 0, 0, 0, 0, 0, 0],
+            to_le_bytes(&elements)
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! The block subsidy schedule, per generation, and the countdown to the next
+//! halving.
+
+use neptune_cash::api::export::BlockHeight;
+use neptune_cash::api::export::NativeCurrencyAmount;
+use neptune_cash::api::export::Network;
+use neptune_cash::api::export::Timestamp;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelectorLiteral;
+use neptune_cash::protocol::consensus::block::Block;
+use num_traits::ops::checked::CheckedSub;
+use num_traits::Zero;
+
+use crate::model::app_state::AppStateInner;
+use crate::model::burn_registry::BurnEntry;
+use crate::model::node_error::fetch_block_info;
+use crate::model::node_error::NodeError;
+use crate::shared::generation_of;
+use crate::shared::generation_start_height;
+use crate::shared::supply_breakdown;
+
+/// Number of most recent blocks whose average interval is used to estimate
+/// when the next halving happens.
+pub const AVERAGE_BLOCK_INTERVAL_WINDOW: u64 = 1000;
+
+/// One generation (block subsidy halving period) of the emission schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GenerationEmission {
+    pub generation: u64,
+    pub start_height: BlockHeight,
+
+    /// The last block height of the generation, inclusive.
+    pub end_height: BlockHeight,
+
+    pub block_subsidy: NativeCurrencyAmount,
+
+    /// The part of the block subsidy that is liquid immediately.
+    pub liquid_per_block: NativeCurrencyAmount,
+
+    /// The part of the block subsidy that is time-locked when mined.
+    pub timelocked_per_block: NativeCurrencyAmount,
+
+    /// The total supply after the last block of the generation, counting the
+    /// burns known so far.
+    pub cumulative_supply: NativeCurrencyAmount,
+}
+
+/// Every generation with a non-zero block subsidy, in order.
+///
+/// Pure arithmetic on the consensus constants; the schedule has a little over a
+/// hundred generations before the subsidy halves to zero nau.
+pub(crate) fn emission_schedule(network: Network, burns: &[BurnEntry]) -> Vec<GenerationEmission> {
+    (0..)
+        .map(|generation| {
+            let start_height = generation_start_height(generation);
+            let end_height = generation_start_height(generation + 1)
+                .previous()
+                .expect("generation 1 starts after genesis");
+            let block_subsidy = Block::block_subsidy(start_height);
+            let liquid_per_block = block_subsidy.half();
+            GenerationEmission {
+                generation,
+                start_height,
+                end_height,
+                block_subsidy,
+                liquid_per_block,
+                timelocked_per_block: block_subsidy.checked_sub(&liquid_per_block).unwrap(),
+                cumulative_supply: supply_breakdown(network, end_height, burns).total(),
+            }
+        })
+        .take_while(|generation| !generation.block_subsidy.is_zero())
+        .collect()
+}
+
+/// The supply once every generation has been mined: the cumulative supply
+/// after the last generation with a non-zero subsidy, minus known burns.
+pub(crate) fn max_supply(network: Network, burns: &[BurnEntry]) -> NativeCurrencyAmount {
+    emission_schedule(network, burns)
+        .last()
+        .expect("generation 0 has a non-zero subsidy")
+        .cumulative_supply
+}
+
+/// Where the chain is in the emission schedule, and when the next halving can
+/// be expected.
+#[derive(Debug, Clone, Copy)]
+pub struct HalvingCountdown {
+    pub tip_height: BlockHeight,
+    pub tip_timestamp: Timestamp,
+    pub current_generation: u64,
+
+    /// The first block height with the halved subsidy.
+    pub next_halving_height: BlockHeight,
+
+    /// Blocks left to mine, including the one at `next_halving_height`.
+    pub blocks_remaining: u64,
+
+    /// Average interval over the last [`AVERAGE_BLOCK_INTERVAL_WINDOW`] blocks
+    /// (or fewer, on a young chain). `None` if only genesis exists.
+    pub average_block_interval: Option<Timestamp>,
+
+    /// Estimated time of the next halving, extrapolating the average interval.
+    pub eta: Option<Timestamp>,
+}
+
+/// Compute the [`HalvingCountdown`] at the current tip. Costs two `block_info`
+/// calls.
+pub async fn halving_countdown(state: &AppStateInner) -> Result<HalvingCountdown, NodeError> {
+    let tip = fetch_block_info(state, BlockSelector::Special(BlockSelectorLiteral::Tip))
+        .await?
+        .ok_or(NodeError::MissingBlock("tip"))?;
+    let tip_height = u64::from(tip.height);
+
+    let window = tip_height.min(AVERAGE_BLOCK_INTERVAL_WINDOW);
+    let average_block_interval = match window {
+        0 => None,
+        window => fetch_block_info(state, BlockSelector::Height((tip_height - window).into()))
+            .await?
+            .map(|earlier| average_interval(&earlier, &tip, window)),
+    };
+
+    let current_generation = generation_of(tip.height);
+    let next_halving_height = generation_start_height(current_generation + 1);
+    let blocks_remaining = u64::from(next_halving_height) - tip_height;
+    let eta = average_block_interval.map(|interval| {
+        Timestamp::millis(
+            tip.timestamp
+                .to_millis()
+                .saturating_add(interval.to_millis().saturating_mul(blocks_remaining)),
+        )
+    });
+
+    Ok(HalvingCountdown {
+        tip_height: tip.height,
+        tip_timestamp: tip.timestamp,
+        current_generation,
+        next_halving_height,
+        blocks_remaining,
+        average_block_interval,
+        eta,
+    })
+}
+
+fn average_interval(earlier: &BlockInfo, later: &BlockInfo, num_blocks: u64) -> Timestamp {
+    let elapsed = later
+        .timestamp
+        .to_millis()
+        .saturating_sub(earlier.timestamp.to_millis());
+    Timestamp::millis(elapsed / num_blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use neptune_cash::protocol::consensus::block::block_height::BLOCKS_PER_GENERATION;
+
+    use super::*;
+
+    #[test]
+    fn generations_are_contiguous_and_halve() {
+        let schedule = emission_schedule(Network::Main, &[]);
+        assert_eq!(BlockHeight::genesis(), schedule[0].start_height);
+        assert_eq!(NativeCurrencyAmount::coins(128), schedule[0].block_subsidy);
+        for pair in schedule.windows(2) {
+            assert_eq!(pair[0].end_height.next(), pair[1].start_height);
+            assert_eq!(pair[0].block_subsidy.half(), pair[1].block_subsidy);
+            assert_eq!(
+                BLOCKS_PER_GENERATION,
+                u64::from(pair[1].end_height) - u64::from(pair[0].end_height)
+            );
+        }
+    }
+
+    #[test]
+    fn split_adds_up_to_subsidy() {
+        for generation in emission_schedule(Network::Main, &[]) {
+            assert_eq!(
+                generation.block_subsidy,
+                generation.liquid_per_block + generation.timelocked_per_block
+            );
+        }
+    }
+
+    #[test]
+    fn max_supply_bounds_every_generation() {
+        for network in [Network::Main, Network::Testnet(0)] {
+            let max = max_supply(network, &[]);
+            assert!(emission_schedule(network, &[])
+                .iter()
+                .all(|generation| generation.cumulative_supply <= max));
+        }
+        assert!(max_supply(Network::Testnet(0), &[]) < max_supply(Network::Main, &[]));
+    }
+
+    #[test]
+    fn cumulative_supply_converges() {
+        let schedule = emission_schedule(Network::Main, &[]);
+        let last = schedule.last().unwrap();
+        // The premine plus twice the first generation's (full-length) emission
+        // is the limit of the geometric series.
+        let limit = NativeCurrencyAmount::from_nau(
+            NativeCurrencyAmount::coins(1).to_nau() * (831_488 + 2 * 128 * 160_815),
+        );
+        assert!(last.cumulative_supply <= limit);
+        assert!(last.cumulative_supply > NativeCurrencyAmount::coins(42_000_000));
+        assert!(schedule
+            .windows(2)
+            .all(|pair| pair[0].cumulative_supply < pair[1].cumulative_supply));
+    }
+}
@@ -0,0 +1,333 @@
+//! Observed reorganizations of the canonical chain.
+//!
+//! A background task ([`run_fork_watcher`]) follows the tip, as published by
+//! the [tip watcher](crate::model::tip_watcher). Whenever the previous tip has
+//! left the canonical chain, it walks the old branch back to the fork point
+//! and records a [`Reorg`]. A tip that advanced by several blocks between two
+//! checks is not a reorganization, even though the new tip is not a direct
+//! child of the old one.
+//!
+//! Reorganizations are kept in memory and, with `--forks-file`, appended to a
+//! JSON-lines file so that the history survives restarts. Reorganizations that
+//! happen while the explorer is not running, or that are undone between two
+//! tip checks, go unobserved.
+
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+
+use neptune_cash::api::export::Timestamp;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelectorLiteral;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::debug;
+use tracing::info;
+use tracing::warn;
+
+use crate::model::app_state::AppState;
+use crate::model::app_state::AppStateInner;
+use crate::model::jsonl;
+use crate::model::node_error::fetch_block_info;
+use crate::model::node_error::NodeError;
+use crate::model::tip_watcher::next_tip;
+use crate::model::tip_watcher::tip_check_interval;
+
+/// Number of reorganizations kept in memory; older ones remain in the file.
+pub const MAX_REORGS: usize = 1000;
+
+/// Upper bound on the number of blocks walked back to find a fork point.
+pub const MAX_FORK_DEPTH: usize = 1000;
+
+/// A block on either side of a reorganization.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkBlock {
+    pub height: u64,
+    pub digest: Digest,
+    /// Unix time in milliseconds.
+    pub timestamp_ms: u64,
+}
+
+impl From<&BlockInfo> for ForkBlock {
+    fn from(block_info: &BlockInfo) -> Self {
+        Self {
+            height: block_info.height.into(),
+            digest: block_info.digest,
+            timestamp_ms: block_info.timestamp.to_millis(),
+        }
+    }
+}
+
+impl ForkBlock {
+    pub fn timestamp(&self) -> Timestamp {
+        Timestamp::millis(self.timestamp_ms)
+    }
+}
+
+/// A switch of the canonical chain to a branch that does not contain the
+/// previous tip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reorg {
+    /// Unix time in milliseconds at which the explorer noticed.
+    pub observed_at_ms: u64,
+
+    /// The last block that both branches have in common.
+    pub fork_point: ForkBlock,
+    pub old_tip: ForkBlock,
+    pub new_tip: ForkBlock,
+
+    /// Number of blocks that left the canonical chain.
+    pub depth: usize,
+
+    /// The blocks that left the canonical chain, highest first.
+    pub orphaned: Vec<ForkBlock>,
+}
+
+impl Reorg {
+    pub fn observed_at(&self) -> Timestamp {
+        Timestamp::millis(self.observed_at_ms)
+    }
+}
+
+/// The observed reorganizations, oldest first.
+#[derive(Debug, Default)]
+pub struct ForkLog {
+    reorgs: VecDeque<Reorg>,
+    file: Option<PathBuf>,
+}
+
+impl ForkLog {
+    /// A log backed by `file`, resuming from its contents if it exists.
+    pub fn open(file: Option<&Path>) -> Result<Self, anyhow::Error> {
+        let mut log = Self {
+            reorgs: VecDeque::new(),
+            file: file.map(Path::to_path_buf),
+        };
+        let Some(file) = file.filter(|file| file.exists()) else {
+            return Ok(log);
+        };
+
+        for (reorg, _) in jsonl::load::<Reorg>(file)? {
+            log.push(reorg);
+        }
+        info!(
+            "Loaded {} reorganizations from {}",
+            log.reorgs.len(),
+            file.display()
+        );
+        Ok(log)
+    }
+
+    pub fn reorgs(&self) -> impl DoubleEndedIterator<Item = &Reorg> {
+        self.reorgs.iter()
+    }
+
+    /// The file the log is backed by, if any.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Remember `reorg`, forgetting the oldest one if the log is full. Callers
+    /// append it to the [`file`](Self::file) themselves.
+    pub fn push(&mut self, reorg: Reorg) {
+        if self.reorgs.len() == MAX_REORGS {
+            self.reorgs.pop_front();
+        }
+        self.reorgs.push_back(reorg);
+    }
+}
+
+/// The last canonical ancestor of `block_info` and the blocks between it and
+/// `block_info`, highest first. For a canonical block, that is the block
+/// itself and no others.
+///
+/// `None` if the branch is deeper than [`MAX_FORK_DEPTH`] or the node does not
+/// know one of its blocks.
+pub async fn fork_point(
+    state: &AppStateInner,
+    block_info: &BlockInfo,
+) -> Result<Option<(BlockInfo, Vec<BlockInfo>)>, NodeError> {
+    let mut branch = vec![];
+    let mut current = block_info.clone();
+    while !current.is_canonical {
+        if branch.len() == MAX_FORK_DEPTH {
+            return Ok(None);
+        }
+        let Some(parent) =
+            fetch_block_info(state, BlockSelector::Digest(current.prev_block_digest)).await?
+        else {
+            return Ok(None);
+        };
+        branch.push(current);
+        current = parent;
+    }
+    Ok(Some((current, branch)))
+}
+
+/// The most recent block that both `a` and `b` descend from or are.
+///
+/// Walks parents, stepping a non-canonical block first and otherwise the
+/// higher one, until the two meet or are both canonical, in which case the
+/// lower one is the ancestor. Every step costs one `block_info` call. `None`
+/// if that takes more than `max_steps` steps or the node does not know one of
+/// the blocks.
+pub async fn common_ancestor(
+    state: &AppStateInner,
+    a: &BlockInfo,
+    b: &BlockInfo,
+    max_steps: usize,
+) -> Result<Option<BlockInfo>, NodeError> {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    for step in 0..=max_steps {
+        if a.digest == b.digest {
+            return Ok(Some(a));
+        }
+        if a.is_canonical && b.is_canonical {
+            return Ok(Some(if a.height <= b.height { a } else { b }));
+        }
+        if step == max_steps {
+            break;
+        }
+
+        let step_a = match (a.is_canonical, b.is_canonical) {
+            (false, true) => true,
+            (true, false) => false,
+            _ => a.height >= b.height,
+        };
+        let stepped = if step_a { &a } else { &b };
+        let Some(parent) =
+            fetch_block_info(state, BlockSelector::Digest(stepped.prev_block_digest)).await?
+        else {
+            return Ok(None);
+        };
+        if step_a {
+            a = parent;
+        } else {
+            b = parent;
+        }
+    }
+    Ok(None)
+}
+
+/// Record reorganizations in the [`ForkLog`], forever.
+pub async fn run_fork_watcher(app_state: AppState) {
+    debug!("fork watcher started");
+
+    let mut chain_head = app_state.load().chain_head.subscribe();
+    let mut last_tip: Option<BlockInfo> = None;
+    loop {
+        let state = app_state.load();
+        match check_for_reorg(&state, last_tip.as_ref()).await {
+            Ok((tip, reorg)) => {
+                if let Some(reorg) = reorg {
+                    info!(
+                        "Observed a reorganization of depth {} at height {}",
+                        reorg.depth, reorg.fork_point.height
+                    );
+                    record(&state, reorg).await;
+                }
+                last_tip = Some(tip);
+            }
+            Err(e) => {
+                warn!("Failed to check for reorganizations: {e}");
+                tokio::time::sleep(tip_check_interval(state.network)).await;
+                continue;
+            }
+        }
+        next_tip(&mut chain_head, last_tip.as_ref().map(|tip| tip.digest)).await;
+    }
+}
+
+/// Add `reorg` to the [`ForkLog`], appending it to its file without holding
+/// the lock.
+async fn record(state: &AppStateInner, reorg: Reorg) {
+    let file = state.fork_log.read().await.file().map(Path::to_path_buf);
+    if let Some(file) = file {
+        if let Err(e) = jsonl::append_blocking(file, vec![reorg.clone()]).await {
+            warn!("Failed to record reorganization: {e}");
+        }
+    }
+    state.fork_log.write().await.push(reorg);
+}
+
+/// The current tip, and the reorganization that replaced `last_tip`, if any.
+async fn check_for_reorg(
+    state: &AppStateInner,
+    last_tip: Option<&BlockInfo>,
+) -> Result<(BlockInfo, Option<Reorg>), NodeError> {
+    let tip = fetch_block_info(state, BlockSelector::Special(BlockSelectorLiteral::Tip))
+        .await?
+        .ok_or(NodeError::MissingBlock("tip"))?;
+
+    let Some(last_tip) = last_tip.filter(|last_tip| last_tip.digest != tip.digest) else {
+        return Ok((tip, None));
+    };
+    if tip.prev_block_digest == last_tip.digest {
+        return Ok((tip, None));
+    }
+
+    // The tip may have advanced by several blocks; only a previous tip that
+    // left the canonical chain makes a reorganization. An unknown one means
+    // the explorer reconnected to another node.
+    let Some(old_tip) = fetch_block_info(state, BlockSelector::Digest(last_tip.digest)).await?
+    else {
+        return Ok((tip, None));
+    };
+    if old_tip.is_canonical {
+        return Ok((tip, None));
+    }
+
+    let Some((fork_point, orphaned)) = fork_point(state, &old_tip).await? else {
+        warn!(
+            "Previous tip {} left the canonical chain, but its fork point was not found",
+            old_tip.digest.to_hex()
+        );
+        return Ok((tip, None));
+    };
+
+    let reorg = Reorg {
+        observed_at_ms: Timestamp::now().to_millis(),
+        fork_point: (&fork_point).into(),
+        old_tip: (&old_tip).into(),
+        new_tip: (&tip).into(),
+        depth: orphaned.len(),
+        orphaned: orphaned.iter().map(ForkBlock::from).collect(),
+    };
+    Ok((tip, Some(reorg)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reorg(fork_height: u64, depth: usize) -> Reorg {
+        let block = |height: u64| ForkBlock {
+            height,
+            digest: Digest::default(),
+            timestamp_ms: height * 60_000,
+        };
+        Reorg {
+            observed_at_ms: 0,
+            fork_point: block(fork_height),
+            old_tip: block(fork_height + depth as u64),
+            new_tip: block(fork_height + depth as u64 + 1),
+            depth,
+            orphaned: (1..=depth as u64)
+                .rev()
+                .map(|i| block(fork_height + i))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn log_keeps_the_most_recent_reorgs() {
+        let mut log = ForkLog::default();
+        for height in 0..MAX_REORGS as u64 + 5 {
+            log.push(reorg(height, 1));
+        }
+        assert_eq!(MAX_REORGS, log.reorgs().count());
+        assert_eq!(5, log.reorgs().next().unwrap().fork_point.height);
+    }
+}
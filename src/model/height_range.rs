@@ -0,0 +1,153 @@
+//! Ranges of block heights, for endpoints that list blocks.
+
+use std::str::FromStr;
+
+use serde::de::Error;
+use serde::Deserialize;
+use serde::Deserializer;
+
+use super::block_selector_extended::parse_height;
+use super::block_selector_extended::BlockSelectorExtendedParseError;
+
+/// Upper bound on the number of blocks in one range, which bounds the number
+/// of `block_info` calls a single request can cause.
+pub const MAX_RANGE_BLOCKS: u64 = 100;
+
+/// The heights `height/<start>..<end>`, excluding `end`, or
+/// `height/<start>..=<end>`, including it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeightRange {
+    start: u64,
+    /// exclusive
+    end: u64,
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum HeightRangeParseError {
+    #[error("invalid height range {0}; expected height/<start>..<end> or height/<start>..=<end>")]
+    Syntax(String),
+    #[error(transparent)]
+    Height(#[from] BlockSelectorExtendedParseError),
+    #[error("height range {0} is empty")]
+    Empty(String),
+    #[error("height range {0} spans more than {MAX_RANGE_BLOCKS} blocks")]
+    TooLong(String),
+}
+
+impl HeightRange {
+    pub fn heights(&self) -> std::ops::Range<u64> {
+        self.start..self.end
+    }
+}
+
+impl std::fmt::Display for HeightRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "height/{}..{}", self.start, self.end)
+    }
+}
+
+impl FromStr for HeightRange {
+    type Err = HeightRangeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let syntax_err = || Self::Err::Syntax(s.to_string());
+
+        let (start, end) = s
+            .strip_prefix("height/")
+            .and_then(|range| range.split_once(".."))
+            .ok_or_else(syntax_err)?;
+        let (end, inclusive) = match end.strip_prefix('=') {
+            Some(end) => (end, true),
+            None => (end, false),
+        };
+        let start = u64::from(parse_height(start)?);
+        let end = u64::from(parse_height(end)?) + u64::from(inclusive);
+
+        if end <= start {
+            return Err(Self::Err::Empty(s.to_string()));
+        }
+        if end - start > MAX_RANGE_BLOCKS {
+            return Err(Self::Err::TooLong(s.to_string()));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+// note: axum uses serde Deserialize for Path elements.
+impl<'de> Deserialize<'de> for HeightRange {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prop_assert;
+    use proptest::prop_assert_eq;
+    use test_strategy::proptest;
+
+    use super::*;
+    use crate::model::block_selector_extended::MAX_BLOCK_HEIGHT;
+
+    #[test]
+    fn parses_exclusive_and_inclusive_ranges() {
+        assert_eq!(
+            100..200,
+            HeightRange::from_str("height/100..200").unwrap().heights()
+        );
+        assert_eq!(
+            100..200,
+            HeightRange::from_str("height/100..=199").unwrap().heights()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_ranges() {
+        let err = |s: &str| HeightRange::from_str(s).unwrap_err();
+        assert!(matches!(err("100..200"), HeightRangeParseError::Syntax(_)));
+        assert!(matches!(
+            err("height/100"),
+            HeightRangeParseError::Syntax(_)
+        ));
+        assert!(matches!(
+            err("height/a..2"),
+            HeightRangeParseError::Height(_)
+        ));
+        assert!(matches!(
+            err("height/5..5"),
+            HeightRangeParseError::Empty(_)
+        ));
+        assert!(matches!(
+            err("height/0..101"),
+            HeightRangeParseError::TooLong(_)
+        ));
+        assert!(matches!(
+            err(&format!("height/{MAX_BLOCK_HEIGHT}..{}", u64::MAX)),
+            HeightRangeParseError::Height(BlockSelectorExtendedParseError::HeightOutOfRange(_))
+        ));
+    }
+
+    #[proptest]
+    fn display_roundtrip(
+        #[strategy(0..MAX_BLOCK_HEIGHT - MAX_RANGE_BLOCKS)] start: u64,
+        #[strategy(1..=MAX_RANGE_BLOCKS)] len: u64,
+    ) {
+        let range = HeightRange::from_str(&format!("height/{start}..{}", start + len)).unwrap();
+        prop_assert_eq!(start..start + len, range.heights());
+        prop_assert_eq!(range, HeightRange::from_str(&range.to_string()).unwrap());
+    }
+
+    #[proptest]
+    fn accepts_exactly_the_bounded_nonempty_ranges(
+        #[strategy(0u64..1000)] start: u64,
+        #[strategy(0u64..1000)] end: u64,
+    ) {
+        let parsed = HeightRange::from_str(&format!("height/{start}..{end}"));
+        let valid = start < end && end - start <= MAX_RANGE_BLOCKS;
+        prop_assert!(parsed.is_ok() == valid);
+    }
+}
@@ -0,0 +1,97 @@
+//! JSON-lines files, in which the explorer keeps what it learns across
+//! restarts: the chain statistics index, observed reorganizations, mining
+//! submissions and recorded RPC fixtures.
+//!
+//! Every line holds one value, so a line cut short by a crash costs only that
+//! value; [`load`] skips it. Appends are a single write each, so concurrent
+//! appends do not interleave.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::warn;
+
+/// The values in `file`, in order, each with the length of the file up to and
+/// including its line. Lines that are not a `T`, e.g. one cut short by a
+/// crash, are skipped with a warning.
+pub fn load<T: DeserializeOwned>(file: &Path) -> io::Result<Vec<(T, u64)>> {
+    let mut reader = BufReader::new(File::open(file)?);
+    let mut values = vec![];
+    let mut line = String::new();
+    let (mut number, mut end) = (0, 0);
+    loop {
+        line.clear();
+        let len = reader.read_line(&mut line)?;
+        if len == 0 {
+            return Ok(values);
+        }
+        number += 1;
+        end += len as u64;
+        match serde_json::from_str::<T>(&line) {
+            Ok(value) => values.push((value, end)),
+            Err(e) => warn!("Skipping line {number} of {}: {e}", file.display()),
+        }
+    }
+}
+
+/// Append `values` to `file`, creating it if needed, and return the length of
+/// the file up to and including each of their lines. The lengths are only
+/// meaningful if no one else appends to the file at the same time.
+pub fn append<T: Serialize>(file: &Path, values: &[T]) -> io::Result<Vec<u64>> {
+    let mut file = OpenOptions::new().create(true).append(true).open(file)?;
+    let start = file.metadata()?.len();
+    let mut lines = vec![];
+    let mut ends = vec![];
+    for value in values {
+        serde_json::to_writer(&mut lines, value)?;
+        lines.push(b'\n');
+        ends.push(start + lines.len() as u64);
+    }
+    file.write_all(&lines)?;
+    Ok(ends)
+}
+
+/// [`append`] on the blocking thread pool, for async callers.
+pub async fn append_blocking<T>(file: PathBuf, values: Vec<T>) -> io::Result<Vec<u64>>
+where
+    T: Serialize + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || append(&file, &values))
+        .await
+        .map_err(io::Error::other)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn appended_values_load_and_malformed_lines_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("values.jsonl");
+
+        let ends = append(&path, &[1u32, 22]).unwrap();
+        assert_eq!(vec![2, 5], ends);
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"truncated\n")
+            .unwrap();
+        let ends = append(&path, &[333u32]).unwrap();
+        assert_eq!(vec![21], ends);
+
+        assert_eq!(
+            vec![(1u32, 2), (22, 5), (333, 21)],
+            load::<u32>(&path).unwrap()
+        );
+    }
+}
@@ -0,0 +1,494 @@
+//! Mining gateway: authenticated, rate-limited access to the node's
+//! proof-of-work endpoints.
+//!
+//! With `--mining-gateway`, `/rpc/pow_puzzle` and `/rpc/provide_pow_solution`
+//! are served to miners that present one of the API keys from
+//! `--mining-api-keys-file` in an `Authorization: Bearer <key>` header, each key
+//! with its own rate limit, checked once the key is known. Without it, those
+//! endpoints do not exist.
+//!
+//! Every submitted solution is recorded in a [`SubmissionLog`], attributed to
+//! the miner and, if the explorer issued the puzzle, to the address it was
+//! issued for. With `--mining-log-file`, submissions are appended to a
+//! JSON-lines file so that the counts survive restarts. `/mining` shows only
+//! totals unless `--mining-show-miners` is given.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Context;
+use axum::extract::FromRequestParts;
+use axum::http::header::AUTHORIZATION;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum_gcra::gcra;
+use neptune_cash::api::export::Timestamp;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tracing::info;
+
+use crate::model::app_state::AppStateInner;
+use crate::model::jsonl;
+
+/// Shortest API key accepted in the keys file.
+pub const MIN_API_KEY_LENGTH: usize = 16;
+
+/// Number of recent submissions kept in memory; older ones remain in the file.
+pub const MAX_RECENT_SUBMISSIONS: usize = 100;
+
+/// Number of issued puzzles remembered to attribute submissions to addresses.
+pub const MAX_ISSUED_PUZZLES: usize = 1000;
+
+/// The API key a request presents in an `Authorization: Bearer <key>` header.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct ApiKey(pub String);
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for ApiKey
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| ApiKey(key.to_string()))
+            .ok_or_else(|| {
+                (
+                    StatusCode::UNAUTHORIZED,
+                    "missing API key: send an `Authorization: Bearer <key>` header",
+                )
+                    .into_response()
+            })
+    }
+}
+
+/// A proof-of-work solution handed to the node through the gateway.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Submission {
+    /// Unix time in milliseconds.
+    pub submitted_at_ms: u64,
+    pub miner: String,
+
+    /// The address the puzzle was issued for, if this explorer issued it to
+    /// the same miner.
+    pub address: Option<String>,
+    pub proposal_id: Digest,
+
+    /// Whether the node accepted the solution.
+    pub accepted: bool,
+}
+
+impl Submission {
+    pub fn submitted_at(&self) -> Timestamp {
+        Timestamp::millis(self.submitted_at_ms)
+    }
+}
+
+/// Submission counts of one miner for one address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SubmissionCounts {
+    pub miner: String,
+    /// `None` for solutions to puzzles this explorer did not issue, or no
+    /// longer remembers.
+    pub address: Option<String>,
+    pub accepted: u64,
+    pub rejected: u64,
+}
+
+/// A puzzle served to a miner, remembered until a solution arrives.
+#[derive(Debug, Clone)]
+struct IssuedPuzzle {
+    id: Digest,
+    miner: String,
+    address: String,
+}
+
+/// All submissions made through the gateway.
+#[derive(Debug, Default)]
+pub struct SubmissionLog {
+    counts: BTreeMap<(String, Option<String>), (u64, u64)>,
+    recent: VecDeque<Submission>,
+    issued: VecDeque<IssuedPuzzle>,
+    file: Option<PathBuf>,
+}
+
+impl SubmissionLog {
+    /// A log backed by `file`, resuming from its contents if it exists.
+    pub fn open(file: Option<&Path>) -> Result<Self, anyhow::Error> {
+        let mut log = Self {
+            file: file.map(Path::to_path_buf),
+            ..Self::default()
+        };
+        let Some(file) = file.filter(|file| file.exists()) else {
+            return Ok(log);
+        };
+
+        let submissions = jsonl::load::<Submission>(file)?;
+        info!(
+            "Loaded {} PoW submissions from {}",
+            submissions.len(),
+            file.display()
+        );
+        for (submission, _) in submissions {
+            log.remember(submission);
+        }
+        Ok(log)
+    }
+
+    /// Remember that the puzzle `id` was issued to `miner` for `address`.
+    pub fn issue(&mut self, id: Digest, miner: &str, address: &str) {
+        if self
+            .issued
+            .iter()
+            .any(|puzzle| puzzle.id == id && puzzle.miner == miner)
+        {
+            return;
+        }
+        if self.issued.len() == MAX_ISSUED_PUZZLES {
+            self.issued.pop_front();
+        }
+        self.issued.push_back(IssuedPuzzle {
+            id,
+            miner: miner.to_string(),
+            address: address.to_string(),
+        });
+    }
+
+    /// Record a solution by `miner` to the puzzle `proposal_id`, returning the
+    /// submission for the caller to append to the [`file`](Self::file).
+    pub fn submit(&mut self, miner: &str, proposal_id: Digest, accepted: bool) -> Submission {
+        let address = self
+            .issued
+            .iter()
+            .rev()
+            .find(|puzzle| puzzle.id == proposal_id && puzzle.miner == miner)
+            .map(|puzzle| puzzle.address.clone());
+        let submission = Submission {
+            submitted_at_ms: Timestamp::now().to_millis(),
+            miner: miner.to_string(),
+            address,
+            proposal_id,
+            accepted,
+        };
+        self.remember(submission.clone());
+        submission
+    }
+
+    /// The file the log is backed by, if any.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
+    /// Counts per miner and address, ordered by miner.
+    pub fn counts(&self) -> Vec<SubmissionCounts> {
+        self.counts
+            .iter()
+            .map(
+                |((miner, address), (accepted, rejected))| SubmissionCounts {
+                    miner: miner.clone(),
+                    address: address.clone(),
+                    accepted: *accepted,
+                    rejected: *rejected,
+                },
+            )
+            .collect()
+    }
+
+    /// Accepted and rejected submissions of all miners.
+    pub fn totals(&self) -> (u64, u64) {
+        self.counts
+            .values()
+            .fold((0, 0), |(accepted, rejected), (a, r)| {
+                (accepted + a, rejected + r)
+            })
+    }
+
+    /// The most recent submissions, oldest first.
+    pub fn recent(&self) -> impl DoubleEndedIterator<Item = &Submission> {
+        self.recent.iter()
+    }
+
+    fn remember(&mut self, submission: Submission) {
+        let (accepted, rejected) = self
+            .counts
+            .entry((submission.miner.clone(), submission.address.clone()))
+            .or_default();
+        if submission.accepted {
+            *accepted += 1;
+        } else {
+            *rejected += 1;
+        }
+
+        if self.recent.len() == MAX_RECENT_SUBMISSIONS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(submission);
+    }
+}
+
+/// The rate limit of every API key, and the state of each miner's quota.
+struct MinerLimits {
+    quota: gcra::Quota,
+    limits: gcra::RateLimiter<String>,
+}
+
+impl std::fmt::Debug for MinerLimits {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MinerLimits")
+            .field("quota", &self.quota)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The miners allowed to use the gateway and what they submitted.
+#[derive(Debug)]
+pub struct MiningGateway {
+    /// Miner names by API key.
+    miners: HashMap<String, String>,
+    limits: MinerLimits,
+    pub log: RwLock<SubmissionLog>,
+}
+
+impl MiningGateway {
+    /// A gateway for the miners in `keys_file`, a JSON object mapping miner
+    /// names to API keys, each limited to `quota`, recording submissions in
+    /// `log_file`.
+    pub fn open(
+        keys_file: &Path,
+        quota: gcra::Quota,
+        log_file: Option<&Path>,
+    ) -> Result<Self, anyhow::Error> {
+        let json = std::fs::read_to_string(keys_file)
+            .with_context(|| format!("Failed to read mining API keys {}", keys_file.display()))?;
+        let keys: BTreeMap<String, String> = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid mining API keys {}", keys_file.display()))?;
+        let gateway = Self::new(keys, quota, SubmissionLog::open(log_file)?)
+            .with_context(|| format!("Invalid mining API keys {}", keys_file.display()))?;
+        info!("Mining gateway enabled for {} miners", gateway.miners.len());
+        Ok(gateway)
+    }
+
+    /// A gateway for miners given by name with their API keys.
+    pub fn new(
+        keys: BTreeMap<String, String>,
+        quota: gcra::Quota,
+        log: SubmissionLog,
+    ) -> Result<Self, anyhow::Error> {
+        let mut miners = HashMap::new();
+        for (miner, key) in keys {
+            anyhow::ensure!(
+                key.len() >= MIN_API_KEY_LENGTH,
+                "API key of {miner} must be at least {MIN_API_KEY_LENGTH} characters long"
+            );
+            if let Some(other) = miners.insert(key, miner.clone()) {
+                anyhow::bail!("{miner} and {other} have the same API key");
+            }
+        }
+        Ok(Self {
+            miners,
+            limits: MinerLimits {
+                quota,
+                limits: gcra::RateLimiter::default(),
+            },
+            log: RwLock::new(log),
+        })
+    }
+
+    /// The name of the miner holding `key`, if any.
+    pub fn miner(&self, key: &ApiKey) -> Option<&str> {
+        self.miners.get(&key.0).map(String::as_str)
+    }
+
+    /// Count a request by `miner` at `now` against the quota of its key.
+    ///
+    /// Only known miners are counted, so there is one quota per API key.
+    pub fn check_rate_limit(&self, miner: &str, now: Instant) -> Result<(), AuthorizeError> {
+        self.limits
+            .limits
+            .req_sync(miner.to_string(), self.limits.quota, now)
+            .map_err(|e| AuthorizeError::Limited {
+                retry_after: e.as_duration(),
+            })
+    }
+
+    /// Record a solution by `miner` to the puzzle `proposal_id`, appending it
+    /// to the log file, if there is one, off the async runtime and without
+    /// holding the log's lock.
+    pub async fn submit(
+        &self,
+        miner: &str,
+        proposal_id: Digest,
+        accepted: bool,
+    ) -> std::io::Result<()> {
+        let (submission, file) = {
+            let mut log = self.log.write().await;
+            let submission = log.submit(miner, proposal_id, accepted);
+            (submission, log.file().map(Path::to_path_buf))
+        };
+        let Some(file) = file else {
+            return Ok(());
+        };
+        jsonl::append_blocking(file, vec![submission]).await?;
+        Ok(())
+    }
+}
+
+/// Why a request may not use the mining gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum AuthorizeError {
+    #[error("mining gateway is disabled")]
+    Disabled,
+    #[error("unknown API key")]
+    UnknownKey,
+    #[error("rate limit of the API key exceeded")]
+    Limited { retry_after: Duration },
+}
+
+/// The gateway and the name of the miner holding `key`, once the request is
+/// counted against the key's rate limit.
+pub fn authorize<'a>(
+    state: &'a AppStateInner,
+    key: &ApiKey,
+) -> Result<(&'a MiningGateway, &'a str), AuthorizeError> {
+    let gateway = state
+        .mining_gateway
+        .as_deref()
+        .ok_or(AuthorizeError::Disabled)?;
+    let miner = gateway.miner(key).ok_or(AuthorizeError::UnknownKey)?;
+    gateway.check_rate_limit(miner, Instant::now())?;
+    Ok((gateway, miner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(i: u64) -> Digest {
+        Digest::new([i.into(); 5])
+    }
+
+    /// Two requests at once, and one more every second.
+    fn quota() -> gcra::Quota {
+        gcra::Quota::new(Duration::from_secs(1), 2.try_into().unwrap())
+    }
+
+    #[test]
+    fn submissions_are_attributed_to_issued_addresses() {
+        let mut log = SubmissionLog::default();
+        log.issue(id(1), "alice", "address-a");
+        log.issue(id(2), "bob", "address-b");
+
+        log.submit("alice", id(1), true);
+        log.submit("alice", id(1), false);
+        // Issued to bob, not alice.
+        log.submit("alice", id(2), false);
+        log.submit("bob", id(3), true);
+
+        let counts = |miner: &str, address: Option<&str>| {
+            log.counts()
+                .into_iter()
+                .find(|c| c.miner == miner && c.address.as_deref() == address)
+                .map(|c| (c.accepted, c.rejected))
+        };
+        assert_eq!(Some((1, 1)), counts("alice", Some("address-a")));
+        assert_eq!(Some((0, 1)), counts("alice", None));
+        assert_eq!(Some((1, 0)), counts("bob", None));
+        assert_eq!(None, counts("bob", Some("address-b")));
+        assert_eq!(4, log.recent().count());
+        assert_eq!((2, 2), log.totals());
+    }
+
+    #[tokio::test]
+    async fn submissions_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mining.jsonl");
+        let keys = BTreeMap::from([
+            ("alice".to_string(), "alice-0123456789abcdef".to_string()),
+            ("bob".to_string(), "bob-0123456789abcdef".to_string()),
+        ]);
+
+        let gateway =
+            MiningGateway::new(keys, quota(), SubmissionLog::open(Some(&path)).unwrap()).unwrap();
+        gateway.log.write().await.issue(id(1), "alice", "address-a");
+        gateway.submit("alice", id(1), true).await.unwrap();
+        gateway.submit("bob", id(2), false).await.unwrap();
+
+        let log = gateway.log.read().await;
+        let reloaded = SubmissionLog::open(Some(&path)).unwrap();
+        assert_eq!(log.counts(), reloaded.counts());
+        assert_eq!(
+            log.recent().collect::<Vec<_>>(),
+            reloaded.recent().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn each_miner_has_its_own_rate_limit() {
+        let keys = BTreeMap::from([
+            ("alice".to_string(), "alice-0123456789abcdef".to_string()),
+            ("bob".to_string(), "bob-0123456789abcdef".to_string()),
+        ]);
+        let gateway = MiningGateway::new(keys, quota(), SubmissionLog::default()).unwrap();
+        let now = Instant::now();
+
+        assert!(gateway.check_rate_limit("alice", now).is_ok());
+        assert!(gateway.check_rate_limit("alice", now).is_ok());
+        assert!(matches!(
+            gateway.check_rate_limit("alice", now),
+            Err(AuthorizeError::Limited { .. })
+        ));
+        assert!(gateway.check_rate_limit("bob", now).is_ok());
+        assert!(gateway
+            .check_rate_limit("alice", now + Duration::from_secs(1))
+            .is_ok());
+    }
+
+    #[test]
+    fn api_keys_must_be_long_and_distinct() {
+        let keys = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(miner, key)| (miner.to_string(), key.to_string()))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let long_key = "0123456789abcdef";
+
+        let gateway = MiningGateway::new(
+            keys(&[("alice", long_key)]),
+            quota(),
+            SubmissionLog::default(),
+        )
+        .unwrap();
+        assert_eq!(Some("alice"), gateway.miner(&ApiKey(long_key.to_string())));
+        assert_eq!(None, gateway.miner(&ApiKey("guess".to_string())));
+
+        assert!(MiningGateway::new(
+            keys(&[("alice", "short")]),
+            quota(),
+            SubmissionLog::default()
+        )
+        .is_err());
+        assert!(MiningGateway::new(
+            keys(&[("alice", long_key), ("bob", long_key)]),
+            quota(),
+            SubmissionLog::default()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,406 @@
+//! An in-memory chain that answers neptune-core's RPC requests.
+//!
+//! [`MockChain`] serves the requests the explorer makes from blocks held in
+//! memory, consistently: a block found by height has the digest it reports,
+//! its announcements and outputs match its counts, and every output has an
+//! AOCL leaf index. Tests script a chain through
+//! [`FakeNode`](crate::fake_node::FakeNode); with the `mock` feature,
+//! [`MockChain::generate`] grows a synthetic one from a seed, for demos and
+//! development without a node.
+
+use std::io;
+
+use neptune_cash::api::export::AdditionRecord;
+use neptune_cash::api::export::Announcement;
+use neptune_cash::api::export::AnnouncementFlag;
+use neptune_cash::api::export::Network;
+use neptune_cash::application::rpc::server::error::RpcError;
+use neptune_cash::application::rpc::server::RPCRequest;
+use neptune_cash::application::rpc::server::RPCResponse;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelectorLiteral;
+use neptune_cash::protocol::consensus::transaction::transaction_kernel::TransactionKernel;
+use tarpc::ServerError;
+
+/// A block of the chain.
+#[derive(Debug, Clone)]
+pub struct MockBlock {
+    pub info: BlockInfo,
+    pub announcements: Vec<Announcement>,
+
+    /// Outputs, in the order they were added to the AOCL.
+    pub outputs: Vec<AdditionRecord>,
+}
+
+/// The chain and mempool of a node.
+#[derive(Debug, Default)]
+pub struct MockChain {
+    pub network: Network,
+
+    /// The canonical chain, by height.
+    pub blocks: Vec<MockBlock>,
+
+    /// Transactions in the mempool.
+    pub mempool: Vec<TransactionKernel>,
+
+    /// Whether the node runs with `--utxo-index`.
+    pub utxo_index: bool,
+}
+
+impl MockChain {
+    fn block(&self, selector: BlockSelector) -> Option<&MockBlock> {
+        match selector {
+            BlockSelector::Special(BlockSelectorLiteral::Genesis) => self.blocks.first(),
+            BlockSelector::Special(BlockSelectorLiteral::Tip) => self.blocks.last(),
+            BlockSelector::Digest(digest) => self.blocks.iter().find(|b| b.info.digest == digest),
+            BlockSelector::Height(height) => self.blocks.get(u64::from(height) as usize),
+        }
+    }
+
+    /// All outputs of the chain with their AOCL leaf indices.
+    fn aocl(&self) -> impl Iterator<Item = (u64, &MockBlock, AdditionRecord)> {
+        self.blocks
+            .iter()
+            .flat_map(|block| block.outputs.iter().map(move |output| (block, *output)))
+            .enumerate()
+            .map(|(leaf_index, (block, output))| (leaf_index as u64, block, output))
+    }
+
+    /// The node's response to `request`. Methods the explorer does not use
+    /// are answered with a server error.
+    pub fn answer(&self, request: RPCRequest) -> Result<RPCResponse, ServerError> {
+        let response = match request {
+            RPCRequest::Network {} => RPCResponse::Network(Ok(self.network)),
+            RPCRequest::BlockHeight { .. } => {
+                let tip = self
+                    .blocks
+                    .last()
+                    .map(|b| b.info.height)
+                    .unwrap_or_default();
+                RPCResponse::BlockHeight(Ok(tip))
+            }
+            RPCRequest::BlockDigest { block_selector, .. } => {
+                RPCResponse::BlockDigest(Ok(self.block(block_selector).map(|b| b.info.digest)))
+            }
+            RPCRequest::BlockInfo { block_selector, .. } => {
+                RPCResponse::BlockInfo(Ok(self.block(block_selector).map(|b| b.info.clone())))
+            }
+            RPCRequest::AnnouncementsInBlock { block_selector, .. } => {
+                RPCResponse::AnnouncementsInBlock(Ok(self
+                    .block(block_selector)
+                    .map(|b| b.announcements.clone())))
+            }
+            RPCRequest::AdditionRecordIndicesForBlock { block_selector, .. } => {
+                let digest = self.block(block_selector).map(|b| b.info.digest);
+                let indices = self
+                    .aocl()
+                    .filter(|(_, block, _)| Some(block.info.digest) == digest)
+                    .map(|(leaf_index, _, output)| (output, Some(leaf_index)))
+                    .collect();
+                RPCResponse::AdditionRecordIndicesForBlock(Ok(indices))
+            }
+            RPCRequest::UtxoDigest { leaf_index, .. } => {
+                let digest = self
+                    .aocl()
+                    .find(|(index, _, _)| *index == leaf_index)
+                    .map(|(_, _, output)| output.canonical_commitment);
+                RPCResponse::UtxoDigest(Ok(digest))
+            }
+            RPCRequest::BlockHeightsByAnnouncementFlags {
+                announcement_flags, ..
+            } => {
+                let heights = if self.utxo_index {
+                    Ok(self
+                        .blocks
+                        .iter()
+                        .filter(|b| {
+                            b.announcements
+                                .iter()
+                                .any(|a| announcement_flags.iter().any(|f| is_flagged(a, f)))
+                        })
+                        .map(|b| b.info.height)
+                        .collect())
+                } else {
+                    Err(RpcError::UtxoIndexNotPresent)
+                };
+                RPCResponse::BlockHeightsByAnnouncementFlags(heights)
+            }
+            RPCRequest::UtxoOriginBlock {
+                addition_record, ..
+            } => {
+                let origin = self
+                    .aocl()
+                    .find(|(_, _, output)| *output == addition_record)
+                    .map(|(_, block, _)| block.info.digest);
+                RPCResponse::UtxoOriginBlock(Ok(origin))
+            }
+            RPCRequest::MempoolTxIds { .. } => {
+                RPCResponse::MempoolTxIds(Ok(self.mempool.iter().map(|k| k.txid()).collect()))
+            }
+            RPCRequest::MempoolTxKernel { tx_kernel_id, .. } => {
+                let kernel = self.mempool.iter().find(|k| k.txid() == tx_kernel_id);
+                RPCResponse::MempoolTxKernel(Ok(kernel.cloned()))
+            }
+            RPCRequest::BestProposal { .. } => RPCResponse::BestProposal(Ok(None)),
+            RPCRequest::PowPuzzleExternalKey { .. } => RPCResponse::PowPuzzleExternalKey(Ok(None)),
+            RPCRequest::ProvidePowSolution { .. } => RPCResponse::ProvidePowSolution(Ok(false)),
+            other => {
+                return Err(ServerError::new(
+                    io::ErrorKind::Unsupported,
+                    format!("mock chain does not answer {other:?}"),
+                ))
+            }
+        };
+        Ok(response)
+    }
+}
+
+/// Whether `announcement` is marked with `flag`, i.e. starts with its flag and
+/// receiver identifier.
+fn is_flagged(announcement: &Announcement, flag: &AnnouncementFlag) -> bool {
+    announcement.message.first() == Some(&flag.flag)
+        && announcement.message.get(1) == Some(&flag.receiver_id)
+}
+
+#[cfg(feature = "mock")]
+mod generate {
+    use neptune_cash::api::export::NativeCurrencyAmount;
+    use neptune_cash::api::export::Timestamp;
+    use neptune_cash::api::export::TransparentInput;
+    use neptune_cash::api::export::TransparentTransactionInfo;
+    use neptune_cash::api::export::UtxoTriple;
+    use neptune_cash::prelude::tasm_lib::prelude::Digest;
+    use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+    use neptune_cash::protocol::consensus::block::block_height::BlockHeight;
+    use neptune_cash::protocol::consensus::block::difficulty_control::Difficulty;
+    use neptune_cash::protocol::consensus::block::difficulty_control::ProofOfWork;
+    use neptune_cash::protocol::consensus::block::Block;
+    use neptune_cash::protocol::consensus::transaction::transaction_kernel::TransactionKernelProxy;
+    use num_traits::Zero;
+    use rand::rngs::StdRng;
+    use rand::Rng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// The transactions of a block being generated.
+    #[derive(Default)]
+    struct BlockBody {
+        announcements: Vec<Announcement>,
+        outputs: Vec<AdditionRecord>,
+        num_inputs: usize,
+        fee: NativeCurrencyAmount,
+    }
+
+    impl MockChain {
+        /// A chain of blocks `0..=tip` on `network`, with a mempool, that is
+        /// the same for the same `seed`.
+        ///
+        /// Blocks link to their predecessors, and their timestamps and
+        /// proof-of-work increase. About half the announcements are
+        /// transparent transactions, whose outputs are in their block and
+        /// whose inputs spend earlier transparent outputs; the others are
+        /// random messages. The node maintains a UTXO index.
+        pub fn generate(seed: [u8; 32], tip: u64, network: Network) -> Self {
+            let mut rng = StdRng::from_seed(seed);
+
+            // transparent outputs not spent yet, ready to be spent
+            let mut unspent: Vec<TransparentInput> = vec![];
+            let mut num_leafs = 0u64;
+            let mut timestamp = network.launch_date();
+            let mut cumulative_proof_of_work = ProofOfWork::zero();
+            let mut prev_block_digest = Digest::default();
+            let mut blocks = vec![];
+
+            for height in 0..=tip {
+                let mut body = BlockBody::default();
+                for _ in 0..rng.random_range(0..4) {
+                    if rng.random_bool(0.5) {
+                        let num_inputs = rng.random_range(0..=unspent.len().min(3));
+                        let inputs = (0..num_inputs)
+                            .map(|_| unspent.swap_remove(rng.random_range(0..unspent.len())))
+                            .collect::<Vec<_>>();
+                        let mut outputs = vec![];
+                        for _ in 0..rng.random_range(1..4) {
+                            let receiver_preimage: Digest = rng.random();
+                            let output = UtxoTriple {
+                                utxo: rng.random(),
+                                sender_randomness: rng.random(),
+                                receiver_digest: receiver_preimage.hash(),
+                            };
+                            unspent.push(TransparentInput {
+                                utxo: output.utxo.clone(),
+                                aocl_leaf_index: num_leafs + body.outputs.len() as u64,
+                                sender_randomness: output.sender_randomness,
+                                receiver_preimage,
+                            });
+                            body.outputs.push(output.addition_record());
+                            outputs.push(output);
+                        }
+                        body.num_inputs += inputs.len();
+                        body.announcements.push(
+                            TransparentTransactionInfo::new(inputs, outputs).to_announcement(),
+                        );
+                    } else {
+                        let message = (0..rng.random_range(2..64))
+                            .map(|_| rng.random::<BFieldElement>())
+                            .collect();
+                        body.announcements.push(Announcement::new(message));
+                    }
+                }
+
+                // shielded transactions
+                for _ in 0..rng.random_range(1..4) {
+                    body.num_inputs += rng.random_range(0..3);
+                    body.outputs.extend(
+                        (0..rng.random_range(1..3)).map(|_| AdditionRecord::new(rng.random())),
+                    );
+                    body.fee += random_fee(&mut rng);
+                }
+                num_leafs += body.outputs.len() as u64;
+
+                if height > 0 {
+                    timestamp += Timestamp::seconds(rng.random_range(60..1200));
+                }
+                let difficulty = Difficulty::from(rng.random_range(1_000_000u32..2_000_000));
+                cumulative_proof_of_work = cumulative_proof_of_work + difficulty;
+                let digest: Digest = rng.random();
+
+                let block_height = BlockHeight::from(height);
+                blocks.push(MockBlock {
+                    info: BlockInfo {
+                        height: block_height,
+                        size: rng.random_range(10_000..2_000_000),
+                        digest,
+                        prev_block_digest,
+                        timestamp,
+                        cumulative_proof_of_work,
+                        difficulty,
+                        num_inputs: body.num_inputs,
+                        num_outputs: body.outputs.len(),
+                        num_announcements: body.announcements.len(),
+                        coinbase_amount: Block::block_subsidy(block_height) + body.fee,
+                        fee: body.fee,
+                        is_genesis: height == 0,
+                        is_tip: height == tip,
+                        is_canonical: true,
+                        sibling_blocks: vec![],
+                        lustration_status: None,
+                    },
+                    announcements: body.announcements,
+                    outputs: body.outputs,
+                });
+                prev_block_digest = digest;
+            }
+
+            let mempool = (0..rng.random_range(1..6))
+                .map(|_| {
+                    TransactionKernelProxy {
+                        inputs: vec![],
+                        outputs: (0..rng.random_range(1..4))
+                            .map(|_| AdditionRecord::new(rng.random()))
+                            .collect(),
+                        announcements: vec![],
+                        fee: random_fee(&mut rng),
+                        coinbase: None,
+                        timestamp: timestamp + Timestamp::seconds(rng.random_range(1..600)),
+                        mutator_set_hash: rng.random(),
+                        merge_bit: false,
+                    }
+                    .into_kernel()
+                })
+                .collect();
+
+            Self {
+                network,
+                blocks,
+                mempool,
+                utxo_index: true,
+            }
+        }
+    }
+
+    /// A fee of up to 0.1 coins.
+    fn random_fee(rng: &mut StdRng) -> NativeCurrencyAmount {
+        let one_coin = NativeCurrencyAmount::coins(1).to_nau();
+        NativeCurrencyAmount::from_nau(rng.random_range(0..one_coin / 10))
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::collections::HashSet;
+
+    use neptune_cash::api::export::TransparentTransactionInfo;
+    use neptune_cash::protocol::consensus::block::block_height::BlockHeight;
+
+    use super::*;
+
+    fn chain(seed: u8) -> MockChain {
+        MockChain::generate([seed; 32], 200, Network::Main)
+    }
+
+    #[test]
+    fn same_seed_same_chain() {
+        let (a, b) = (chain(1), chain(1));
+        assert_eq!(a.blocks.len(), b.blocks.len());
+        for (a, b) in a.blocks.iter().zip(&b.blocks) {
+            assert_eq!(a.info, b.info);
+            assert_eq!(a.announcements, b.announcements);
+        }
+        assert_ne!(a.blocks[5].info.digest, chain(2).blocks[5].info.digest);
+    }
+
+    #[test]
+    fn blocks_are_linked_and_consistent() {
+        let chain = chain(3);
+        for pair in chain.blocks.windows(2) {
+            let (prev, block) = (&pair[0].info, &pair[1].info);
+            assert_eq!(prev.height.next(), block.height);
+            assert_eq!(prev.digest, block.prev_block_digest);
+            assert!(prev.timestamp < block.timestamp);
+            assert!(prev.cumulative_proof_of_work < block.cumulative_proof_of_work);
+        }
+        for block in &chain.blocks {
+            assert_eq!(block.announcements.len(), block.info.num_announcements);
+            assert_eq!(block.outputs.len(), block.info.num_outputs);
+            let by_digest = chain.block(BlockSelector::Digest(block.info.digest));
+            assert_eq!(Some(block.info.height), by_digest.map(|b| b.info.height));
+        }
+        assert!(chain.blocks.last().unwrap().info.is_tip);
+        assert!(!chain.mempool.is_empty());
+    }
+
+    #[test]
+    fn transparent_transactions_spend_and_create_outputs_of_the_chain() {
+        let chain = chain(4);
+        let aocl = chain
+            .aocl()
+            .map(|(leaf_index, block, output)| (output, (leaf_index, block.info.height)))
+            .collect::<std::collections::HashMap<_, _>>();
+
+        let mut num_transparent = 0;
+        let mut spent = HashSet::new();
+        for block in &chain.blocks {
+            let height: BlockHeight = block.info.height;
+            for announcement in &block.announcements {
+                let Ok(tx) = TransparentTransactionInfo::try_from_announcement(announcement) else {
+                    continue;
+                };
+                num_transparent += 1;
+                for output in &tx.outputs {
+                    assert_eq!(height, aocl[&output.addition_record()].1);
+                }
+                for input in &tx.inputs {
+                    let (leaf_index, origin) = aocl[&input.addition_record()];
+                    assert_eq!(input.aocl_leaf_index, leaf_index);
+                    assert!(origin <= height);
+                    assert!(spent.insert(leaf_index), "double spend");
+                }
+            }
+        }
+        assert!(num_transparent > 0);
+        assert!(!spent.is_empty());
+    }
+}
@@ -1,8 +1,31 @@
+pub mod address;
+pub mod announcement_search;
 pub mod announcement_selector;
 pub mod announcement_type;
 pub mod app_state;
+pub mod assets;
+pub mod block_at_time;
 pub mod block_selector_extended;
+pub mod burn_registry;
+pub mod chain_stats;
 pub mod config;
+pub mod download_format;
+pub mod emission;
+pub mod forks;
 pub mod height_or_digest;
+pub mod height_range;
+pub mod jsonl;
+pub mod mining_gateway;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock_chain;
+pub mod node_error;
 pub mod output_status;
+pub mod rate_limit;
+pub mod rpc_fixtures;
+pub mod rpc_limits;
+pub mod single_flight;
+pub mod supply_cache;
+pub mod template_overrides;
+pub mod theme;
+pub mod tip_watcher;
 pub mod transparent_utxo_tuple;
@@ -0,0 +1,37 @@
+//! Failures of calls into neptune-core, shared by the models that make them.
+
+use neptune_cash::application::rpc::server::error::RpcError;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use tarpc::client::RpcError as TransportError;
+use tarpc::context;
+
+use crate::model::app_state::AppStateInner;
+
+/// Error of a call into neptune-core, preserving the distinction between
+/// transport and RPC-method failures. See
+/// [`node_err`](crate::http_util::node_err) for the HTTP responses.
+#[derive(Debug, thiserror::Error)]
+pub enum NodeError {
+    /// Could not reach / talk to neptune-core (tarpc transport).
+    #[error("could not reach neptune-core: {0}")]
+    Transport(#[from] TransportError),
+    /// neptune-core rejected or failed the RPC call.
+    #[error("neptune-core failed the request: {0:?}")]
+    Method(#[from] RpcError),
+    /// neptune-core does not know a block that always exists, such as the tip
+    /// or the genesis block.
+    #[error("neptune-core does not know the {0} block")]
+    MissingBlock(&'static str),
+}
+
+/// The [`BlockInfo`] of the block `block_selector` names, if the node knows it.
+pub async fn fetch_block_info(
+    state: &AppStateInner,
+    block_selector: BlockSelector,
+) -> Result<Option<BlockInfo>, NodeError> {
+    Ok(state
+        .rpc_client
+        .block_info(context::current(), state.token(), block_selector)
+        .await??)
+}
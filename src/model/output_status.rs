@@ -209,8 +209,7 @@ pub async fn resolve_output_status(
     let token = state.token();
 
     // 1. MINED (canonical) — utxo_origin_block returns the canonical block
-    //    digest that created this output, or None. Reached via Deref to the
-    //    underlying RPCClient (AuthenticatedClient adds no wrapper for it).
+    //    digest that created this output, or None.
     let origin_digest = state
         .rpc_client
         .utxo_origin_block(context::current(), token, addition_record, None)
@@ -0,0 +1,483 @@
+//! Per-client rate limiting by route class.
+//!
+//! Every request is counted against the quota of its [`RouteClass`] for the
+//! client's IP address. The quotas, the reverse proxies whose forwarding
+//! headers are believed, and lists of clients that are never limited or always
+//! rejected form a [`RateLimitPolicy`], read from `--rate-limit-file`. Without
+//! it, every class gets [`DEFAULT_QUOTA`] and forwarding headers are ignored.
+//!
+//! Every [`CLEAN_INTERVAL`], the state of clients that are back to their full
+//! quota is dropped, so that the state does not grow with every client ever
+//! seen.
+
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use anyhow::Context;
+use axum::extract::ConnectInfo;
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::header::RETRY_AFTER;
+use axum::http::HeaderMap;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum_gcra::gcra;
+use ipnet::IpNet;
+use serde::Deserialize;
+
+/// The quota of every route class unless configured otherwise: one request
+/// every 10 milliseconds.
+pub const DEFAULT_QUOTA: QuotaConfig = QuotaConfig {
+    interval_ms: 10,
+    burst: 1,
+};
+
+/// Interval at which the state of clients back to their full quota is dropped.
+pub const CLEAN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Routes that share a quota, by how much they cost the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteClass {
+    /// HTML pages and static files.
+    Html,
+    /// JSON and plain-text endpoints.
+    Rpc,
+    /// Pages and endpoints that may make many node calls per request: output
+    /// status tracking, which may scan the mempool, block comparison,
+    /// announcement search, and the supply at a point in time.
+    Expensive,
+    /// The mining gateway's proof-of-work endpoints.
+    Pow,
+}
+
+impl RouteClass {
+    /// The class of requests for `path`.
+    pub fn of(path: &str) -> Self {
+        if path.starts_with("/rpc/pow_puzzle/") || path == "/rpc/provide_pow_solution" {
+            Self::Pow
+        } else if [
+            "/rpc/output_status/",
+            "/output/",
+            "/compare/",
+            "/rpc/announcements/search",
+            "/announcements/search",
+            "/rpc/supply/at/",
+        ]
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+        {
+            Self::Expensive
+        } else if path.starts_with("/rpc/") || path.starts_with("/api/") {
+            Self::Rpc
+        } else {
+            Self::Html
+        }
+    }
+}
+
+/// A quota per client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuotaConfig {
+    /// Interval in milliseconds at which a client regains a request.
+    pub interval_ms: u64,
+
+    /// Number of requests a client may make at once.
+    #[serde(default = "default_burst")]
+    pub burst: u64,
+}
+
+fn default_burst() -> u64 {
+    1
+}
+
+impl QuotaConfig {
+    fn quota(&self) -> gcra::Quota {
+        gcra::Quota::new(
+            Duration::from_millis(self.interval_ms),
+            self.burst.try_into().unwrap_or(std::num::NonZeroU64::MIN),
+        )
+    }
+}
+
+/// Rate limits, as read from `--rate-limit-file`. Omitted fields take their
+/// defaults; see `data/rate_limit.example.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct RateLimitPolicy {
+    pub html: QuotaConfig,
+    pub rpc: QuotaConfig,
+    pub expensive: QuotaConfig,
+    pub pow: QuotaConfig,
+
+    /// Reverse proxies whose `X-Forwarded-For` and `X-Real-IP` headers name
+    /// the client. Requests from other addresses are attributed to the peer.
+    pub trusted_proxies: Vec<IpNet>,
+
+    /// Clients that are never rate-limited.
+    pub allow: Vec<IpNet>,
+
+    /// Clients whose requests are always rejected, even if also allowed.
+    pub deny: Vec<IpNet>,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        Self {
+            html: DEFAULT_QUOTA,
+            rpc: DEFAULT_QUOTA,
+            expensive: DEFAULT_QUOTA,
+            pow: DEFAULT_QUOTA,
+            trusted_proxies: vec![],
+            allow: vec![],
+            deny: vec![],
+        }
+    }
+}
+
+impl RateLimitPolicy {
+    pub fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rate-limit policy {}", path.display()))?;
+        Self::from_json(&json)
+            .with_context(|| format!("Invalid rate-limit policy {}", path.display()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, anyhow::Error> {
+        let policy: Self = serde_json::from_str(json)?;
+        for (name, quota) in [
+            ("html", policy.html),
+            ("rpc", policy.rpc),
+            ("expensive", policy.expensive),
+            ("pow", policy.pow),
+        ] {
+            anyhow::ensure!(
+                quota.interval_ms > 0 && quota.burst > 0,
+                "{name} quota must have a positive interval and burst"
+            );
+        }
+        Ok(policy)
+    }
+
+    pub fn quota(&self, class: RouteClass) -> QuotaConfig {
+        match class {
+            RouteClass::Html => self.html,
+            RouteClass::Rpc => self.rpc,
+            RouteClass::Expensive => self.expensive,
+            RouteClass::Pow => self.pow,
+        }
+    }
+
+    /// The client a request from `peer` is made on behalf of.
+    ///
+    /// For a trusted proxy, that is the last address in `X-Forwarded-For` that
+    /// is not itself a trusted proxy, or, without that header, the address in
+    /// `X-Real-IP`. Addresses further left in `X-Forwarded-For` are supplied by
+    /// the client and cannot be believed.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !contains(&self.trusted_proxies, peer) {
+            return peer;
+        }
+
+        let forwarded = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        if forwarded.is_empty() {
+            return headers
+                .get("x-real-ip")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .unwrap_or(peer);
+        }
+
+        let mut client = peer;
+        for hop in forwarded.into_iter().rev() {
+            let Ok(ip) = hop.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip;
+            if !contains(&self.trusted_proxies, ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+fn contains(networks: &[IpNet], ip: IpAddr) -> bool {
+    networks.iter().any(|network| network.contains(&ip))
+}
+
+/// Why a request was not let through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// The client is on the deny list.
+    Denied,
+    /// The client exceeded the quota of the route class.
+    Limited { retry_after: Duration },
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        match self {
+            Rejection::Denied => (StatusCode::FORBIDDEN, "Forbidden").into_response(),
+            Rejection::Limited { retry_after } => {
+                // Retry-After is in whole seconds; round up so that a client
+                // honoring it is not rejected again.
+                let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(RETRY_AFTER, seconds.to_string())],
+                    format!(
+                        "rate limit exceeded, retry in {:.3} seconds",
+                        retry_after.as_secs_f64()
+                    ),
+                )
+                    .into_response()
+            }
+        }
+    }
+}
+
+/// A [`RateLimitPolicy`] and the state of every client's quotas.
+pub struct RateLimiter {
+    policy: RateLimitPolicy,
+    limits: gcra::RateLimiter<(RouteClass, IpAddr)>,
+
+    /// When the state of clients back to their full quota was last dropped.
+    last_clean: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(policy: RateLimitPolicy) -> Self {
+        Self {
+            policy,
+            limits: gcra::RateLimiter::default(),
+            last_clean: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn policy(&self) -> &RateLimitPolicy {
+        &self.policy
+    }
+
+    /// Count a request of `class` by `client` at `now` against its quota.
+    pub fn check(&self, class: RouteClass, client: IpAddr, now: Instant) -> Result<(), Rejection> {
+        if contains(&self.policy.deny, client) {
+            return Err(Rejection::Denied);
+        }
+        if contains(&self.policy.allow, client) {
+            return Ok(());
+        }
+        self.clean_if_due(now);
+        self.limits
+            .req_sync((class, client), self.policy.quota(class).quota(), now)
+            .map_err(|e| Rejection::Limited {
+                retry_after: e.as_duration(),
+            })
+    }
+
+    /// Drop the state of clients back to their full quota, which is the same
+    /// as having none, if the last time was [`CLEAN_INTERVAL`] ago. Another
+    /// request cleaning at the same time is not waited for.
+    fn clean_if_due(&self, now: Instant) {
+        let Ok(mut last_clean) = self.last_clean.try_lock() else {
+            return;
+        };
+        if now.saturating_duration_since(*last_clean) < CLEAN_INTERVAL {
+            return;
+        }
+        *last_clean = now;
+        drop(last_clean);
+        self.limits.clean_sync(now);
+    }
+}
+
+/// Middleware applying the [`RateLimiter`] to every route.
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let now = Instant::now();
+
+    #[cfg(feature = "attacks")]
+    {
+        // Lets the attack binaries pose as many clients.
+        let ip_override = request
+            .headers()
+            .get("X-Real-IP-Override")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<IpAddr>().ok());
+        if let Some(ip) = ip_override {
+            let class = RouteClass::of(request.uri().path());
+            return match limiter.check(class, ip, now) {
+                Ok(()) => next.run(request).await,
+                Err(rejection) => rejection.into_response(),
+            };
+        }
+    }
+
+    let Some(peer) = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|connect_info| connect_info.0.ip())
+    else {
+        return (StatusCode::BAD_REQUEST, "No IP found").into_response();
+    };
+    let client = limiter.policy.client_ip(peer, request.headers());
+
+    match limiter.check(RouteClass::of(request.uri().path()), client, now) {
+        Ok(()) => next.run(request).await,
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn policy(json: &str) -> RateLimitPolicy {
+        RateLimitPolicy::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn routes_are_classified_by_cost() {
+        assert_eq!(RouteClass::Html, RouteClass::of("/block/tip"));
+        assert_eq!(RouteClass::Html, RouteClass::of("/css/styles.css"));
+        assert_eq!(RouteClass::Rpc, RouteClass::of("/rpc/block_info/tip"));
+        assert_eq!(RouteClass::Rpc, RouteClass::of("/api/circulating"));
+        assert_eq!(RouteClass::Expensive, RouteClass::of("/output/00ff"));
+        assert_eq!(
+            RouteClass::Expensive,
+            RouteClass::of("/rpc/output_status/00ff")
+        );
+        assert_eq!(
+            RouteClass::Pow,
+            RouteClass::of("/rpc/pow_puzzle/nolgam1x/wait")
+        );
+        assert_eq!(RouteClass::Pow, RouteClass::of("/rpc/provide_pow_solution"));
+        for path in [
+            "/compare/1/2",
+            "/announcements/search?flag=1&receiver_id=2",
+            "/rpc/announcements/search",
+            "/rpc/supply/at/1800",
+        ] {
+            assert_eq!(RouteClass::Expensive, RouteClass::of(path), "{path}");
+        }
+        assert_eq!(RouteClass::Rpc, RouteClass::of("/rpc/supply/height/5"));
+    }
+
+    #[test]
+    fn policy_defaults_omitted_fields_and_rejects_unknown_ones() {
+        let parsed = policy(r#"{"expensive": {"interval_ms": 1000, "burst": 3}}"#);
+        assert_eq!(DEFAULT_QUOTA, parsed.html);
+        assert_eq!(
+            QuotaConfig {
+                interval_ms: 1000,
+                burst: 3
+            },
+            parsed.expensive
+        );
+        assert_eq!(RateLimitPolicy::default(), policy("{}"));
+
+        assert!(RateLimitPolicy::from_json(r#"{"htlm": {"interval_ms": 1}}"#).is_err());
+        assert!(RateLimitPolicy::from_json(r#"{"rpc": {"interval_ms": 0}}"#).is_err());
+    }
+
+    #[test]
+    fn forwarding_headers_are_believed_from_trusted_proxies_only() {
+        let policy = policy(r#"{"trusted_proxies": ["10.0.0.0/8", "::1/128"]}"#);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            "1.2.3.4, 5.6.7.8, 10.0.0.2".parse().unwrap(),
+        );
+
+        assert_eq!(ip("5.6.7.8"), policy.client_ip(ip("10.0.0.1"), &headers));
+        assert_eq!(ip("9.9.9.9"), policy.client_ip(ip("9.9.9.9"), &headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", "5.6.7.8".parse().unwrap());
+        assert_eq!(ip("5.6.7.8"), policy.client_ip(ip("::1"), &headers));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "garbage".parse().unwrap());
+        assert_eq!(ip("10.0.0.1"), policy.client_ip(ip("10.0.0.1"), &headers));
+    }
+
+    #[test]
+    fn quotas_are_per_class_and_client() {
+        let limiter = RateLimiter::new(policy(
+            r#"{
+                "rpc": {"interval_ms": 60000, "burst": 2},
+                "allow": ["192.168.0.0/16"],
+                "deny": ["192.168.6.6/32"]
+            }"#,
+        ));
+        let now = Instant::now();
+        let client = ip("1.2.3.4");
+
+        assert_eq!(Ok(()), limiter.check(RouteClass::Rpc, client, now));
+        assert_eq!(Ok(()), limiter.check(RouteClass::Rpc, client, now));
+        assert!(matches!(
+            limiter.check(RouteClass::Rpc, client, now),
+            Err(Rejection::Limited { retry_after }) if retry_after > Duration::from_secs(59)
+        ));
+        assert_eq!(Ok(()), limiter.check(RouteClass::Html, client, now));
+        assert_eq!(Ok(()), limiter.check(RouteClass::Rpc, ip("4.3.2.1"), now));
+
+        for _ in 0..10 {
+            assert_eq!(
+                Ok(()),
+                limiter.check(RouteClass::Rpc, ip("192.168.1.1"), now)
+            );
+        }
+        assert_eq!(
+            Err(Rejection::Denied),
+            limiter.check(RouteClass::Html, ip("192.168.6.6"), now)
+        );
+    }
+
+    #[test]
+    fn clients_back_to_their_full_quota_are_forgotten() {
+        let limiter = RateLimiter::new(policy(r#"{"rpc": {"interval_ms": 10000}}"#));
+        let now = Instant::now();
+        let (idle, busy) = (ip("1.2.3.4"), ip("5.6.7.8"));
+
+        limiter.check(RouteClass::Rpc, idle, now).unwrap();
+        limiter
+            .check(RouteClass::Rpc, busy, now + Duration::from_secs(55))
+            .unwrap();
+        limiter
+            .check(RouteClass::Rpc, ip("9.9.9.9"), now + CLEAN_INTERVAL)
+            .unwrap();
+        assert!(!limiter.limits.reset_sync(&(RouteClass::Rpc, idle)));
+        assert!(limiter.limits.reset_sync(&(RouteClass::Rpc, busy)));
+    }
+
+    #[test]
+    fn rejections_carry_retry_after_in_whole_seconds() {
+        let response = Rejection::Limited {
+            retry_after: Duration::from_millis(1500),
+        }
+        .into_response();
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, response.status());
+        assert_eq!("2", response.headers()[RETRY_AFTER]);
+    }
+}
@@ -0,0 +1,245 @@
+//! Recording neptune-core's answers to fixtures, and replaying them without a
+//! node.
+//!
+//! With `--rpc-record-dir`, every RPC request the explorer makes is recorded
+//! with the node's response, the first time it is made, to one JSON-lines file
+//! per method in that directory. With `--rpc-replay-dir`, the explorer serves
+//! those responses instead of connecting to a node, so that the pages render
+//! exactly as they did while recording. Requests are matched on their method
+//! and arguments; the authentication token is ignored.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use anyhow::Context;
+use futures::FutureExt;
+use neptune_cash::application::rpc::server::RPCRequest;
+use neptune_cash::application::rpc::server::RPCResponse;
+use serde::Deserialize;
+use serde::Serialize;
+use tarpc::client::stub::Stub;
+use tarpc::client::Channel;
+use tarpc::client::RpcError as TransportError;
+use tarpc::context;
+use tarpc::ServerError;
+use tracing::info;
+use tracing::warn;
+
+use crate::model::jsonl;
+#[cfg(feature = "mock")]
+use crate::model::mock_chain::MockChain;
+
+/// One line of a fixture file.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FixtureEntry {
+    /// The request, without token.
+    pub request: serde_json::Value,
+    pub response: serde_json::Value,
+}
+
+/// The key a request is recorded and looked up under: its JSON encoding
+/// without the token, which differs between sessions.
+fn request_key(request: &RPCRequest) -> Result<serde_json::Value, serde_json::Error> {
+    let mut value = serde_json::to_value(request)?;
+    if let Some(arguments) = value
+        .as_object_mut()
+        .and_then(|variant| variant.values_mut().next())
+        .and_then(|arguments| arguments.as_object_mut())
+    {
+        arguments.remove("token");
+    }
+    Ok(value)
+}
+
+/// Appends the first answer to every distinct request to the fixture files in
+/// a directory.
+#[derive(Debug)]
+pub struct FixtureRecorder {
+    dir: PathBuf,
+    recorded: Mutex<HashSet<String>>,
+}
+
+impl FixtureRecorder {
+    /// Record into `dir`, creating it if needed. Requests already recorded
+    /// there are not recorded again.
+    pub fn open(dir: &Path) -> Result<Self, anyhow::Error> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create fixture directory {}", dir.display()))?;
+        let recorded = read_fixtures(dir)?
+            .into_iter()
+            .map(|entry| entry.request.to_string())
+            .collect::<HashSet<_>>();
+        info!(
+            "Recording RPC fixtures to {} ({} already recorded)",
+            dir.display(),
+            recorded.len()
+        );
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            recorded: Mutex::new(recorded),
+        })
+    }
+
+    /// Record `response` to the request with key `request`, unless that
+    /// request was recorded before. The file is written on the blocking
+    /// thread pool, without holding the lock.
+    async fn record(
+        &self,
+        method: &str,
+        request: serde_json::Value,
+        response: &RPCResponse,
+    ) -> Result<(), anyhow::Error> {
+        let entry = FixtureEntry {
+            request,
+            response: serde_json::to_value(response)?,
+        };
+        if !self
+            .recorded
+            .lock()
+            .unwrap()
+            .insert(entry.request.to_string())
+        {
+            return Ok(());
+        }
+
+        let file = self.dir.join(format!("{method}.jsonl"));
+        jsonl::append_blocking(file, vec![entry]).await?;
+        Ok(())
+    }
+}
+
+/// Recorded answers, by request.
+#[derive(Debug, Default)]
+pub struct Fixtures {
+    responses: HashMap<String, serde_json::Value>,
+}
+
+impl Fixtures {
+    /// Load all fixture files in `dir`.
+    pub fn open(dir: &Path) -> Result<Self, anyhow::Error> {
+        let responses = read_fixtures(dir)?
+            .into_iter()
+            .map(|entry| (entry.request.to_string(), entry.response))
+            .collect::<HashMap<_, _>>();
+        anyhow::ensure!(
+            !responses.is_empty(),
+            "No RPC fixtures found in {}",
+            dir.display()
+        );
+        info!(
+            "Replaying {} RPC fixtures from {}",
+            responses.len(),
+            dir.display()
+        );
+        Ok(Self { responses })
+    }
+
+    /// The recorded response to `request`.
+    pub fn replay(&self, request: &RPCRequest) -> Result<RPCResponse, ServerError> {
+        let missing = |detail: String| ServerError::new(io::ErrorKind::NotFound, detail);
+        let key = request_key(request).map_err(|e| missing(e.to_string()))?;
+        let response = self
+            .responses
+            .get(&key.to_string())
+            .ok_or_else(|| missing(format!("no fixture for request {key}")))?;
+        serde_json::from_value(response.clone()).map_err(|e| missing(e.to_string()))
+    }
+}
+
+/// All entries of the `*.jsonl` files in `dir`.
+fn read_fixtures(dir: &Path) -> Result<Vec<FixtureEntry>, anyhow::Error> {
+    let mut entries = vec![];
+    let files = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read fixture directory {}", dir.display()))?;
+    for file in files {
+        let path = file?.path();
+        if path
+            .extension()
+            .is_none_or(|extension| extension != "jsonl")
+        {
+            continue;
+        }
+        entries.extend(
+            jsonl::load::<FixtureEntry>(&path)?
+                .into_iter()
+                .map(|(entry, _)| entry),
+        );
+    }
+    Ok(entries)
+}
+
+/// How [`AuthenticatedClient`](crate::neptune_rpc::AuthenticatedClient) reaches
+/// neptune-core.
+#[derive(Debug, Clone)]
+pub enum NodeStub {
+    /// Calls go to the node.
+    Live(Channel<RPCRequest, RPCResponse>),
+    /// Calls go to the node, and the answers are recorded.
+    Recording(Channel<RPCRequest, RPCResponse>, Arc<FixtureRecorder>),
+    /// Calls are answered from fixtures; there is no node.
+    Replay(Arc<Fixtures>),
+    /// Calls are answered from a generated chain; there is no node.
+    #[cfg(feature = "mock")]
+    Mock(Arc<MockChain>),
+}
+
+impl Stub for NodeStub {
+    type Req = RPCRequest;
+    type Resp = RPCResponse;
+
+    async fn call(
+        &self,
+        ctx: context::Context,
+        request_name: &'static str,
+        request: RPCRequest,
+    ) -> Result<RPCResponse, TransportError> {
+        // calls on the channel are boxed, because their futures are large and
+        // every RPC method embeds this one
+        match self {
+            Self::Live(channel) => channel.call(ctx, request_name, request).boxed().await,
+            Self::Recording(channel, recorder) => {
+                // requests are not `Clone`, so take the key before sending
+                let key = request_key(&request);
+                let response = channel.call(ctx, request_name, request).boxed().await?;
+                let recorded = match key {
+                    Ok(key) => recorder.record(request_name, key, &response).await,
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = recorded {
+                    warn!("Failed to record {request_name} fixture: {e}");
+                }
+                Ok(response)
+            }
+            Self::Replay(fixtures) => Ok(fixtures.replay(&request)?),
+            #[cfg(feature = "mock")]
+            Self::Mock(chain) => Ok(chain.answer(request)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use neptune_cash::application::rpc::auth;
+
+    use super::*;
+
+    #[test]
+    fn requests_are_keyed_without_token() {
+        let request = |cookie: [u8; 32]| RPCRequest::BlockHeight {
+            token: auth::Cookie::from(cookie).into(),
+        };
+        assert_eq!(
+            request_key(&request([0; 32])).unwrap(),
+            request_key(&request([1; 32])).unwrap()
+        );
+        assert_eq!(
+            serde_json::json!({"BlockHeight": {}}),
+            request_key(&request([0; 32])).unwrap()
+        );
+    }
+}
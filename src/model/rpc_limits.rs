@@ -0,0 +1,410 @@
+//! Admission control for calls into neptune-core.
+//!
+//! Every RPC call made through
+//! [`AuthenticatedClient`](crate::neptune_rpc::AuthenticatedClient) belongs to
+//! an [`RpcClass`]. Each class admits a bounded number of concurrent calls and
+//! queues a bounded number more; calls beyond that are shed at once with
+//! [`Overloaded`], which HTTP handlers answer with `503` and `Retry-After`.
+//! Every call, including its time in the queue, must complete within the
+//! class's deadline. The limits are read from `--rpc-limits-file`.
+
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use serde::Deserialize;
+use tarpc::client::RpcError as TransportError;
+use tokio::sync::Semaphore;
+use tokio::sync::SemaphorePermit;
+
+/// How long clients are asked to wait after a call was shed.
+pub const OVERLOADED_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// RPC methods that share a concurrency limit, by how much work they make the
+/// node do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcClass {
+    /// Lookups of the tip, single blocks and UTXOs.
+    Chain,
+    /// Reading all announcements or outputs of a block, or searching an index.
+    Block,
+    /// Reading the mempool.
+    Mempool,
+    /// Proof-of-work puzzles and solutions.
+    Mining,
+}
+
+impl RpcClass {
+    pub const ALL: [RpcClass; 4] = [Self::Chain, Self::Block, Self::Mempool, Self::Mining];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Chain => "chain",
+            Self::Block => "block",
+            Self::Mempool => "mempool",
+            Self::Mining => "mining",
+        }
+    }
+}
+
+/// Limits of one [`RpcClass`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClassLimits {
+    /// Number of calls that may be in flight at once.
+    pub concurrency: usize,
+    /// Number of calls that may wait for one of those to finish.
+    pub queue: usize,
+    /// Milliseconds within which a call, including its wait, must complete.
+    pub deadline_ms: u64,
+}
+
+impl ClassLimits {
+    pub fn deadline(&self) -> Duration {
+        Duration::from_millis(self.deadline_ms)
+    }
+}
+
+/// Limits of all classes, as read from `--rpc-limits-file`. Omitted classes
+/// take their defaults; see `data/rpc_limits.example.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct RpcLimitsPolicy {
+    pub chain: ClassLimits,
+    pub block: ClassLimits,
+    pub mempool: ClassLimits,
+    pub mining: ClassLimits,
+}
+
+impl Default for RpcLimitsPolicy {
+    fn default() -> Self {
+        let limits = |concurrency, queue| ClassLimits {
+            concurrency,
+            queue,
+            // tarpc's default deadline
+            deadline_ms: 10_000,
+        };
+        Self {
+            chain: limits(32, 256),
+            block: limits(8, 64),
+            mempool: limits(2, 16),
+            mining: limits(4, 32),
+        }
+    }
+}
+
+impl RpcLimitsPolicy {
+    pub fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read RPC limits {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("Invalid RPC limits {}", path.display()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, anyhow::Error> {
+        let policy: Self = serde_json::from_str(json)?;
+        for class in RpcClass::ALL {
+            let limits = policy.limits(class);
+            anyhow::ensure!(
+                limits.concurrency > 0 && limits.deadline_ms > 0,
+                "{} limits must have a positive concurrency and deadline",
+                class.name()
+            );
+        }
+        Ok(policy)
+    }
+
+    pub fn limits(&self, class: RpcClass) -> ClassLimits {
+        match class {
+            RpcClass::Chain => self.chain,
+            RpcClass::Block => self.block,
+            RpcClass::Mempool => self.mempool,
+            RpcClass::Mining => self.mining,
+        }
+    }
+}
+
+/// A call shed because its class's queue was full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("too many concurrent {} calls to neptune-core", .0.name())]
+pub struct Overloaded(pub RpcClass);
+
+impl Overloaded {
+    /// The [`Overloaded`] that caused a transport error, if any.
+    pub fn cause_of(e: &TransportError) -> Option<&Self> {
+        match e {
+            TransportError::Send(e) => e.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Counters of one class.
+#[derive(Debug)]
+struct ClassState {
+    limits: ClassLimits,
+    permits: Semaphore,
+    queued: AtomicUsize,
+    admitted: AtomicU64,
+    shed: AtomicU64,
+    timed_out: AtomicU64,
+}
+
+/// Snapshot of the counters of one class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassMetrics {
+    pub class: RpcClass,
+    pub limits: ClassLimits,
+    pub in_flight: usize,
+    pub queued: usize,
+    /// Calls admitted since startup.
+    pub admitted: u64,
+    /// Calls shed because the queue was full.
+    pub shed: u64,
+    /// Calls whose deadline passed while queued.
+    pub timed_out: u64,
+}
+
+/// The semaphores and counters of all classes, shared by every
+/// [`AuthenticatedClient`](crate::neptune_rpc::AuthenticatedClient) across
+/// reconnects.
+#[derive(Debug)]
+pub struct RpcLimiter {
+    classes: [ClassState; 4],
+}
+
+impl RpcLimiter {
+    pub fn new(policy: &RpcLimitsPolicy) -> Self {
+        Self {
+            classes: RpcClass::ALL.map(|class| {
+                let limits = policy.limits(class);
+                ClassState {
+                    limits,
+                    permits: Semaphore::new(limits.concurrency),
+                    queued: AtomicUsize::new(0),
+                    admitted: AtomicU64::new(0),
+                    shed: AtomicU64::new(0),
+                    timed_out: AtomicU64::new(0),
+                }
+            }),
+        }
+    }
+
+    fn state(&self, class: RpcClass) -> &ClassState {
+        &self.classes[class as usize]
+    }
+
+    /// Wait for a slot of `class`, returning it with the deadline of the call.
+    ///
+    /// Fails at once with [`Overloaded`] if the queue is full, or with
+    /// [`TransportError::DeadlineExceeded`] if no slot frees up in time.
+    pub async fn admit(
+        &self,
+        class: RpcClass,
+    ) -> Result<(SemaphorePermit<'_>, SystemTime), TransportError> {
+        let state = self.state(class);
+        let deadline = SystemTime::now() + state.limits.deadline();
+
+        let permit = match state.permits.try_acquire() {
+            Ok(permit) => permit,
+            Err(_) => {
+                let queued = state.queued.fetch_add(1, Ordering::SeqCst);
+                let _dequeue = Dequeue(&state.queued);
+                if queued >= state.limits.queue {
+                    state.shed.fetch_add(1, Ordering::Relaxed);
+                    return Err(TransportError::Send(Box::new(Overloaded(class))));
+                }
+                match tokio::time::timeout(state.limits.deadline(), state.permits.acquire()).await {
+                    Ok(permit) => permit.expect("semaphore is never closed"),
+                    Err(_) => {
+                        state.timed_out.fetch_add(1, Ordering::Relaxed);
+                        return Err(TransportError::DeadlineExceeded);
+                    }
+                }
+            }
+        };
+        state.admitted.fetch_add(1, Ordering::Relaxed);
+        Ok((permit, deadline))
+    }
+
+    pub fn metrics(&self) -> Vec<ClassMetrics> {
+        RpcClass::ALL
+            .into_iter()
+            .map(|class| {
+                let state = self.state(class);
+                ClassMetrics {
+                    class,
+                    limits: state.limits,
+                    in_flight: state.limits.concurrency - state.permits.available_permits(),
+                    queued: state.queued.load(Ordering::SeqCst),
+                    admitted: state.admitted.load(Ordering::Relaxed),
+                    shed: state.shed.load(Ordering::Relaxed),
+                    timed_out: state.timed_out.load(Ordering::Relaxed),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Leaves the queue when dropped, however the wait ends.
+struct Dequeue<'a>(&'a AtomicUsize);
+
+impl Drop for Dequeue<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A Prometheus metric family: name, type, help text, and the value per class.
+type MetricFamily = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&ClassMetrics) -> u64,
+);
+
+/// The metrics in the Prometheus text exposition format.
+pub fn prometheus_metrics(metrics: &[ClassMetrics]) -> String {
+    let families: [MetricFamily; 6] = [
+        (
+            "neptune_explorer_rpc_in_flight",
+            "gauge",
+            "RPC calls to neptune-core in flight.",
+            |m| m.in_flight as u64,
+        ),
+        (
+            "neptune_explorer_rpc_queued",
+            "gauge",
+            "RPC calls waiting for a slot.",
+            |m| m.queued as u64,
+        ),
+        (
+            "neptune_explorer_rpc_concurrency_limit",
+            "gauge",
+            "Maximum number of RPC calls in flight.",
+            |m| m.limits.concurrency as u64,
+        ),
+        (
+            "neptune_explorer_rpc_admitted_total",
+            "counter",
+            "RPC calls admitted.",
+            |m| m.admitted,
+        ),
+        (
+            "neptune_explorer_rpc_shed_total",
+            "counter",
+            "RPC calls rejected because the queue was full.",
+            |m| m.shed,
+        ),
+        (
+            "neptune_explorer_rpc_queue_timeouts_total",
+            "counter",
+            "RPC calls whose deadline passed while queued.",
+            |m| m.timed_out,
+        ),
+    ];
+
+    let mut text = String::new();
+    for (name, kind, help, value) in families {
+        let _ = writeln!(text, "# HELP {name} {help}");
+        let _ = writeln!(text, "# TYPE {name} {kind}");
+        for m in metrics {
+            let _ = writeln!(text, "{name}{{class=\"{}\"}} {}", m.class.name(), value(m));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(concurrency: usize, queue: usize, deadline_ms: u64) -> RpcLimiter {
+        let limits = ClassLimits {
+            concurrency,
+            queue,
+            deadline_ms,
+        };
+        RpcLimiter::new(&RpcLimitsPolicy {
+            chain: limits,
+            ..RpcLimitsPolicy::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn calls_beyond_the_queue_are_shed() {
+        let limiter = limiter(1, 1, 60_000);
+        let (_permit, _) = limiter.admit(RpcClass::Chain).await.unwrap();
+
+        let queued = limiter.admit(RpcClass::Chain);
+        tokio::pin!(queued);
+        assert!(futures_poll_once(queued.as_mut()).await.is_none());
+
+        let shed = limiter.admit(RpcClass::Chain).await.unwrap_err();
+        assert_eq!(
+            Some(&Overloaded(RpcClass::Chain)),
+            Overloaded::cause_of(&shed)
+        );
+
+        let chain = limiter.metrics()[0];
+        assert_eq!(
+            (1, 1, 1, 1),
+            (chain.in_flight, chain.queued, chain.admitted, chain.shed)
+        );
+
+        // Other classes are not affected.
+        assert!(limiter.admit(RpcClass::Block).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn queued_calls_time_out_at_the_deadline() {
+        let limiter = limiter(1, 1, 10);
+        let (_permit, _) = limiter.admit(RpcClass::Chain).await.unwrap();
+
+        let e = limiter.admit(RpcClass::Chain).await.unwrap_err();
+        assert!(matches!(e, TransportError::DeadlineExceeded));
+        assert_eq!(1, limiter.metrics()[0].timed_out);
+        assert_eq!(0, limiter.metrics()[0].queued);
+    }
+
+    #[test]
+    fn policy_defaults_omitted_classes() {
+        let policy = RpcLimitsPolicy::from_json(
+            r#"{"mempool": {"concurrency": 1, "queue": 0, "deadline_ms": 5000}}"#,
+        )
+        .unwrap();
+        assert_eq!(RpcLimitsPolicy::default().chain, policy.chain);
+        assert_eq!(Duration::from_secs(5), policy.mempool.deadline());
+
+        assert!(RpcLimitsPolicy::from_json(
+            r#"{"chain": {"concurrency": 0, "queue": 0, "deadline_ms": 1}}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn metrics_are_labelled_by_class() {
+        let text = prometheus_metrics(&limiter(1, 1, 1).metrics());
+        assert!(text.contains("# TYPE neptune_explorer_rpc_shed_total counter"));
+        assert!(text.contains("neptune_explorer_rpc_queued{class=\"mempool\"} 0"));
+    }
+
+    /// Poll `future` once, returning its output if it is ready.
+    async fn futures_poll_once<F: std::future::Future>(
+        future: std::pin::Pin<&mut F>,
+    ) -> Option<F::Output> {
+        let mut future = Some(future);
+        std::future::poll_fn(|cx| {
+            let output = future.take().unwrap().poll(cx);
+            std::task::Poll::Ready(match output {
+                std::task::Poll::Ready(output) => Some(output),
+                std::task::Poll::Pending => None,
+            })
+        })
+        .await
+    }
+}
@@ -0,0 +1,241 @@
+//! Coalescing of identical concurrent RPC calls.
+//!
+//! When a block lands, many clients ask for the same tip at once. A
+//! [`SingleFlight`] lets the first of them make the call to neptune-core and
+//! hands its result to everyone who asks for the same key while it is in
+//! flight. Once the call completes the key is forgotten, so later requests see
+//! fresh data.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use futures::future::BoxFuture;
+use futures::future::Shared;
+use futures::FutureExt;
+use neptune_cash::application::rpc::server::error::RpcError;
+use neptune_cash::application::rpc::server::RpcResult;
+use tarpc::client::RpcError as TransportError;
+
+use crate::model::rpc_limits::Overloaded;
+
+/// The outcome of an RPC call, shared among all callers that joined it.
+type SharedOutcome<T> = Arc<Result<RpcResult<T>, TransportError>>;
+
+/// A call that any number of callers can await.
+type SharedCall<T> = Shared<BoxFuture<'static, SharedOutcome<T>>>;
+
+/// Calls in flight, by key.
+pub struct SingleFlight<T> {
+    in_flight: Arc<Mutex<HashMap<String, SharedCall<T>>>>,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self {
+            in_flight: Default::default(),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for SingleFlight<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let in_flight = self.in_flight.lock().map(|m| m.len()).unwrap_or_default();
+        f.debug_struct("SingleFlight")
+            .field("in_flight", &in_flight)
+            .finish()
+    }
+}
+
+impl<T> SingleFlight<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    /// The result of `call`, or of the call already in flight under `key`.
+    ///
+    /// `call` is spawned as a task of its own, independently of the caller that
+    /// started it: if that caller goes away, the call still completes, hands
+    /// its result to the others, releases whatever it holds (e.g. its
+    /// admission permit) and forgets `key`. It comes boxed because RPC futures
+    /// are large, and every caller would carry its own copy otherwise.
+    pub async fn run(
+        &self,
+        key: String,
+        call: BoxFuture<'static, Result<RpcResult<T>, TransportError>>,
+    ) -> Result<RpcResult<T>, TransportError> {
+        let shared = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(&key) {
+                Some(shared) => shared.clone(),
+                None => {
+                    let forget = Forget {
+                        in_flight: self.in_flight.clone(),
+                        key: key.clone(),
+                    };
+                    let task = tokio::spawn(async move {
+                        let _forget = forget;
+                        Arc::new(call.await)
+                    });
+                    let shared = task
+                        .map(|joined| {
+                            joined.unwrap_or_else(|e| {
+                                Arc::new(Err(TransportError::Send(e.to_string().into())))
+                            })
+                        })
+                        .boxed()
+                        .shared();
+                    in_flight.insert(key, shared.clone());
+                    shared
+                }
+            }
+        };
+
+        let outcome = shared.await;
+        match outcome.as_ref() {
+            Ok(Ok(value)) => Ok(Ok(value.clone())),
+            Ok(Err(e)) => Ok(Err(clone_rpc_error(e))),
+            Err(e) => Err(clone_transport_error(e)),
+        }
+    }
+
+    /// Number of keys with a call in flight.
+    pub fn len(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Removes a key from the calls in flight when its call ends, even by panic.
+struct Forget<T> {
+    in_flight: Arc<Mutex<HashMap<String, SharedCall<T>>>>,
+    key: String,
+}
+
+impl<T> Drop for Forget<T> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            in_flight.remove(&self.key);
+        }
+    }
+}
+
+/// [`RpcError`] is not `Clone`, but it reaches us as JSON anyway, so a round
+/// trip gives every caller what it would have received on its own.
+fn clone_rpc_error(e: &RpcError) -> RpcError {
+    serde_json::to_value(e)
+        .and_then(serde_json::from_value)
+        .unwrap_or(RpcError::Failed(format!("{e:?}")))
+}
+
+/// Copy of a transport error for another caller. Boxed send errors other than
+/// [`Overloaded`] keep only their message.
+fn clone_transport_error(e: &TransportError) -> TransportError {
+    match e {
+        TransportError::Shutdown => TransportError::Shutdown,
+        TransportError::Send(e) => match e.downcast_ref::<Overloaded>() {
+            Some(overloaded) => TransportError::Send(Box::new(*overloaded)),
+            None => TransportError::Send(e.to_string().into()),
+        },
+        TransportError::Receive(e) => TransportError::Receive(e.clone()),
+        TransportError::DeadlineExceeded => TransportError::DeadlineExceeded,
+        TransportError::Server(e) => TransportError::Server(e.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::model::rpc_limits::RpcClass;
+    use crate::model::rpc_limits::RpcLimiter;
+    use crate::model::rpc_limits::RpcLimitsPolicy;
+
+    #[tokio::test]
+    async fn joins_calls_in_flight_and_forgets_finished_ones() {
+        let flights = SingleFlight::<u64>::default();
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+
+        let first = flights.run(
+            "tip".to_string(),
+            async move {
+                released.await.unwrap();
+                Ok(Ok(1))
+            }
+            .boxed(),
+        );
+        let second = flights.run("tip".to_string(), async { Ok(Ok(2)) }.boxed());
+        let other = flights.run("other".to_string(), async { Ok(Ok(3)) }.boxed());
+
+        let (first, second, other, ()) = tokio::join!(first, second, other, async {
+            tokio::task::yield_now().await;
+            release.send(()).unwrap();
+        });
+        assert_eq!(1, first.unwrap().unwrap());
+        assert_eq!(1, second.unwrap().unwrap());
+        assert_eq!(3, other.unwrap().unwrap());
+        assert!(flights.is_empty());
+
+        let later = flights
+            .run("tip".to_string(), async { Ok(Ok(4)) }.boxed())
+            .await;
+        assert_eq!(4, later.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn abandoned_calls_complete_and_release_their_permit() {
+        let limiter = Arc::new(RpcLimiter::new(&RpcLimitsPolicy::default()));
+        let in_flight = |limiter: &RpcLimiter| {
+            limiter
+                .metrics()
+                .into_iter()
+                .find(|metrics| metrics.class == RpcClass::Chain)
+                .unwrap()
+                .in_flight
+        };
+        let flights = SingleFlight::<u64>::default();
+        let (release, released) = tokio::sync::oneshot::channel::<()>();
+        let call_limiter = limiter.clone();
+        let call = async move {
+            let _permit = call_limiter.admit(RpcClass::Chain).await?;
+            let _ = released.await;
+            Ok(Ok(1))
+        }
+        .boxed();
+
+        // the only caller gives up while the call holds its permit
+        let caller = flights.run("tip".to_string(), call);
+        let timed_out = tokio::time::timeout(Duration::from_millis(50), caller).await;
+        assert!(timed_out.is_err());
+        assert_eq!(1, in_flight(&limiter));
+        assert_eq!(1, flights.len());
+
+        release.send(()).unwrap();
+        while !flights.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(0, in_flight(&limiter));
+
+        let later = flights
+            .run("tip".to_string(), async { Ok(Ok(2)) }.boxed())
+            .await;
+        assert_eq!(2, later.unwrap().unwrap());
+    }
+
+    #[test]
+    fn errors_are_copied_for_every_caller() {
+        assert!(matches!(
+            clone_rpc_error(&RpcError::UtxoIndexNotPresent),
+            RpcError::UtxoIndexNotPresent
+        ));
+
+        let shed = TransportError::Send(Box::new(Overloaded(RpcClass::Chain)));
+        assert_eq!(
+            Some(&Overloaded(RpcClass::Chain)),
+            Overloaded::cause_of(&clone_transport_error(&shed))
+        );
+    }
+}
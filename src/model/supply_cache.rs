@@ -0,0 +1,108 @@
+//! The headline supply figures, cached per tip for clients that poll them,
+//! such as market data aggregators.
+
+use neptune_cash::api::export::BlockHeight;
+use neptune_cash::api::export::NativeCurrencyAmount;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelectorLiteral;
+
+use crate::model::app_state::AppStateInner;
+use crate::model::emission::max_supply;
+use crate::model::node_error::fetch_block_info;
+use crate::model::node_error::NodeError;
+use crate::shared::monetary_supplies;
+
+/// Supply figures at one tip height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SupplySnapshot {
+    pub block_height: BlockHeight,
+    pub liquid: NativeCurrencyAmount,
+    pub total: NativeCurrencyAmount,
+    pub max: NativeCurrencyAmount,
+}
+
+/// The [`SupplySnapshot`] at the last tip it was computed for.
+///
+/// The cache belongs to one node connection and is replaced on reconnect, as
+/// the node may be on another network.
+#[derive(Debug, Default)]
+pub struct SupplyCache {
+    tip: Option<Digest>,
+    snapshot: Option<SupplySnapshot>,
+
+    /// The maximum supply of the network, computed on first use.
+    max: Option<NativeCurrencyAmount>,
+}
+
+impl SupplyCache {
+    fn snapshot_at(&self, tip: Digest) -> Option<SupplySnapshot> {
+        (self.tip == Some(tip)).then_some(self.snapshot).flatten()
+    }
+}
+
+/// The supply figures at the current tip, as published by the
+/// [tip watcher](crate::model::tip_watcher).
+///
+/// Asks the node for the height of each published tip once, however many
+/// requests arrive: a miss holds the cache lock while asking, so concurrent
+/// requests wait for its answer instead of asking too. Until the watcher first
+/// reached the node, no tip is published and every call asks the node for its
+/// tip, one at a time.
+pub async fn cached_supply(state: &AppStateInner) -> Result<SupplySnapshot, NodeError> {
+    let tip = state.chain_head.borrow().tip;
+    let mut cache = state.supply_cache.lock().await;
+    if let Some(snapshot) = tip.and_then(|tip| cache.snapshot_at(tip)) {
+        return Ok(snapshot);
+    }
+
+    let selector = tip.map_or(
+        BlockSelector::Special(BlockSelectorLiteral::Tip),
+        BlockSelector::Digest,
+    );
+    let block_info = fetch_block_info(state, selector)
+        .await?
+        .ok_or(NodeError::MissingBlock("tip"))?;
+    let (liquid, total) = monetary_supplies(state.network, block_info.height, state.burns());
+    let snapshot = SupplySnapshot {
+        block_height: block_info.height,
+        liquid,
+        total,
+        max: *cache
+            .max
+            .get_or_insert_with(|| max_supply(state.network, state.burns())),
+    };
+
+    cache.tip = Some(block_info.digest);
+    cache.snapshot = Some(snapshot);
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::Zero;
+
+    use super::*;
+
+    fn snapshot() -> SupplySnapshot {
+        SupplySnapshot {
+            block_height: 7u64.into(),
+            liquid: NativeCurrencyAmount::zero(),
+            total: NativeCurrencyAmount::zero(),
+            max: NativeCurrencyAmount::zero(),
+        }
+    }
+
+    #[test]
+    fn snapshot_is_served_for_its_tip_only() {
+        let tip = Digest::new([1u64.into(); Digest::LEN]);
+        let cache = SupplyCache {
+            tip: Some(tip),
+            snapshot: Some(snapshot()),
+            max: None,
+        };
+        assert_eq!(Some(snapshot()), cache.snapshot_at(tip));
+        assert_eq!(None, cache.snapshot_at(Digest::default()));
+        assert_eq!(None, SupplyCache::default().snapshot_at(tip));
+    }
+}
@@ -0,0 +1,208 @@
+//! Operator overrides of the bundled page templates.
+//!
+//! Templates are compiled into the binary, so their Rust code cannot change at
+//! runtime, but their text can: with `--templates-dir`, a file at
+//! `<dir>/page/block_info.html` replaces the bundled
+//! `templates/web/html/page/block_info.html` when rendering, provided that it
+//! keeps every `{{ ... }}` and `%%` block of the bundled one, in the same
+//! order. Start from a copy of the bundled template and change the markup
+//! around those blocks. Overrides are read and checked once on startup; an
+//! override that does not match its bundled template is an error.
+//!
+//! All pages can be overridden, and of the components the ones shared by all
+//! pages: `components/head.html`, `components/header.html` and
+//! `components/footer.html`.
+//!
+//! Overrides need the `template-overrides` cargo feature. Without it, the
+//! bundled templates are rendered as compiled and `--templates-dir` is
+//! refused.
+
+#[cfg(feature = "template-overrides")]
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+
+#[cfg(feature = "template-overrides")]
+use anyhow::Context;
+use boilerplate::Boilerplate;
+#[cfg(feature = "template-overrides")]
+use boilerplate::Token;
+
+#[cfg(feature = "template-overrides")]
+macro_rules! bundled {
+    ($path:literal) => {
+        (
+            $path,
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/templates/web/html/",
+                $path
+            )),
+        )
+    };
+}
+
+/// Templates that can be overridden, with their bundled text.
+#[cfg(feature = "template-overrides")]
+const OVERRIDABLE: &[(&str, &str)] = &[
+    bundled!("components/footer.html"),
+    bundled!("components/head.html"),
+    bundled!("components/header.html"),
+    bundled!("page/address.html"),
+    bundled!("page/announcement.html"),
+    bundled!("page/announcement_search.html"),
+    bundled!("page/block_info.html"),
+    bundled!("page/burns.html"),
+    bundled!("page/compare.html"),
+    bundled!("page/emission.html"),
+    bundled!("page/forks.html"),
+    bundled!("page/mining.html"),
+    bundled!("page/not_found.html"),
+    bundled!("page/root.html"),
+    bundled!("page/stats.html"),
+    bundled!("page/supply.html"),
+    bundled!("page/tx_output.html"),
+    bundled!("page/utxo.html"),
+];
+
+/// Replacement texts of templates, by path relative to `templates/web/html`.
+///
+/// Each override is kept as the text blocks between its code blocks, parsed
+/// once on load.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateOverrides {
+    #[cfg(feature = "template-overrides")]
+    templates: HashMap<&'static str, Vec<String>>,
+}
+
+#[cfg(not(feature = "template-overrides"))]
+impl TemplateOverrides {
+    /// No overrides; `dir` must be `None`.
+    pub fn load(dir: Option<&Path>) -> Result<Self, anyhow::Error> {
+        anyhow::ensure!(
+            dir.is_none(),
+            "--templates-dir requires building the explorer with the template-overrides feature"
+        );
+        Ok(Self::default())
+    }
+
+    /// Render `template` as bundled.
+    pub fn render<T: Boilerplate + Display>(&self, template: &T) -> String {
+        template.to_string()
+    }
+}
+
+#[cfg(feature = "template-overrides")]
+impl TemplateOverrides {
+    /// The overrides in `dir`, if any.
+    pub fn load(dir: Option<&Path>) -> Result<Self, anyhow::Error> {
+        let mut overrides = Self::default();
+        let Some(dir) = dir else {
+            return Ok(overrides);
+        };
+        anyhow::ensure!(
+            dir.is_dir(),
+            "Templates directory {} does not exist",
+            dir.display()
+        );
+        for (path, bundled) in OVERRIDABLE {
+            let file = dir.join(path);
+            if !file.exists() {
+                continue;
+            }
+            let text = std::fs::read_to_string(&file)
+                .with_context(|| format!("Failed to read template {}", file.display()))?;
+            let text_blocks = check_compatible(bundled, &text).with_context(|| {
+                format!(
+                    "Template {} does not match the bundled {path}",
+                    file.display()
+                )
+            })?;
+            tracing::info!("Overriding template {path} with {}", file.display());
+            overrides.templates.insert(path, text_blocks);
+        }
+        Ok(overrides)
+    }
+
+    /// Render `template` with the text of its override, if there is one.
+    pub fn render<T: Boilerplate + Display>(&self, template: &T) -> String {
+        let text = T::PATH
+            .and_then(|path| path.split_once("templates/web/html/"))
+            .and_then(|(_, path)| self.templates.get(path));
+        match text {
+            Some(text) => Overridden { template, text }.to_string(),
+            None => template.to_string(),
+        }
+    }
+}
+
+/// A template rendered with the text blocks of its override.
+#[cfg(feature = "template-overrides")]
+struct Overridden<'a, T> {
+    template: &'a T,
+    text: &'a [String],
+}
+
+#[cfg(feature = "template-overrides")]
+impl<T: Boilerplate> Display for Overridden<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.template.boilerplate(self.text, f)
+    }
+}
+
+/// Checks that `text` has the same code blocks as `bundled`, as
+/// [`Boilerplate::boilerplate`] requires, and returns its text blocks.
+#[cfg(feature = "template-overrides")]
+fn check_compatible(bundled: &str, text: &str) -> Result<Vec<String>, anyhow::Error> {
+    let bundled = Token::parse(bundled).map_err(|e| anyhow::anyhow!("{e}"))?;
+    let text = Token::parse(text).map_err(|e| anyhow::anyhow!("{e}"))?;
+    anyhow::ensure!(
+        bundled.len() == text.len(),
+        "it has {} blocks instead of {}",
+        text.len(),
+        bundled.len()
+    );
+    for (new, old) in text.iter().zip(bundled) {
+        anyhow::ensure!(new.is_compatible_with(old), "{new} should be {old}");
+    }
+    Ok(text
+        .into_iter()
+        .filter_map(Token::text)
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(feature = "template-overrides"))]
+    #[test]
+    fn overrides_are_refused_without_the_feature() {
+        assert!(TemplateOverrides::load(None).is_ok());
+        assert!(TemplateOverrides::load(Some(Path::new("templates"))).is_err());
+    }
+
+    #[cfg(feature = "template-overrides")]
+    #[test]
+    fn every_page_can_be_overridden() {
+        let pages = concat!(env!("CARGO_MANIFEST_DIR"), "/templates/web/html/page");
+        for entry in std::fs::read_dir(pages).unwrap() {
+            let path = format!("page/{}", entry.unwrap().file_name().to_str().unwrap());
+            assert!(OVERRIDABLE.iter().any(|(p, _)| *p == path), "{path}");
+        }
+    }
+
+    #[cfg(feature = "template-overrides")]
+    #[test]
+    fn overrides_may_change_text_but_not_code() {
+        let (_, bundled) = bundled!("components/header.html");
+        assert!(
+            check_compatible(bundled, &bundled.replace("<h1>", "<h1 class=\"brand\">")).is_ok()
+        );
+        assert!(
+            check_compatible(bundled, &bundled.replace("self.state.network", "\"main\"")).is_err()
+        );
+        assert!(check_compatible(bundled, &format!("{bundled}{{{{1}}}}")).is_err());
+    }
+}
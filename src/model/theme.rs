@@ -0,0 +1,120 @@
+//! Branding of the explorer's pages.
+//!
+//! The theme is read from `--theme-file`, a JSON file such as:
+//!
+//! ```json
+//! {
+//!     "logo": "image/my-logo.png",
+//!     "accent_color": "#8a2be2",
+//!     "footer_links": [
+//!         { "text": "Community", "url": "https://example.org" }
+//!     ],
+//!     "extra_head_html": "<meta name=\"robots\" content=\"noindex\">",
+//!     "custom_css": "css/custom.css"
+//! }
+//! ```
+//!
+//! Every field is optional. `logo` and `custom_css` are paths of assets, e.g.
+//! from `--assets-dir`, or URLs. `extra_head_html` is inserted verbatim into
+//! the `<head>` of every page. See `data/theme.example.json`.
+
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    /// Asset path or URL of the logo in the page header.
+    pub logo: String,
+
+    /// CSS hex colour of headings, links and buttons, e.g. `#8a2be2`.
+    pub accent_color: Option<String>,
+
+    /// Links in the footer of every page, after the link to the source code.
+    pub footer_links: Vec<FooterLink>,
+
+    /// HTML inserted verbatim at the end of every `<head>`, e.g. analytics.
+    pub extra_head_html: String,
+
+    /// Asset path or URL of a stylesheet loaded after the bundled ones.
+    pub custom_css: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FooterLink {
+    pub text: String,
+    pub url: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            logo: "image/neptune-logo-circle-small.png".to_string(),
+            accent_color: None,
+            footer_links: vec![],
+            extra_head_html: String::new(),
+            custom_css: None,
+        }
+    }
+}
+
+impl Theme {
+    pub fn from_file(path: &Path) -> Result<Self, anyhow::Error> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read theme {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("Invalid theme {}", path.display()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, anyhow::Error> {
+        let theme: Self = serde_json::from_str(json)?;
+        if let Some(color) = &theme.accent_color {
+            // the colour goes into a <style> element, so nothing but a colour
+            // may get through
+            anyhow::ensure!(
+                is_hex_color(color),
+                "accent_color must be a hex colour like #8a2be2, not {color}"
+            );
+        }
+        Ok(theme)
+    }
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.strip_prefix('#').is_some_and(|hex| {
+        [3, 4, 6, 8].contains(&hex.len()) && hex.chars().all(|c| c.is_ascii_hexdigit())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_theme_is_valid() {
+        let example = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/data/theme.example.json"
+        ));
+        let theme = Theme::from_json(example).unwrap();
+        assert_eq!(Some("#8a2be2"), theme.accent_color.as_deref());
+        assert_eq!(1, theme.footer_links.len());
+    }
+
+    #[test]
+    fn missing_fields_are_defaults() {
+        let theme = Theme::from_json(r##"{"accent_color": "#abc"}"##).unwrap();
+        assert_eq!(Theme::default().logo, theme.logo);
+        assert!(theme.footer_links.is_empty());
+    }
+
+    #[test]
+    fn accent_color_must_be_a_hex_colour() {
+        for color in ["red", "#12345", "#ggg", "#fff;} body {display: none"] {
+            let json = format!(r#"{{"accent_color": "{color}"}}"#);
+            assert!(Theme::from_json(&json).is_err(), "{color}");
+        }
+    }
+}
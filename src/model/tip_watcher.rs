@@ -0,0 +1,198 @@
+//! One shared watch on the tip and what the node is mining on, so that the
+//! background tasks and clients waiting for a change need not poll the node
+//! themselves.
+//!
+//! [`run_tip_watcher`] checks the tip once per [`tip_check_interval`], or, with
+//! the mining gateway, the tip and the node's best block proposal once per
+//! [`PROPOSAL_CHECK_INTERVAL`]. It publishes every change as a new
+//! [`ChainHead`] through [`AppStateInner::chain_head`], to which any number of
+//! tasks and requests can subscribe; [`next_tip`] waits for the next block.
+
+use std::time::Duration;
+
+use neptune_cash::api::export::Network;
+
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelectorLiteral;
+use tarpc::context;
+use tokio::sync::watch;
+use tracing::debug;
+use tracing::warn;
+
+use crate::model::app_state::AppState;
+use crate::model::app_state::AppStateInner;
+use crate::model::node_error::NodeError;
+
+/// Lower bound on [`tip_check_interval`], for networks (regtest) whose minimum
+/// block time is a millisecond.
+pub const MIN_TIP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the watcher asks the node for its tip and best proposal while the
+/// mining gateway is enabled. New proposals for the same tip can arrive at any
+/// time, so this is much shorter than the [`tip_check_interval`].
+pub const PROPOSAL_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The tip and the block proposal the node is mining on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChainHead {
+    /// `None` until the watcher first reached the node.
+    pub tip: Option<Digest>,
+
+    /// Digest of the best proposal, without proof-of-work. `None` if the node
+    /// knows of no proposal.
+    pub proposal: Option<Digest>,
+}
+
+/// How often the tip is checked: the network's minimum block time, since no two
+/// blocks can be closer together than that, but at least
+/// [`MIN_TIP_CHECK_INTERVAL`].
+pub fn tip_check_interval(network: Network) -> Duration {
+    network
+        .minimum_block_time()
+        .as_duration()
+        .max(MIN_TIP_CHECK_INTERVAL)
+}
+
+/// Wait until the published tip is known and differs from `tip`, and return
+/// it. If the watcher is gone, returns `None` after [`MIN_TIP_CHECK_INTERVAL`]
+/// instead, so that callers in a loop fall back to polling.
+pub async fn next_tip(
+    chain_head: &mut watch::Receiver<ChainHead>,
+    tip: Option<Digest>,
+) -> Option<Digest> {
+    let next = chain_head
+        .wait_for(|head| head.tip.is_some() && head.tip != tip)
+        .await
+        .map(|head| head.tip);
+    match next {
+        Ok(tip) => tip,
+        Err(_) => {
+            tokio::time::sleep(MIN_TIP_CHECK_INTERVAL).await;
+            None
+        }
+    }
+}
+
+/// Publish `head` to subscribers of `sender`, if it changed. Returns whether it
+/// did.
+pub fn publish(sender: &watch::Sender<ChainHead>, head: ChainHead) -> bool {
+    sender.send_if_modified(|current| {
+        if *current == head {
+            return false;
+        }
+        *current = head;
+        true
+    })
+}
+
+/// Keep [`AppStateInner::chain_head`] up to date, forever.
+pub async fn run_tip_watcher(app_state: AppState) {
+    debug!("tip watcher started");
+
+    loop {
+        let state = app_state.load();
+        match chain_head(&state).await {
+            Ok(head) => {
+                if publish(&state.chain_head, head) {
+                    debug!("chain head changed: {head:?}");
+                }
+            }
+            Err(e) => warn!("Failed to check tip and block proposal: {e}"),
+        }
+        let interval = match state.mining_gateway {
+            Some(_) => PROPOSAL_CHECK_INTERVAL,
+            None => tip_check_interval(state.network),
+        };
+        tokio::time::sleep(interval).await;
+    }
+}
+
+async fn chain_head(state: &AppStateInner) -> Result<ChainHead, NodeError> {
+    let tip = state
+        .rpc_client
+        .block_digest(
+            context::current(),
+            state.token(),
+            BlockSelector::Special(BlockSelectorLiteral::Tip),
+        )
+        .await??;
+    // only miners are interested in proposals
+    let proposal = match state.mining_gateway {
+        Some(_) => {
+            state
+                .rpc_client
+                .best_proposal(context::current(), state.token())
+                .await??
+        }
+        None => None,
+    };
+
+    Ok(ChainHead {
+        tip,
+        proposal: proposal.map(|proposal| proposal.digest),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_see_changes_only() {
+        let (sender, mut receiver) = watch::channel(ChainHead::default());
+        let head = ChainHead {
+            tip: Some(Digest::default()),
+            proposal: None,
+        };
+
+        assert!(!publish(&sender, ChainHead::default()));
+        assert!(!receiver.has_changed().unwrap());
+
+        assert!(publish(&sender, head));
+        assert!(receiver.has_changed().unwrap());
+        assert_eq!(head, *receiver.borrow_and_update());
+
+        assert!(!publish(&sender, head));
+        assert!(!receiver.has_changed().unwrap());
+    }
+
+    #[tokio::test]
+    async fn next_tip_skips_unknown_and_unchanged_tips() {
+        let (sender, mut receiver) = watch::channel(ChainHead::default());
+        let tip = |byte: u64| Some(Digest::new([byte.into(); Digest::LEN]));
+        let waiting = tokio::spawn(async move { next_tip(&mut receiver, tip(1)).await });
+
+        publish(
+            &sender,
+            ChainHead {
+                tip: tip(1),
+                proposal: None,
+            },
+        );
+        publish(
+            &sender,
+            ChainHead {
+                tip: tip(1),
+                proposal: tip(9),
+            },
+        );
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+
+        publish(
+            &sender,
+            ChainHead {
+                tip: tip(2),
+                proposal: None,
+            },
+        );
+        assert_eq!(tip(2), waiting.await.unwrap());
+    }
+
+    #[test]
+    fn tip_check_interval_follows_minimum_block_time() {
+        assert_eq!(Duration::from_secs(60), tip_check_interval(Network::Main));
+        assert_eq!(MIN_TIP_CHECK_INTERVAL, tip_check_interval(Network::RegTest));
+    }
+}
@@ -1,5 +1,6 @@
 use std::net::Ipv4Addr;
 use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Context;
@@ -7,22 +8,31 @@ use chrono::DateTime;
 use chrono::TimeDelta;
 use chrono::Utc;
 use clap::Parser;
+use futures::FutureExt;
 use neptune_cash::api::export::Announcement;
 use neptune_cash::api::export::Network;
+use neptune_cash::api::export::TransactionKernelId;
 use neptune_cash::application::config::data_directory::DataDirectory;
 use neptune_cash::application::rpc::auth;
 use neptune_cash::application::rpc::server::error::RpcError;
+use neptune_cash::application::rpc::server::proof_of_work_puzzle::ProofOfWorkPuzzle;
 use neptune_cash::application::rpc::server::RPCClient;
+use neptune_cash::application::rpc::server::RPCRequest;
+use neptune_cash::application::rpc::server::RPCResponse;
 use neptune_cash::application::rpc::server::RpcResult;
 use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::protocol::consensus::block::block_header::BlockPow;
 use neptune_cash::protocol::consensus::block::block_height::BlockHeight;
 use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
 use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use neptune_cash::protocol::consensus::transaction::transaction_kernel::TransactionKernel;
+use neptune_cash::state::wallet::address::announcement_flag::AnnouncementFlag;
+use neptune_cash::state::wallet::address::ReceivingAddress;
 use neptune_cash::util_types::mutator_set::addition_record::AdditionRecord;
 use tarpc::client;
 use tarpc::context;
 use tarpc::tokio_serde::formats::Json as RpcJson;
-use tokio::sync::Mutex;
+use tokio::sync::SemaphorePermit;
 use tracing::debug;
 use tracing::info;
 use tracing::warn;
@@ -30,222 +40,195 @@ use tracing::warn;
 use crate::alert_email;
 use crate::model::app_state::AppState;
 use crate::model::config::Config;
-use crate::model::transparent_utxo_tuple::TransparentUtxoTuple;
+#[cfg(feature = "mock")]
+use crate::model::mock_chain::MockChain;
+use crate::model::rpc_fixtures::FixtureRecorder;
+use crate::model::rpc_fixtures::Fixtures;
+use crate::model::rpc_fixtures::NodeStub;
+use crate::model::rpc_limits::RpcClass;
+use crate::model::rpc_limits::RpcLimiter;
+use crate::model::single_flight::SingleFlight;
 
 #[cfg(feature = "mock")]
 const MOCK_KEY: &str = "MOCK";
 
+/// Height of the tip of the generated chain served with `MOCK`.
+#[cfg(feature = "mock")]
+const MOCK_CHAIN_TIP: u64 = 1000;
+
 #[derive(Debug, Clone)]
 pub struct AuthenticatedClient {
-    pub client: RPCClient,
+    pub client: RPCClient<NodeStub>,
     pub token: auth::Token,
     pub network: Network,
+
+    /// Admission control shared by all clients, so that explorer traffic
+    /// cannot overwhelm the node.
+    pub limiter: Arc<RpcLimiter>,
+
+    /// Hot calls in flight, which identical concurrent calls join instead of
+    /// asking the node again.
+    pub in_flight: Arc<InFlightCalls>,
 }
 
-impl std::ops::Deref for AuthenticatedClient {
-    type Target = RPCClient;
+/// The calls every page load makes, coalesced per [`AuthenticatedClient`].
+///
+/// The explorer makes all calls with the one token of its client, so the token
+/// is not part of the key.
+#[derive(Debug, Default)]
+pub struct InFlightCalls {
+    block_height: SingleFlight<BlockHeight>,
+    block_digest: SingleFlight<Option<Digest>>,
+    block_info: SingleFlight<Option<BlockInfo>>,
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.client
-    }
+/// Relay a call to the [`RPCClient`] method of the same name, once admitted to
+/// the given [`RpcClass`] and with that class's deadline.
+macro_rules! relay {
+    ($class:ident, $method:ident($($arg:ident: $ty:ty),*) -> $ret:ty) => {
+        #[doc = concat!("Relay call to [`RPCClient::", stringify!($method), "`]")]
+        pub async fn $method(
+            &self,
+            mut ctx: ::tarpc::context::Context,
+            $($arg: $ty),*
+        ) -> ::core::result::Result<RpcResult<$ret>, ::tarpc::client::RpcError> {
+            let _permit = self.admit(RpcClass::$class, &mut ctx).await?;
+            self.client.$method(ctx, $($arg),*).await
+        }
+    };
 }
 
 impl AuthenticatedClient {
-    /// Intercept and relay call to [`RPCClient::block_info`]
-    pub async fn block_info(
+    /// Wait for a slot of `class` and replace the deadline of `ctx` with that
+    /// of the class. The call must be made while the permit is held.
+    async fn admit(
         &self,
-        ctx: ::tarpc::context::Context,
-        token: auth::Token,
-        block_selector: BlockSelector,
-    ) -> ::core::result::Result<RpcResult<Option<BlockInfo>>, ::tarpc::client::RpcError> {
-        let rpc_result = self.client.block_info(ctx, token, block_selector).await;
-
-        // if the RPC call was successful, return that
-        if let Ok(Ok(Some(_))) = rpc_result {
-            return rpc_result;
-        }
+        class: RpcClass,
+        ctx: &mut ::tarpc::context::Context,
+    ) -> Result<SemaphorePermit<'_>, ::tarpc::client::RpcError> {
+        let (permit, deadline) = self.limiter.admit(class).await?;
+        ctx.deadline = deadline;
+        Ok(permit)
+    }
 
-        // if MOCK environment variable is set and feature is enabled,
-        // imagine some mock block info
-        #[cfg(feature = "mock")]
-        if std::env::var(MOCK_KEY).is_ok() {
-            use blake3::Hasher;
-            use rand::rngs::StdRng;
-            use rand::Rng;
-            use rand::SeedableRng;
-            tracing::warn!("RPC query failed and MOCK flag set, so returning an imagined block");
-            let mut hasher = Hasher::new();
-            hasher.update(&block_selector.to_string().bytes().collect::<Vec<_>>());
-            let mut rng = StdRng::from_seed(*hasher.finalize().as_bytes());
-            let mut block_info: BlockInfo = rng.random();
-            match block_selector {
-                BlockSelector::Digest(digest) => {
-                    block_info.digest = digest;
-                }
-                BlockSelector::Height(height) => {
-                    block_info.height = height;
-                }
-                _ => {}
-            };
-            return Ok(Ok(Some(block_info)));
-        }
+    relay!(Chain, best_proposal(token: auth::Token) -> Option<BlockInfo>);
+    relay!(Chain, utxo_digest(token: auth::Token, leaf_index: u64) -> Option<Digest>);
+    relay!(
+        Block,
+        announcements_in_block(
+            token: auth::Token,
+            block_selector: BlockSelector
+        ) -> Option<Vec<Announcement>>
+    );
+    relay!(
+        Block,
+        addition_record_indices_for_block(
+            token: auth::Token,
+            block_selector: BlockSelector
+        ) -> Vec<(AdditionRecord, Option<u64>)>
+    );
+    relay!(
+        Block,
+        block_heights_by_announcement_flags(
+            token: auth::Token,
+            announcement_flags: Vec<AnnouncementFlag>
+        ) -> Vec<BlockHeight>
+    );
+    relay!(
+        Block,
+        utxo_origin_block(
+            token: auth::Token,
+            addition_record: AdditionRecord,
+            max_search_depth: Option<u64>
+        ) -> Option<Digest>
+    );
+    relay!(Mempool, mempool_tx_ids(token: auth::Token) -> Vec<TransactionKernelId>);
+    relay!(
+        Mempool,
+        mempool_tx_kernel(
+            token: auth::Token,
+            tx_kernel_id: TransactionKernelId
+        ) -> Option<TransactionKernel>
+    );
+    relay!(
+        Mining,
+        pow_puzzle_external_key(
+            token: auth::Token,
+            guesser_fee_address: ReceivingAddress
+        ) -> Option<ProofOfWorkPuzzle>
+    );
+    relay!(
+        Mining,
+        provide_pow_solution(token: auth::Token, pow: BlockPow, proposal_id: Digest) -> bool
+    );
 
-        // otherwise, return the original error
-        rpc_result
+    /// Relay call to [`RPCClient::network`], bypassing admission control: the
+    /// watchdog uses it to check the connection, which must not look lost
+    /// just because the explorer is busy.
+    pub async fn network(
+        &self,
+        ctx: ::tarpc::context::Context,
+    ) -> ::core::result::Result<RpcResult<Network>, ::tarpc::client::RpcError> {
+        self.client.network(ctx).await
     }
 
-    /// Intercept and relay call to [`RPCClient::utxo_digest`]
-    pub async fn utxo_digest(
+    /// Relay call to [`RPCClient::block_height`], joining an identical call
+    /// in flight.
+    pub async fn block_height(
         &self,
-        ctx: ::tarpc::context::Context,
+        mut ctx: ::tarpc::context::Context,
         token: auth::Token,
-        leaf_index: u64,
-        _transparent_utxos_cache: Arc<Mutex<Vec<TransparentUtxoTuple>>>,
-    ) -> ::core::result::Result<RpcResult<Option<Digest>>, ::tarpc::client::RpcError> {
-        let rpc_result = self.client.utxo_digest(ctx, token, leaf_index).await;
-
-        if let Ok(Ok(Some(_))) = rpc_result {
-            return rpc_result;
-        }
-
-        // If mocking is enabled, it is possible that the cache contains a UTXO
-        // for this index that was imagined in the past.
-        #[cfg(feature = "mock")]
-        if let Some(entry) = _transparent_utxos_cache
-            .lock()
-            .await
-            .iter()
-            .find(|tu| tu.aocl_leaf_index().is_some_and(|li| li == leaf_index))
-        {
-            tracing::warn!("returning a cached utxo");
-            return Ok(Ok(Some(entry.addition_record().canonical_commitment)));
+    ) -> ::core::result::Result<RpcResult<BlockHeight>, ::tarpc::client::RpcError> {
+        let (client, limiter) = (self.client.clone(), self.limiter.clone());
+        let call = async move {
+            let (_permit, deadline) = limiter.admit(RpcClass::Chain).await?;
+            ctx.deadline = deadline;
+            client.block_height(ctx, token).await
         }
-
-        rpc_result
+        .boxed();
+        self.in_flight.block_height.run(String::new(), call).await
     }
 
-    /// Intercept and relay call to [`RPCClient::announcements_in_block`]
-    pub async fn announcements_in_block(
+    /// Relay call to [`RPCClient::block_digest`], joining an identical call
+    /// in flight.
+    pub async fn block_digest(
         &self,
-        ctx: ::tarpc::context::Context,
+        mut ctx: ::tarpc::context::Context,
         token: auth::Token,
         block_selector: BlockSelector,
-    ) -> Result<Result<Option<Vec<Announcement>>, RpcError>, ::tarpc::client::RpcError> {
-        let rpc_result = self
-            .client
-            .announcements_in_block(ctx, token, block_selector)
-            .await;
-
-        // if the RPC call was successful, return that
-        if let Ok(Ok(Some(_))) = rpc_result {
-            return rpc_result;
-        }
-
-        // if MOCK environment variable is set and feature is enabled,
-        // imagine some mock block info
-        #[cfg(feature = "mock")]
-        if std::env::var(MOCK_KEY).is_ok() {
-            use blake3::Hasher;
-            use neptune_cash::api::export::TransparentTransactionInfo;
-            use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
-            use rand::rngs::StdRng;
-            use rand::Rng;
-            use rand::SeedableRng;
-            tracing::warn!(
-                "RPC query failed and MOCK flag set, so returning an imagined announcement"
-            );
-            let mut hasher = Hasher::new();
-            hasher.update(&block_selector.to_string().bytes().collect::<Vec<_>>());
-            let mut rng = StdRng::from_seed(*hasher.finalize().as_bytes());
-
-            // make sure the number of announcements matches with the block
-            let block_info = self
-                .block_info(ctx, token, block_selector)
-                .await
-                .unwrap()
-                .unwrap()
-                .unwrap();
-            let num_announcements = block_info.num_announcements;
-
-            let mut announcements = vec![];
-            for _ in 0..num_announcements {
-                let announcement = if rng.random_bool(0.5_f64) {
-                    let message = (0..rng.random_range(0..256))
-                        .map(|_| rng.random::<BFieldElement>())
-                        .collect::<Vec<_>>();
-                    Announcement::new(message)
-                } else {
-                    rng.random::<TransparentTransactionInfo>().to_announcement()
-                };
-                announcements.push(announcement);
-            }
-
-            return Ok(Ok(Some(announcements)));
+    ) -> ::core::result::Result<RpcResult<Option<Digest>>, ::tarpc::client::RpcError> {
+        let (client, limiter) = (self.client.clone(), self.limiter.clone());
+        let call = async move {
+            let (_permit, deadline) = limiter.admit(RpcClass::Chain).await?;
+            ctx.deadline = deadline;
+            client.block_digest(ctx, token, block_selector).await
         }
-
-        // otherwise, return the original error
-        rpc_result
+        .boxed();
+        self.in_flight
+            .block_digest
+            .run(block_selector.to_string(), call)
+            .await
     }
 
-    /// Intercept and relay call to
-    /// [`RPCClient::addition_record_indices_for_block`].
-    ///
-    /// Also take an extra argument for mocking purposes.
-    pub async fn addition_record_indices_for_block(
+    /// Intercept and relay call to [`RPCClient::block_info`], joining an
+    /// identical call in flight.
+    pub async fn block_info(
         &self,
-        ctx: ::tarpc::context::Context,
+        mut ctx: ::tarpc::context::Context,
         token: auth::Token,
         block_selector: BlockSelector,
-        _addition_records: &[AdditionRecord],
-    ) -> ::core::result::Result<
-        RpcResult<Vec<(AdditionRecord, Option<u64>)>>,
-        ::tarpc::client::RpcError,
-    > {
-        let rpc_result = self
-            .client
-            .addition_record_indices_for_block(ctx, token, block_selector)
-            .await;
-
-        // if the RPC call was successful, return that
-        if let Ok(Ok(_)) = rpc_result {
-            return rpc_result;
-        }
-
-        // if MOCK environment variable is set and feature is enabled,
-        // imagine some mock hash map
-        #[cfg(feature = "mock")]
-        if std::env::var(MOCK_KEY).is_ok() {
-            use blake3::Hasher;
-            use rand::rngs::StdRng;
-            use rand::Rng;
-            use rand::SeedableRng;
-            tracing::warn!(
-                "RPC query failed and MOCK flag set, so returning an imagined addition records"
-            );
-            let mut hasher = Hasher::new();
-            hasher.update(&block_selector.to_string().bytes().collect::<Vec<_>>());
-            let mut rng = StdRng::from_seed(*hasher.finalize().as_bytes());
-
-            let aocl_offset = rng.random::<u64>() >> 1;
-            let addition_record_indices = _addition_records
-                .iter()
-                .enumerate()
-                .map(|(i, ar)| {
-                    (
-                        *ar,
-                        if rng.random_bool(0.5_f64) {
-                            Some(i as u64 + aocl_offset)
-                        } else {
-                            None
-                        },
-                    )
-                })
-                .collect();
-            return Ok(Ok(addition_record_indices));
+    ) -> ::core::result::Result<RpcResult<Option<BlockInfo>>, ::tarpc::client::RpcError> {
+        let (client, limiter) = (self.client.clone(), self.limiter.clone());
+        let call = async move {
+            let (_permit, deadline) = limiter.admit(RpcClass::Chain).await?;
+            ctx.deadline = deadline;
+            client.block_info(ctx, token, block_selector).await
         }
-
-        // otherwise, return the original error
-        rpc_result
+        .boxed();
+        self.in_flight
+            .block_info
+            .run(block_selector.to_string(), call)
+            .await
     }
 
     /// Detect whether the connected neptune-core node maintains a UTXO index
@@ -263,9 +246,10 @@ impl AuthenticatedClient {
     /// this to keep that page disabled unless the node maintains the index.
     pub async fn maintains_utxo_index(
         &self,
-        ctx: ::tarpc::context::Context,
+        mut ctx: ::tarpc::context::Context,
         token: auth::Token,
     ) -> anyhow::Result<bool> {
+        let _permit = self.admit(RpcClass::Block, &mut ctx).await?;
         match self
             .client
             .block_heights_by_announcement_flags(ctx, token, vec![])
@@ -284,25 +268,101 @@ impl AuthenticatedClient {
 }
 
 /// generates RPCClient, for querying neptune-core RPC server.
-pub async fn gen_authenticated_rpc_client() -> Result<AuthenticatedClient, anyhow::Error> {
-    let client = gen_rpc_client().await?;
+///
+/// All clients of one explorer should share one `limiter`.
+///
+/// With `--rpc-replay-dir`, the client answers from fixtures instead, and no
+/// node is needed. With `--rpc-record-dir`, its answers are recorded.
+pub async fn gen_authenticated_rpc_client(
+    limiter: Arc<RpcLimiter>,
+) -> Result<AuthenticatedClient, anyhow::Error> {
+    let config = Config::parse();
+    #[cfg(feature = "mock")]
+    if let Ok(seed) = std::env::var(MOCK_KEY) {
+        return Ok(gen_mock_client(&seed, limiter));
+    }
+    if let Some(dir) = &config.rpc_replay_dir {
+        return gen_replay_client(dir, limiter).await;
+    }
+
+    let channel = gen_rpc_channel().await?;
 
     let auth::CookieHint {
         data_directory,
         network,
-    } = get_cookie_hint(&client, &None).await?;
+    } = get_cookie_hint(&RPCClient::from(NodeStub::Live(channel.clone())), &None).await?;
 
     let token: auth::Token = auth::Cookie::try_load(&data_directory).await?.into();
 
+    let client = match &config.rpc_record_dir {
+        Some(dir) => {
+            let recorder = Arc::new(FixtureRecorder::open(dir)?);
+            let client = RPCClient::from(NodeStub::Recording(channel, recorder));
+            // replaying starts by asking for the network, so make sure it is
+            // recorded
+            client.network(context::current()).await??;
+            client
+        }
+        None => RPCClient::from(NodeStub::Live(channel)),
+    };
+
     Ok(AuthenticatedClient {
         client,
         token,
         network,
+        limiter,
+        in_flight: Default::default(),
     })
 }
 
+/// generates AuthenticatedClient that answers from the fixtures in `dir`
+/// instead of querying neptune-core.
+pub async fn gen_replay_client(
+    dir: &Path,
+    limiter: Arc<RpcLimiter>,
+) -> Result<AuthenticatedClient, anyhow::Error> {
+    let client = RPCClient::from(NodeStub::Replay(Arc::new(Fixtures::open(dir)?)));
+    let network = client
+        .network(context::current())
+        .await
+        .with_context(|| "Failed to replay neptune-core api: network")??;
+    Ok(AuthenticatedClient {
+        client,
+        // nothing to authenticate to
+        token: auth::Cookie::from([0; 32]).into(),
+        network,
+        limiter,
+        in_flight: Default::default(),
+    })
+}
+
+/// generates AuthenticatedClient that answers from a chain generated from
+/// `seed` instead of querying neptune-core.
+#[cfg(feature = "mock")]
+pub fn gen_mock_client(seed: &str, limiter: Arc<RpcLimiter>) -> AuthenticatedClient {
+    let seed = *blake3::hash(seed.as_bytes()).as_bytes();
+    let chain = MockChain::generate(seed, MOCK_CHAIN_TIP, Network::Main);
+    info!(
+        "MOCK flag set, so serving a chain of {} generated blocks",
+        chain.blocks.len()
+    );
+    AuthenticatedClient {
+        network: chain.network,
+        client: RPCClient::from(NodeStub::Mock(Arc::new(chain))),
+        // nothing to authenticate to
+        token: auth::Cookie::from([0; 32]).into(),
+        limiter,
+        in_flight: Default::default(),
+    }
+}
+
 /// generates RPCClient, for querying neptune-core RPC server.
-pub async fn gen_rpc_client() -> Result<RPCClient, anyhow::Error> {
+pub async fn gen_rpc_client() -> Result<RPCClient<NodeStub>, anyhow::Error> {
+    Ok(RPCClient::from(NodeStub::Live(gen_rpc_channel().await?)))
+}
+
+/// Connects to the neptune-core RPC server.
+async fn gen_rpc_channel() -> Result<client::Channel<RPCRequest, RPCResponse>, anyhow::Error> {
     // Create connection to neptune-core RPC server
     let args: Config = Config::parse();
     let server_socket = SocketAddr::new(
@@ -314,7 +374,7 @@ pub async fn gen_rpc_client() -> Result<RPCClient, anyhow::Error> {
         .with_context(|| {
             format!("Failed to connect to neptune-core rpc service at {server_socket}")
         })?;
-    Ok(RPCClient::new(client::Config::default(), transport).spawn())
+    Ok(client::new(client::Config::default(), transport).spawn())
 }
 
 // returns result with a CookieHint{ data_directory, network }.
@@ -324,11 +384,11 @@ pub async fn gen_rpc_client() -> Result<RPCClient, anyhow::Error> {
 // Otherwise we call cookie_hint() RPC to obtain data-dir.
 // But the API might be disabled, which we detect and fallback to the default data-dir.
 async fn get_cookie_hint(
-    client: &RPCClient,
+    client: &RPCClient<NodeStub>,
     data_dir: &Option<std::path::PathBuf>,
 ) -> anyhow::Result<auth::CookieHint> {
     async fn fallback(
-        client: &RPCClient,
+        client: &RPCClient<NodeStub>,
         data_dir: &Option<std::path::PathBuf>,
     ) -> anyhow::Result<auth::CookieHint> {
         let network = client.network(context::current()).await??;
@@ -414,7 +474,8 @@ pub async fn watchdog(app_state: AppState) {
         }
 
         if !now_connected {
-            if let Ok(c) = gen_authenticated_rpc_client().await {
+            let limiter = app_state.load().rpc_client.limiter.clone();
+            if let Ok(c) = gen_authenticated_rpc_client(limiter).await {
                 // Re-probe UTXO-index support on reconnect so the tx-output page
                 // can't stay enabled against a node that no longer maintains the
                 // index (and vice-versa). On probe failure, disable to be safe.
@@ -551,3 +612,55 @@ pub async fn blockchain_watchdog(app_state: AppState) {
         tokio::time::sleep(tokio::time::Duration::from_secs(watchdog_secs)).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::future::join_all;
+    use neptune_cash::protocol::consensus::block::block_selector::BlockSelectorLiteral;
+
+    use super::*;
+    use crate::fake_node::FakeNode;
+
+    #[tokio::test]
+    async fn identical_concurrent_calls_share_one_rpc() {
+        let node = Arc::new(FakeNode::with_tip(10).with_latency(Duration::from_millis(50)));
+        let client = node.client();
+        let tip = BlockSelector::Special(BlockSelectorLiteral::Tip);
+
+        let heights =
+            join_all((0..20).map(|_| client.block_height(context::current(), client.token))).await;
+        let infos =
+            join_all((0..20).map(|_| client.block_info(context::current(), client.token, tip)))
+                .await;
+        let other = client
+            .block_info(
+                context::current(),
+                client.token,
+                BlockSelector::Height(3u64.into()),
+            )
+            .await;
+
+        assert!(heights
+            .into_iter()
+            .all(|height| height.unwrap().unwrap() == BlockHeight::from(10u64)));
+        assert!(infos
+            .into_iter()
+            .all(|info| info.unwrap().unwrap().unwrap().height == BlockHeight::from(10u64)));
+        assert_eq!(
+            BlockHeight::from(3u64),
+            other.unwrap().unwrap().unwrap().height
+        );
+        assert_eq!(1, node.calls("block_height"));
+        assert_eq!(2, node.calls("block_info"));
+
+        // finished calls are not reused
+        client
+            .block_height(context::current(), client.token)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(2, node.calls("block_height"));
+    }
+}
@@ -0,0 +1,1007 @@
+//! The explorer's routes: pages, endpoints, static files and rate limiting.
+
+use std::sync::Arc;
+
+use axum::middleware;
+use axum::routing::get;
+use axum::routing::post;
+use axum::routing::Router;
+
+use crate::html::page::address::address_page;
+use crate::html::page::announcement::announcement_page;
+use crate::html::page::announcement_search::announcement_search_page;
+use crate::html::page::block::block_page;
+use crate::html::page::burns::burns_page;
+use crate::html::page::compare::compare_page;
+use crate::html::page::emission::emission_page;
+use crate::html::page::forks::forks_page;
+use crate::html::page::mining::mining_page;
+use crate::html::page::not_found::not_found_html_fallback;
+use crate::html::page::redirect_qs_to_path::redirect_query_string_to_path;
+use crate::html::page::root::root;
+use crate::html::page::stats::stats_page;
+use crate::html::page::supply::supply_page;
+use crate::html::page::tx_output::tx_output_page;
+use crate::html::page::utxo::utxo_page;
+use crate::model::app_state::AppState;
+use crate::model::assets::hashed_asset;
+use crate::model::assets::plain_asset;
+use crate::model::config::Config;
+use crate::model::rate_limit::rate_limit;
+use crate::model::rate_limit::RateLimitPolicy;
+use crate::model::rate_limit::RateLimiter;
+use crate::rpc::address::address;
+use crate::rpc::announcement::announcement;
+use crate::rpc::announcement_search::announcement_search;
+use crate::rpc::block_digest::block_digest;
+use crate::rpc::block_info::block_info;
+use crate::rpc::blocks::blocks;
+use crate::rpc::burns::burns;
+use crate::rpc::circulating_supply::circulating_supply;
+use crate::rpc::download::announcement_download;
+use crate::rpc::download::block_announcements_download;
+use crate::rpc::download::block_info_download;
+use crate::rpc::emission::emission;
+use crate::rpc::forks::forks;
+use crate::rpc::metrics::metrics;
+use crate::rpc::mining::mining;
+use crate::rpc::output_status::output_status;
+use crate::rpc::plain_text_supply::api_circulating;
+use crate::rpc::plain_text_supply::api_max;
+use crate::rpc::plain_text_supply::api_total;
+use crate::rpc::pow_puzzle::pow_puzzle;
+use crate::rpc::pow_puzzle::pow_puzzle_wait;
+use crate::rpc::provide_pow_solution::provide_pow_solution;
+use crate::rpc::stats::stats;
+use crate::rpc::supply::supply;
+use crate::rpc::supply::supply_at_height;
+use crate::rpc::supply::supply_at_time;
+use crate::rpc::total_supply::total_supply;
+use crate::rpc::utxo_digest::utxo_digest;
+
+/// All routes of the explorer, rate-limited per client by `rate_limit_policy`.
+pub fn setup_routes(app_state: AppState, rate_limit_policy: RateLimitPolicy) -> Router {
+    let mining_routes = mining_gateway_routes(&app_state.load().config);
+    let rate_limiter = Arc::new(RateLimiter::new(rate_limit_policy));
+
+    Router::new()
+        // -- RPC calls --
+        .route("/rpc/block_info/*selector", get(block_info))
+        .route("/rpc/block_digest/*selector", get(block_digest))
+        .route("/rpc/blocks/*range", get(blocks))
+        .route("/rpc/utxo_digest/:index", get(utxo_digest))
+        .route("/rpc/announcement/*selector", get(announcement))
+        .route("/rpc/announcements/search", get(announcement_search))
+        .route(
+            "/rpc/download/announcement/*selector",
+            get(announcement_download),
+        )
+        .route(
+            "/rpc/download/announcements/*selector",
+            get(block_announcements_download),
+        )
+        .route(
+            "/rpc/download/block_info/*selector",
+            get(block_info_download),
+        )
+        .route("/rpc/output_status/:addition_record", get(output_status))
+        .route("/rpc/address/:bech32m", get(address))
+        .route("/rpc/circulating_supply", get(circulating_supply))
+        .route("/rpc/total_supply", get(total_supply))
+        .route("/rpc/supply", get(supply))
+        .route("/rpc/burns", get(burns))
+        .route("/rpc/supply/height/:height", get(supply_at_height))
+        .route("/rpc/supply/at/:unix_timestamp", get(supply_at_time))
+        .route("/rpc/emission", get(emission))
+        .route("/rpc/stats", get(stats))
+        .route("/rpc/forks", get(forks))
+        .route("/rpc/mining", get(mining))
+        .route("/api/circulating", get(api_circulating))
+        .route("/api/total", get(api_total))
+        .route("/api/max", get(api_max))
+        .route("/metrics", get(metrics))
+        // -- Dynamic HTML pages --
+        .route("/", get(root))
+        .route("/block/*selector", get(block_page))
+        .route("/compare/:selector_a/:selector_b", get(compare_page))
+        .route("/utxo/:value", get(utxo_page))
+        .route("/output/:addition_record", get(tx_output_page))
+        .route("/address/:bech32m", get(address_page))
+        .route("/announcement/*selector", get(announcement_page))
+        .route("/announcements/search", get(announcement_search_page))
+        .route("/supply", get(supply_page))
+        .route("/emission", get(emission_page))
+        .route("/forks", get(forks_page))
+        .route("/burns", get(burns_page))
+        .route("/stats", get(stats_page))
+        .route("/mining", get(mining_page))
+        // -- Rewrite query-strings to path --
+        .route("/rqs", get(redirect_query_string_to_path))
+        // -- Static files --
+        .route("/assets/*path", get(hashed_asset))
+        .route("/css/*path", get(plain_asset))
+        .route("/image/*path", get(plain_asset))
+        .merge(mining_routes)
+        // handle route not-found
+        .fallback(not_found_html_fallback)
+        // add state
+        .with_state(app_state.into())
+        // apply rate-limiting
+        .route_layer(middleware::from_fn_with_state(rate_limiter, rate_limit))
+}
+
+/// The proof-of-work endpoints, if the mining gateway is enabled. They are
+/// limited per client by the `pow` quota of the rate-limit policy.
+fn mining_gateway_routes(config: &Config) -> Router<Arc<AppState>> {
+    if !config.mining_gateway {
+        return Router::new();
+    }
+    Router::new()
+        .route("/rpc/pow_puzzle/:address", get(pow_puzzle))
+        .route("/rpc/pow_puzzle/:address/wait", get(pow_puzzle_wait))
+        .route("/rpc/provide_pow_solution", post(provide_pow_solution))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::header;
+    use axum::http::Request;
+    use axum::http::StatusCode;
+    use clap::Parser;
+    use neptune_cash::api::export::AdditionRecord;
+    use neptune_cash::api::export::Announcement;
+    use neptune_cash::api::export::Network;
+    use neptune_cash::application::rpc::server::proof_of_work_puzzle::ProofOfWorkPuzzle;
+    use neptune_cash::application::rpc::server::RPCClient;
+    use neptune_cash::prelude::tasm_lib::prelude::Digest;
+    use neptune_cash::prelude::triton_vm::prelude::BFieldCodec;
+    use neptune_cash::prelude::triton_vm::prelude::BFieldElement;
+    use neptune_cash::protocol::consensus::block::block_header::BlockPow;
+    use neptune_cash::state::wallet::address::generation_address::GenerationReceivingAddress;
+    use tarpc::context;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    use super::*;
+    use crate::fake_node::block_digest;
+    use crate::fake_node::output;
+    use crate::fake_node::FakeNode;
+    use crate::model::announcement_type::AnnouncementDecoderRegistry;
+    use crate::model::rpc_fixtures::FixtureRecorder;
+    use crate::model::rpc_fixtures::NodeStub;
+    use crate::model::rpc_limits::RpcClass;
+    use crate::model::rpc_limits::RpcLimiter;
+    use crate::model::rpc_limits::RpcLimitsPolicy;
+    use crate::model::tip_watcher::publish;
+    use crate::model::tip_watcher::ChainHead;
+    use crate::neptune_rpc::gen_replay_client;
+    use crate::neptune_rpc::AuthenticatedClient;
+
+    /// Flag and receiver identifier of the announcement in block 3.
+    const FLAG: u64 = 7;
+    const RECEIVER_ID: u64 = 42;
+
+    fn mempool_output() -> AdditionRecord {
+        AdditionRecord::new(Digest::new([BFieldElement::new(5000); Digest::LEN]))
+    }
+
+    /// A node at height 5, with an announcement in block 3 and one transaction
+    /// in the mempool.
+    fn node() -> FakeNode {
+        let message = [FLAG, RECEIVER_ID, 1, 2, 3]
+            .map(BFieldElement::new)
+            .to_vec();
+        FakeNode::with_tip(5)
+            .with_announcement(3, Announcement::new(message))
+            .with_mempool_tx(vec![mempool_output()])
+    }
+
+    async fn explorer(node: FakeNode) -> (Arc<FakeNode>, AppState, Router) {
+        let node = Arc::new(node);
+        let (state, router) = explorer_with(node.client()).await;
+        (node, state, router)
+    }
+
+    async fn explorer_with(client: AuthenticatedClient) -> (AppState, Router) {
+        explorer_with_args(client, &[]).await
+    }
+
+    /// An explorer started with additional command-line `args`.
+    async fn explorer_with_args(client: AuthenticatedClient, args: &[&str]) -> (AppState, Router) {
+        let default_args = ["neptune-explorer", "--site-domain", "example.org"];
+        let config = Config::parse_from(default_args.iter().chain(args));
+        let state = AppState::with_client(config, client, AnnouncementDecoderRegistry::builtin())
+            .await
+            .unwrap();
+        // requests in tests come faster than any quota would allow
+        let policy = RateLimitPolicy::from_json(r#"{"allow": ["127.0.0.0/8"]}"#).unwrap();
+        let router = setup_routes(state.clone(), policy);
+        (state, router)
+    }
+
+    /// API key of the one miner of [`gateway_explorer`].
+    const MINER_KEY: &str = "alice-0123456789abcdef";
+
+    /// An explorer with the mining gateway enabled for one miner, and the
+    /// directory holding its keys file.
+    async fn gateway_explorer(node: FakeNode) -> (Arc<FakeNode>, AppState, Router, TempDir) {
+        gateway_explorer_with_args(node, &[]).await
+    }
+
+    /// Like [`gateway_explorer`], started with additional command-line `args`.
+    async fn gateway_explorer_with_args(
+        node: FakeNode,
+        args: &[&str],
+    ) -> (Arc<FakeNode>, AppState, Router, TempDir) {
+        let node = Arc::new(node);
+        let dir = tempfile::tempdir().unwrap();
+        let keys_file = dir.path().join("keys.json");
+        std::fs::write(&keys_file, format!(r#"{{"alice": "{MINER_KEY}"}}"#)).unwrap();
+        let keys_file = keys_file.to_str().unwrap();
+        let gateway_args = ["--mining-gateway", "--mining-api-keys-file", keys_file];
+        let args = gateway_args.iter().chain(args).copied().collect::<Vec<_>>();
+        let (state, router) = explorer_with_args(node.client(), &args).await;
+        (node, state, router, dir)
+    }
+
+    /// A generation address on the fake node's network.
+    fn guesser_address() -> String {
+        GenerationReceivingAddress::derive_from_seed(Digest::default())
+            .to_bech32m(Network::Main)
+            .unwrap()
+    }
+
+    /// The JSON body of a solution to the puzzle `proposal_id`.
+    fn pow_solution(proposal_id: Digest) -> String {
+        let pow = BlockPow {
+            root: Digest::default(),
+            path_a: [Digest::default(); BlockPow::MERKLE_TREE_HEIGHT],
+            path_b: [Digest::default(); BlockPow::MERKLE_TREE_HEIGHT],
+            nonce: Digest::default(),
+        };
+        serde_json::json!({ "pow": pow, "proposal_id": proposal_id }).to_string()
+    }
+
+    /// GET `uri` with the API key `key`.
+    async fn get_with_key(router: &Router, uri: &str, key: &str) -> (StatusCode, String) {
+        let request = Request::get(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {key}"))
+            .body(Body::empty())
+            .unwrap();
+        send(router, request).await
+    }
+
+    async fn get(router: &Router, uri: &str) -> (StatusCode, String) {
+        send(router, Request::get(uri).body(Body::empty()).unwrap()).await
+    }
+
+    async fn send(router: &Router, mut request: Request<Body>) -> (StatusCode, String) {
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 1234))));
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    async fn assert_statuses(router: &Router, expected: &[(&str, StatusCode)]) {
+        for (uri, status) in expected {
+            assert_eq!(*status, get(router, uri).await.0, "GET {uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn every_page_and_endpoint_answers() {
+        let (node, _, router, _keys) = gateway_explorer(node().with_utxo_index()).await;
+        node.set_pow_puzzle(block_digest(100));
+        let output_2 = output(2).canonical_commitment.to_hex();
+        let block_3 = block_digest(3).to_hex();
+        let search = format!("search?flag={FLAG}&receiver_id={RECEIVER_ID}");
+        let address = guesser_address();
+
+        assert_statuses(
+            &router,
+            &[
+                // HTML pages
+                ("/", StatusCode::OK),
+                ("/block/tip", StatusCode::OK),
+                ("/block/height/3", StatusCode::OK),
+                (&format!("/block/digest/{block_3}"), StatusCode::OK),
+                ("/block/tip-2", StatusCode::OK),
+                ("/compare/3/tip", StatusCode::OK),
+                ("/utxo/2", StatusCode::OK),
+                (&format!("/output/{output_2}"), StatusCode::OK),
+                ("/announcement/height/3/0", StatusCode::OK),
+                (&format!("/announcements/{search}"), StatusCode::OK),
+                ("/supply", StatusCode::OK),
+                ("/burns", StatusCode::OK),
+                ("/emission", StatusCode::OK),
+                ("/stats", StatusCode::OK),
+                ("/forks", StatusCode::OK),
+                ("/mining", StatusCode::OK),
+                (&format!("/address/{address}"), StatusCode::OK),
+                ("/rqs?block=&height=3", StatusCode::PERMANENT_REDIRECT),
+                ("/css/styles.css", StatusCode::OK),
+                ("/image/neptune-logo.png", StatusCode::OK),
+                // JSON and plain-text endpoints
+                ("/rpc/block_info/tip", StatusCode::OK),
+                ("/rpc/block_digest/genesis", StatusCode::OK),
+                ("/rpc/blocks/height/0..=5", StatusCode::OK),
+                ("/rpc/utxo_digest/2", StatusCode::OK),
+                ("/rpc/announcement/height/3/0", StatusCode::OK),
+                (&format!("/rpc/announcements/{search}"), StatusCode::OK),
+                (
+                    "/rpc/download/announcement/height/3/0?format=hex",
+                    StatusCode::OK,
+                ),
+                (
+                    "/rpc/download/announcements/height/3?format=json",
+                    StatusCode::OK,
+                ),
+                ("/rpc/download/block_info/tip?format=json", StatusCode::OK),
+                (&format!("/rpc/output_status/{output_2}"), StatusCode::OK),
+                ("/rpc/circulating_supply", StatusCode::OK),
+                ("/rpc/total_supply", StatusCode::OK),
+                ("/rpc/supply", StatusCode::OK),
+                ("/rpc/supply/height/3", StatusCode::OK),
+                // block 3 is 30 minutes after genesis
+                ("/rpc/supply/at/1800", StatusCode::OK),
+                (&format!("/rpc/address/{address}"), StatusCode::OK),
+                ("/rpc/burns", StatusCode::OK),
+                ("/rpc/emission", StatusCode::OK),
+                ("/rpc/stats", StatusCode::OK),
+                ("/rpc/forks", StatusCode::OK),
+                ("/rpc/mining", StatusCode::OK),
+                ("/api/circulating", StatusCode::OK),
+                ("/api/total", StatusCode::OK),
+                ("/api/max", StatusCode::OK),
+                ("/metrics", StatusCode::OK),
+            ],
+        )
+        .await;
+
+        // the mining gateway's endpoints
+        let stale = block_digest(99).to_hex();
+        for uri in [
+            format!("/rpc/pow_puzzle/{address}"),
+            format!("/rpc/pow_puzzle/{address}/wait?after={stale}"),
+        ] {
+            let (status, _) = get_with_key(&router, &uri, MINER_KEY).await;
+            assert_eq!(StatusCode::OK, status, "GET {uri}");
+        }
+        let solution = Request::post("/rpc/provide_pow_solution")
+            .header(header::AUTHORIZATION, format!("Bearer {MINER_KEY}"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(pow_solution(block_digest(100))))
+            .unwrap();
+        assert_eq!(StatusCode::OK, send(&router, solution).await.0);
+    }
+
+    #[tokio::test]
+    async fn bad_requests_are_rejected() {
+        let (_, _, router, _keys) = gateway_explorer(node()).await;
+        let address = guesser_address();
+
+        assert_statuses(
+            &router,
+            &[
+                ("/rpc/address/nolgam1qqqq", StatusCode::BAD_REQUEST),
+                ("/address/nolgam1qqqq", StatusCode::NOT_FOUND),
+                ("/rqs", StatusCode::NOT_FOUND),
+                ("/rpc/supply/at/not-a-time", StatusCode::BAD_REQUEST),
+                (
+                    &format!("/rpc/pow_puzzle/{address}"),
+                    StatusCode::UNAUTHORIZED,
+                ),
+            ],
+        )
+        .await;
+        for (uri, key, status) in [
+            (
+                "/rpc/pow_puzzle/nolgam1qqqq".to_string(),
+                MINER_KEY,
+                StatusCode::BAD_REQUEST,
+            ),
+            (
+                format!("/rpc/pow_puzzle/{address}"),
+                "mallory-0123456789abcdef",
+                StatusCode::UNAUTHORIZED,
+            ),
+            (
+                format!("/rpc/pow_puzzle/{address}/wait"),
+                "mallory-0123456789abcdef",
+                StatusCode::UNAUTHORIZED,
+            ),
+        ] {
+            assert_eq!(
+                status,
+                get_with_key(&router, &uri, key).await.0,
+                "GET {uri}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_things_are_not_found() {
+        let (_, _, router) = explorer(node().with_utxo_index()).await;
+
+        assert_statuses(
+            &router,
+            &[
+                ("/no/such/page", StatusCode::NOT_FOUND),
+                ("/block/height/6", StatusCode::NOT_FOUND),
+                ("/block/tip-6", StatusCode::NOT_FOUND),
+                ("/utxo/6", StatusCode::NOT_FOUND),
+                ("/css/no-such.css", StatusCode::NOT_FOUND),
+                (
+                    "/assets/css/styles.0000000000000000.css",
+                    StatusCode::NOT_FOUND,
+                ),
+                ("/announcement/height/3/1", StatusCode::NOT_FOUND),
+                ("/announcement/height/2/0", StatusCode::NOT_FOUND),
+                // the proof-of-work endpoints exist only behind the gateway
+                ("/rpc/pow_puzzle/nolgam1qqqq", StatusCode::NOT_FOUND),
+            ],
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn outputs_are_tracked_from_mempool_to_block() {
+        let (_, _, router) = explorer(node().with_utxo_index()).await;
+
+        let (_, body) = get(
+            &router,
+            &format!(
+                "/rpc/output_status/{}",
+                output(2).canonical_commitment.to_hex()
+            ),
+        )
+        .await;
+        let status: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!("mined", status["status"]);
+        assert_eq!(2, status["block_height"]);
+
+        let (_, body) = get(
+            &router,
+            &format!(
+                "/rpc/output_status/{}",
+                mempool_output().canonical_commitment.to_hex()
+            ),
+        )
+        .await;
+        let status: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!("in_mempool", status["status"]);
+
+        let unknown = AdditionRecord::new(Digest::default());
+        let (_, body) = get(
+            &router,
+            &format!(
+                "/rpc/output_status/{}",
+                unknown.canonical_commitment.to_hex()
+            ),
+        )
+        .await;
+        let status: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!("not_known", status["status"]);
+    }
+
+    #[tokio::test]
+    async fn announcements_are_found_by_flag() {
+        let (_, _, router) = explorer(node().with_utxo_index()).await;
+
+        let (status, body) = get(
+            &router,
+            &format!("/rpc/announcements/search?flag={FLAG}&receiver_id={RECEIVER_ID}"),
+        )
+        .await;
+        assert_eq!(StatusCode::OK, status);
+        let results: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(1, results["total"]);
+
+        let (status, _) = get(&router, "/rpc/announcements/search?flag=7").await;
+        assert_eq!(StatusCode::BAD_REQUEST, status);
+    }
+
+    #[tokio::test]
+    async fn announcements_download_as_named_attachments() {
+        let (_, _, router) = explorer(node()).await;
+        let block_3 = block_digest(3).to_hex();
+        let message = [FLAG, RECEIVER_ID, 1, 2, 3];
+
+        let download = |uri: &str| {
+            let mut request = Request::get(uri).body(Body::empty()).unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 1234))));
+            let response = router.clone().oneshot(request);
+            async move {
+                let response = response.await.unwrap();
+                assert_eq!(StatusCode::OK, response.status());
+                let content_type = response.headers()[header::CONTENT_TYPE].clone();
+                let disposition = response.headers()[header::CONTENT_DISPOSITION].clone();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                (content_type, disposition, body)
+            }
+        };
+
+        let (content_type, disposition, body) =
+            download("/rpc/download/announcement/height/3/0?format=json").await;
+        assert_eq!("application/json", content_type);
+        assert_eq!(
+            format!("attachment; filename=\"block-{block_3}-announcement-0.json\""),
+            disposition
+        );
+        assert_eq!(serde_json::to_vec(&message).unwrap(), body);
+
+        let (_, disposition, body) =
+            download("/rpc/download/announcement/height/3/0?format=hex").await;
+        assert_eq!(
+            format!("attachment; filename=\"block-{block_3}-announcement-0.hex\""),
+            disposition
+        );
+        assert_eq!(
+            "0000000000000007 000000000000002a 0000000000000001 0000000000000002 0000000000000003",
+            body
+        );
+
+        let (content_type, _, body) =
+            download("/rpc/download/announcement/height/3/0?format=bin").await;
+        assert_eq!("application/octet-stream", content_type);
+        let encoding = body
+            .chunks(8)
+            .map(|chunk| BFieldElement::new(u64::from_le_bytes(chunk.try_into().unwrap())))
+            .collect::<Vec<_>>();
+        let decoded = *Announcement::decode(&encoding).unwrap();
+        assert_eq!(message.map(BFieldElement::new).to_vec(), decoded.message);
+
+        let (_, disposition, body) =
+            download("/rpc/download/announcements/height/3?format=json").await;
+        assert_eq!(
+            format!("attachment; filename=\"block-{block_3}-announcements.json\""),
+            disposition
+        );
+        assert_eq!(serde_json::to_vec(&[message]).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn index_features_are_unavailable_without_utxo_index() {
+        let (node, state, router) = explorer(node()).await;
+        let output_2 = output(2).canonical_commitment.to_hex();
+        let search = format!("search?flag={FLAG}&receiver_id={RECEIVER_ID}");
+
+        assert_statuses(
+            &router,
+            &[
+                (
+                    &format!("/output/{output_2}"),
+                    StatusCode::SERVICE_UNAVAILABLE,
+                ),
+                (
+                    &format!("/rpc/output_status/{output_2}"),
+                    StatusCode::SERVICE_UNAVAILABLE,
+                ),
+                (
+                    &format!("/announcements/{search}"),
+                    StatusCode::SERVICE_UNAVAILABLE,
+                ),
+                (
+                    &format!("/rpc/announcements/{search}"),
+                    StatusCode::SERVICE_UNAVAILABLE,
+                ),
+            ],
+        )
+        .await;
+        assert_eq!(0, node.calls("utxo_origin_block"));
+
+        // a node that lost its index since the last probe answers
+        // `UtxoIndexNotPresent`, which is reported the same way
+        state.set_rpc_client(node.client(), true);
+        assert_statuses(
+            &router,
+            &[(
+                &format!("/rpc/announcements/{search}"),
+                StatusCode::SERVICE_UNAVAILABLE,
+            )],
+        )
+        .await;
+        assert_eq!(2, node.calls("block_heights_by_announcement_flags"));
+    }
+
+    #[tokio::test]
+    async fn outage_of_the_node_is_an_error_not_an_answer() {
+        let (node, _, router) = explorer(node().with_utxo_index()).await;
+        node.disconnect();
+        let output_2 = output(2).canonical_commitment.to_hex();
+
+        assert_statuses(
+            &router,
+            &[
+                ("/rpc/block_info/tip", StatusCode::INTERNAL_SERVER_ERROR),
+                ("/rpc/utxo_digest/2", StatusCode::INTERNAL_SERVER_ERROR),
+                (
+                    &format!("/rpc/output_status/{output_2}"),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                ),
+                ("/block/tip", StatusCode::INTERNAL_SERVER_ERROR),
+                (
+                    &format!("/output/{output_2}"),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                ),
+            ],
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn pages_ask_to_retry_when_the_node_is_busy() {
+        let node = Arc::new(node());
+        for (queue, deadline_ms, status) in [
+            (0, 10_000, StatusCode::SERVICE_UNAVAILABLE),
+            (1, 10, StatusCode::GATEWAY_TIMEOUT),
+        ] {
+            let policy = RpcLimitsPolicy::from_json(&format!(
+                r#"{{"chain": {{"concurrency": 1, "queue": {queue}, "deadline_ms": {deadline_ms}}}}}"#
+            ))
+            .unwrap();
+            let limiter = Arc::new(RpcLimiter::new(&policy));
+            let (_, router) = explorer_with(AuthenticatedClient {
+                limiter: limiter.clone(),
+                ..node.client()
+            })
+            .await;
+            let (_permit, _) = limiter.admit(RpcClass::Chain).await.unwrap();
+
+            for uri in ["/block/tip", "/rpc/block_info/tip"] {
+                let mut request = Request::get(uri).body(Body::empty()).unwrap();
+                request
+                    .extensions_mut()
+                    .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 1234))));
+                let response = router.clone().oneshot(request).await.unwrap();
+                assert_eq!(status, response.status(), "GET {uri}");
+                assert_eq!(
+                    status == StatusCode::SERVICE_UNAVAILABLE,
+                    response.headers().contains_key(header::RETRY_AFTER),
+                    "GET {uri}"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn recorded_answers_replay_without_a_node() {
+        let fixtures = tempfile::tempdir().unwrap();
+        let node = Arc::new(node().with_utxo_index());
+        let recorder = Arc::new(FixtureRecorder::open(fixtures.path()).unwrap());
+        let client = RPCClient::from(NodeStub::Recording(node.connect(), recorder));
+        // as when recording from the command line, which starts with this
+        client.network(context::current()).await.unwrap().unwrap();
+        let (_, recording) = explorer_with(AuthenticatedClient {
+            client,
+            ..node.client()
+        })
+        .await;
+
+        let output_2 = output(2).canonical_commitment.to_hex();
+        let uris = [
+            "/block/height/3".to_string(),
+            "/utxo/2".to_string(),
+            "/rpc/block_info/tip".to_string(),
+            "/rpc/blocks/height/0..=5".to_string(),
+            format!("/rpc/output_status/{output_2}"),
+            format!("/rpc/announcements/search?flag={FLAG}&receiver_id={RECEIVER_ID}"),
+        ];
+        let mut recorded = vec![];
+        for uri in &uris {
+            recorded.push(get(&recording, uri).await);
+        }
+        node.disconnect();
+
+        let limiter = Arc::new(RpcLimiter::new(&RpcLimitsPolicy::default()));
+        let client = gen_replay_client(fixtures.path(), limiter).await.unwrap();
+        let (_, replaying) = explorer_with(client).await;
+        for (uri, recorded) in uris.iter().zip(recorded) {
+            assert_eq!(StatusCode::OK, recorded.0, "GET {uri}");
+            assert_eq!(recorded, get(&replaying, uri).await, "GET {uri}");
+        }
+
+        // requests never recorded are not answered
+        assert_eq!(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            get(&replaying, "/rpc/utxo_digest/1").await.0
+        );
+    }
+
+    #[tokio::test]
+    async fn pages_link_to_immutable_assets() {
+        let (_, state, router) = explorer(node()).await;
+        let stylesheet = state.load().assets.url("css/styles.css");
+        assert!(stylesheet.starts_with("/assets/css/styles."));
+        assert!(get(&router, "/").await.1.contains(&stylesheet));
+        assert!(get(&router, "/no/such/page").await.1.contains(&stylesheet));
+
+        let mut request = Request::get(&stylesheet).body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 1234))));
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!("text/css", response.headers()["content-type"]);
+        assert!(response.headers()["cache-control"]
+            .to_str()
+            .unwrap()
+            .contains("immutable"));
+    }
+
+    #[tokio::test]
+    async fn pages_are_branded_by_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme = dir.path().join("theme.json");
+        std::fs::write(
+            &theme,
+            r##"{
+                "logo": "https://example.org/logo.png",
+                "accent_color": "#8a2be2",
+                "footer_links": [{"text": "Community", "url": "https://example.org"}],
+                "extra_head_html": "<meta name=\"robots\" content=\"noindex\">"
+            }"##,
+        )
+        .unwrap();
+
+        let node = Arc::new(node());
+        let (_, router) =
+            explorer_with_args(node.client(), &["--theme-file", theme.to_str().unwrap()]).await;
+
+        let (status, utxo) = get(&router, "/utxo/2").await;
+        assert_eq!(StatusCode::OK, status);
+        for branding in [
+            "https://example.org/logo.png",
+            "--pico-primary: #8a2be2",
+            "<meta name=\"robots\" content=\"noindex\">",
+            ">Community</a>",
+        ] {
+            assert!(utxo.contains(branding), "{branding}");
+        }
+        // the accent colour is not the colour of all text
+        assert!(!utxo.contains("--pico-color: #8a2be2"));
+        let (_, root) = get(&router, "/").await;
+        assert!(root.contains(">Community</a>"));
+    }
+
+    #[cfg(feature = "template-overrides")]
+    #[tokio::test]
+    async fn pages_use_template_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("page")).unwrap();
+        let bundled = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/templates/web/html/page/utxo.html"
+        ));
+        std::fs::write(
+            dir.path().join("page/utxo.html"),
+            bundled.replace(
+                "<main class=\"container\">",
+                "<main class=\"container branded\">",
+            ),
+        )
+        .unwrap();
+
+        let node = Arc::new(node());
+        let (_, router) = explorer_with_args(
+            node.client(),
+            &["--templates-dir", dir.path().to_str().unwrap()],
+        )
+        .await;
+
+        let (status, utxo) = get(&router, "/utxo/2").await;
+        assert_eq!(StatusCode::OK, status);
+        assert!(utxo.contains("<main class=\"container branded\">"));
+        let (_, root) = get(&router, "/").await;
+        assert!(!root.contains("branded"));
+    }
+
+    #[tokio::test]
+    async fn supply_figures_are_computed_once_per_tip() {
+        let (node, state, router) = explorer(node()).await;
+        let publish_tip = |height| {
+            publish(
+                &state.load().chain_head,
+                ChainHead {
+                    tip: Some(block_digest(height)),
+                    proposal: None,
+                },
+            )
+        };
+
+        publish_tip(5);
+        let calls = node.calls("block_info");
+        for uri in ["/api/circulating", "/api/total", "/api/max", "/api/total"] {
+            assert_eq!(StatusCode::OK, get(&router, uri).await.0, "GET {uri}");
+        }
+        assert_eq!(calls + 1, node.calls("block_info"));
+
+        publish_tip(4);
+        get(&router, "/api/total").await;
+        get(&router, "/api/total").await;
+        assert_eq!(calls + 2, node.calls("block_info"));
+
+        // concurrent requests for a new tip wait for the first one's answer
+        publish_tip(3);
+        futures::future::join_all((0..4).map(|_| get(&router, "/api/circulating"))).await;
+        assert_eq!(calls + 3, node.calls("block_info"));
+    }
+
+    #[tokio::test]
+    async fn pow_endpoints_need_a_known_api_key() {
+        let (node, _, router, _keys) =
+            gateway_explorer_with_args(node(), &["--mining-show-miners"]).await;
+        node.set_pow_puzzle(block_digest(100));
+        let solution = pow_solution(block_digest(100));
+        let submit = |key: Option<&str>| {
+            let request = Request::post("/rpc/provide_pow_solution")
+                .header(header::CONTENT_TYPE, "application/json");
+            let request = match key {
+                Some(key) => request.header(header::AUTHORIZATION, format!("Bearer {key}")),
+                None => request,
+            };
+            request.body(Body::from(solution.clone())).unwrap()
+        };
+        let puzzle = format!("/rpc/pow_puzzle/{}", guesser_address());
+
+        assert_eq!(StatusCode::UNAUTHORIZED, get(&router, &puzzle).await.0);
+        assert_eq!(
+            StatusCode::UNAUTHORIZED,
+            get_with_key(&router, &puzzle, "mallory-0123456789abcdef")
+                .await
+                .0
+        );
+        assert_eq!(
+            StatusCode::OK,
+            get_with_key(&router, &puzzle, MINER_KEY).await.0
+        );
+
+        assert_eq!(
+            StatusCode::UNAUTHORIZED,
+            send(&router, submit(None)).await.0
+        );
+        assert_eq!(
+            StatusCode::UNAUTHORIZED,
+            send(&router, submit(Some("mallory-0123456789abcdef")))
+                .await
+                .0
+        );
+        assert_eq!(
+            (StatusCode::OK, "true".to_string()),
+            send(&router, submit(Some(MINER_KEY))).await
+        );
+
+        let (_, mining) = get(&router, "/rpc/mining").await;
+        let mining: serde_json::Value = serde_json::from_str(&mining).unwrap();
+        assert_eq!(1, mining["counts"][0]["accepted"]);
+        assert_eq!("alice", mining["counts"][0]["miner"]);
+        assert_eq!(guesser_address(), mining["counts"][0]["address"]);
+    }
+
+    #[tokio::test]
+    async fn each_api_key_is_rate_limited() {
+        let (node, _, router, _keys) =
+            gateway_explorer_with_args(node(), &["--mining-rate-limit-burst", "2"]).await;
+        node.set_pow_puzzle(block_digest(100));
+        let puzzle = format!("/rpc/pow_puzzle/{}", guesser_address());
+
+        for _ in 0..2 {
+            let (status, _) = get_with_key(&router, &puzzle, MINER_KEY).await;
+            assert_eq!(StatusCode::OK, status);
+        }
+        let (status, _) = get_with_key(&router, &puzzle, MINER_KEY).await;
+        assert_eq!(StatusCode::TOO_MANY_REQUESTS, status);
+        // unknown keys do not count against the miner's quota, nor get one
+        let (status, _) = get_with_key(&router, &puzzle, "mallory-0123456789abcdef").await;
+        assert_eq!(StatusCode::UNAUTHORIZED, status);
+    }
+
+    #[tokio::test]
+    async fn mining_stats_show_miners_only_if_asked_to() {
+        let (node, _, router, _keys) = gateway_explorer(node()).await;
+        node.set_pow_puzzle(block_digest(100));
+        let solution = Request::post("/rpc/provide_pow_solution")
+            .header(header::AUTHORIZATION, format!("Bearer {MINER_KEY}"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(pow_solution(block_digest(100))))
+            .unwrap();
+        assert_eq!(StatusCode::OK, send(&router, solution).await.0);
+
+        let (_, mining) = get(&router, "/rpc/mining").await;
+        let mining: serde_json::Value = serde_json::from_str(&mining).unwrap();
+        assert_eq!(1, mining["accepted"]);
+        assert_eq!(0, mining["counts"].as_array().unwrap().len());
+        assert_eq!(0, mining["recent"].as_array().unwrap().len());
+        let (_, page) = get(&router, "/mining").await;
+        assert!(page.contains("Solutions accepted: 1, rejected: 0."));
+        assert!(!page.contains("alice"));
+    }
+
+    #[tokio::test]
+    async fn puzzle_wait_times_out_without_a_new_puzzle() {
+        let (node, _, router, _keys) = gateway_explorer(node()).await;
+        node.set_pow_puzzle(block_digest(100));
+        let after = block_digest(100).to_hex();
+        let uri = format!(
+            "/rpc/pow_puzzle/{}/wait?after={after}&timeout=1",
+            guesser_address()
+        );
+
+        let (status, _) = get_with_key(&router, &uri, MINER_KEY).await;
+        assert_eq!(StatusCode::NO_CONTENT, status);
+    }
+
+    #[tokio::test]
+    async fn puzzle_wait_answers_at_once_if_the_puzzle_is_new() {
+        let (node, _, router, _keys) = gateway_explorer(node()).await;
+        node.set_pow_puzzle(block_digest(101));
+        let stale = block_digest(100).to_hex();
+        let address = guesser_address();
+
+        for uri in [
+            format!("/rpc/pow_puzzle/{address}/wait?after={stale}&timeout=60"),
+            format!("/rpc/pow_puzzle/{address}/wait?timeout=60"),
+        ] {
+            let (status, body) = tokio::time::timeout(
+                Duration::from_secs(5),
+                get_with_key(&router, &uri, MINER_KEY),
+            )
+            .await
+            .expect("answers without waiting");
+            assert_eq!(StatusCode::OK, status, "GET {uri}");
+            let puzzle: ProofOfWorkPuzzle = serde_json::from_str(&body).unwrap();
+            assert_eq!(block_digest(101), puzzle.id);
+        }
+    }
+
+    #[tokio::test]
+    async fn puzzle_wait_wakes_when_the_chain_head_changes() {
+        let (node, state, router, _keys) = gateway_explorer(node()).await;
+        node.set_pow_puzzle(block_digest(100));
+        let after = block_digest(100).to_hex();
+        let uri = format!(
+            "/rpc/pow_puzzle/{}/wait?after={after}&timeout=60",
+            guesser_address()
+        );
+
+        let waiting = tokio::spawn({
+            let router = router.clone();
+            async move { get_with_key(&router, &uri, MINER_KEY).await }
+        });
+        while node.calls("pow_puzzle_external_key") == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert!(!waiting.is_finished());
+
+        node.set_pow_puzzle(block_digest(101));
+        publish(
+            &state.load().chain_head,
+            ChainHead {
+                tip: Some(block_digest(5)),
+                proposal: Some(block_digest(101)),
+            },
+        );
+        let (status, body) = tokio::time::timeout(Duration::from_secs(5), waiting)
+            .await
+            .expect("woken by the new chain head")
+            .unwrap();
+        assert_eq!(StatusCode::OK, status);
+        let puzzle: ProofOfWorkPuzzle = serde_json::from_str(&body).unwrap();
+        assert_eq!(block_digest(101), puzzle.id);
+        assert_eq!(2, node.calls("pow_puzzle_external_key"));
+    }
+}
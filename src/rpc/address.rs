@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use axum::extract::Path;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Json;
+use axum::response::Response;
+
+use crate::model::address::decode_generation_address;
+use crate::model::address::AddressDetails;
+use crate::model::app_state::AppState;
+
+/// Route: `GET /rpc/address/:bech32m`.
+///
+/// Validates a generation address against the network this explorer follows
+/// and returns its public parts. Invalid addresses, and addresses of another
+/// network, get a 400 with the reason.
+#[axum::debug_handler]
+pub async fn address(
+    Path(bech32m): Path<String>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AddressDetails>, Response> {
+    let s = state.load();
+    decode_generation_address(bech32m.trim(), s.network)
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()).into_response())
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use axum::extract::Path;
+use axum::extract::State;
+use axum::response::Json;
+use axum::response::Response;
+use neptune_cash::api::export::BlockHeight;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use serde::Serialize;
+use tarpc::context;
+
+use crate::http_util::not_found_err;
+use crate::http_util::rpc_err;
+use crate::http_util::rpc_method_err;
+use crate::model::announcement_selector::AnnouncementSelector;
+use crate::model::announcement_type::AnnouncementType;
+use crate::model::app_state::AppState;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnouncementResponse {
+    pub block_digest: Digest,
+    pub block_height: BlockHeight,
+    pub index: usize,
+    pub num_announcements: usize,
+
+    /// The decoded announcement: `{"type": <name>, "payload": ..}`.
+    pub announcement: AnnouncementType,
+}
+
+/// The announcement at the given index in the given block, decoded with the
+/// app state's announcement decoders.
+#[axum::debug_handler]
+pub async fn announcement(
+    Path(AnnouncementSelector {
+        block_selector,
+        index,
+    }): Path<AnnouncementSelector>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AnnouncementResponse>, Response> {
+    let s = state.load();
+    let block_info = s
+        .rpc_client
+        .block_info(context::current(), s.token(), block_selector)
+        .await
+        .map_err(rpc_err)?
+        .map_err(rpc_method_err)?
+        .ok_or_else(not_found_err)?;
+
+    // by digest, so that a new tip in the meantime cannot change the block
+    let announcements = s
+        .rpc_client
+        .announcements_in_block(
+            context::current(),
+            s.token(),
+            BlockSelector::Digest(block_info.digest),
+        )
+        .await
+        .map_err(rpc_err)?
+        .map_err(rpc_method_err)?
+        .ok_or_else(not_found_err)?;
+    let num_announcements = announcements.len();
+    let announcement = announcements
+        .get(index)
+        .cloned()
+        .ok_or_else(not_found_err)?;
+
+    Ok(Json(AnnouncementResponse {
+        block_digest: block_info.digest,
+        block_height: block_info.height,
+        index,
+        num_announcements,
+        announcement: s.announcement_decoders.parse(announcement),
+    }))
+}
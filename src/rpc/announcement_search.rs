@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::extract::Query;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Json;
+use axum::response::Response;
+
+use crate::http_util::rpc_err;
+use crate::http_util::rpc_method_err;
+use crate::http_util::service_unavailable_err;
+use crate::model::announcement_search::search_announcements;
+use crate::model::announcement_search::AnnouncementSearchError;
+use crate::model::announcement_search::AnnouncementSearchQuery;
+use crate::model::announcement_search::AnnouncementSearchResults;
+use crate::model::announcement_search::INDEX_REQUIRED_MESSAGE;
+use crate::model::app_state::AppState;
+
+/// Route: `GET /rpc/announcements/search?flag=..&receiver_id=..`.
+///
+/// Optional parameters: `indices=true`, `page`, `page_size`. See
+/// [`AnnouncementSearchQuery`]; with `indices=true`, the page size is at most
+/// [`MAX_PAGE_SIZE_WITH_INDICES`](crate::model::announcement_search::MAX_PAGE_SIZE_WITH_INDICES). Both `flag` and `receiver_id` are required,
+/// since the node's UTXO index is keyed by the pair; a query without either is
+/// a 400.
+#[axum::debug_handler]
+pub async fn announcement_search(
+    Query(query): Query<AnnouncementSearchQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<AnnouncementSearchResults>, Response> {
+    let s = state.load();
+
+    if !s.maintains_utxo_index {
+        return Err(service_unavailable_err(INDEX_REQUIRED_MESSAGE));
+    }
+
+    let results = search_announcements(&s, &query)
+        .await
+        .map_err(|e| match e {
+            AnnouncementSearchError::Query(q) => {
+                (StatusCode::BAD_REQUEST, q.to_string()).into_response()
+            }
+            AnnouncementSearchError::Transport(t) => rpc_err(t),
+            AnnouncementSearchError::Method(m) => rpc_method_err(m),
+            AnnouncementSearchError::IndexUnavailable => {
+                service_unavailable_err(INDEX_REQUIRED_MESSAGE)
+            }
+        })?;
+
+    Ok(Json(results))
+}
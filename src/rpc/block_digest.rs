@@ -7,6 +7,7 @@ use axum::response::Json;
 use neptune_cash::prelude::twenty_first::tip5::Digest;
 use tarpc::context;
 
+use crate::http_util::block_selector_err;
 use crate::http_util::not_found_err;
 use crate::http_util::rpc_err;
 use crate::http_util::rpc_method_err;
@@ -19,9 +20,14 @@ pub async fn block_digest(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<Digest>, impl IntoResponse> {
     let s = state.load();
+    let block_selector = selector
+        .resolve(&s)
+        .await
+        .map_err(block_selector_err)?
+        .ok_or_else(not_found_err)?;
     match s
         .rpc_client
-        .block_digest(context::current(), s.token(), selector.into())
+        .block_digest(context::current(), s.token(), block_selector)
         .await
         .map_err(rpc_err)?
         .map_err(rpc_method_err)?
@@ -7,6 +7,7 @@ use axum::response::Response;
 use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
 use tarpc::context;
 
+use crate::http_util::block_selector_err;
 use crate::http_util::not_found_err;
 use crate::http_util::rpc_err;
 use crate::http_util::rpc_method_err;
@@ -19,9 +20,14 @@ pub async fn block_info(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<BlockInfo>, Response> {
     let s = state.load();
+    let block_selector = selector
+        .resolve(&s)
+        .await
+        .map_err(block_selector_err)?
+        .ok_or_else(not_found_err)?;
     let block_info = s
         .rpc_client
-        .block_info(context::current(), s.token(), selector.into())
+        .block_info(context::current(), s.token(), block_selector)
         .await
         .map_err(rpc_err)?
         .map_err(rpc_method_err)?
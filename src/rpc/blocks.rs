@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use axum::extract::Path;
+use axum::extract::State;
+use axum::response::Json;
+use axum::response::Response;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use tarpc::context;
+
+use crate::http_util::rpc_err;
+use crate::http_util::rpc_method_err;
+use crate::model::app_state::AppState;
+use crate::model::height_range::HeightRange;
+
+/// Route: `GET /rpc/blocks/height/<start>..<end>` (or `..=<end>`).
+///
+/// The canonical blocks in the range, in order of height, up to the tip. At
+/// most [`MAX_RANGE_BLOCKS`](crate::model::height_range::MAX_RANGE_BLOCKS)
+/// blocks per request.
+#[axum::debug_handler]
+pub async fn blocks(
+    Path(range): Path<HeightRange>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<BlockInfo>>, Response> {
+    let s = state.load();
+
+    let mut blocks = vec![];
+    for height in range.heights() {
+        let block_info = s
+            .rpc_client
+            .block_info(
+                context::current(),
+                s.token(),
+                BlockSelector::Height(height.into()),
+            )
+            .await
+            .map_err(rpc_err)?
+            .map_err(rpc_method_err)?;
+        let Some(block_info) = block_info else {
+            // beyond the tip
+            break;
+        };
+        blocks.push(block_info);
+    }
+
+    Ok(Json(blocks))
+}
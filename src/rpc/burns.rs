@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::Json;
+use neptune_cash::api::export::BlockHeight;
+use neptune_cash::api::export::NativeCurrencyAmount;
+use serde::Serialize;
+
+use crate::model::app_state::AppState;
+use crate::model::burn_registry::BurnEntry;
+use crate::rpc::supply::AmountResponse;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BurnResponse {
+    pub height: BlockHeight,
+    pub amount: AmountResponse,
+    pub description: String,
+    pub evidence: String,
+}
+
+impl From<&BurnEntry> for BurnResponse {
+    fn from(entry: &BurnEntry) -> Self {
+        Self {
+            height: entry.height,
+            amount: entry.amount.into(),
+            description: entry.description.clone(),
+            evidence: entry.evidence.clone(),
+        }
+    }
+}
+
+/// The known burns on the connected node's network, as subtracted from the
+/// supply.
+#[derive(Debug, Clone, Serialize)]
+pub struct BurnsResponse {
+    pub network: String,
+    pub total_burned: AmountResponse,
+    /// In order of height.
+    pub burns: Vec<BurnResponse>,
+}
+
+/// Route: `GET /rpc/burns`.
+#[axum::debug_handler]
+pub async fn burns(State(state): State<Arc<AppState>>) -> Json<BurnsResponse> {
+    let s = state.load();
+    let burns = s.burns();
+
+    Json(BurnsResponse {
+        network: s.network.to_string(),
+        total_burned: burns
+            .iter()
+            .map(|entry| entry.amount)
+            .sum::<NativeCurrencyAmount>()
+            .into(),
+        burns: burns.iter().map(BurnResponse::from).collect(),
+    })
+}
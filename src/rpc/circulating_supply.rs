@@ -13,6 +13,13 @@ use crate::shared::monetary_supplies;
 /// Return the current monetary amount that is liquid, assuming all redemptions
 /// on the old chain have successfully been made. Returned unit is in number of
 /// coins. To convert to number of nau, multiply by $4*10^{30}$/
+///
+/// Kept for compatibility. The value is rounded up to whole coins; use
+/// `/rpc/supply` for the exact amount and its components.
+///
+/// The value is the `liquid` of `/rpc/supply`, the same as before that was
+/// added for every height this endpoint used to answer. From height 139,505 it
+/// used to fail, like `/rpc/total_supply`.
 #[axum::debug_handler]
 pub async fn circulating_supply(State(state): State<Arc<AppState>>) -> Result<Json<i32>, Response> {
     let s = state.load();
@@ -24,7 +31,7 @@ pub async fn circulating_supply(State(state): State<Arc<AppState>>) -> Result<Js
         .map_err(rpc_err)?
         .map_err(rpc_method_err)?;
 
-    let (liquid_supply, _) = monetary_supplies(block_height);
+    let (liquid_supply, _) = monetary_supplies(s.network, block_height, s.burns());
 
     Ok(Json(liquid_supply.ceil_num_whole_coins()))
 }
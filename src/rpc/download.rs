@@ -0,0 +1,187 @@
+//! Raw data downloads, served as attachments named after the block digest.
+
+use std::sync::Arc;
+
+use axum::extract::Path;
+use axum::extract::Query;
+use axum::extract::State;
+use axum::http::header;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use neptune_cash::api::export::Announcement;
+use neptune_cash::prelude::triton_vm::prelude::BFieldCodec;
+use neptune_cash::protocol::consensus::block::block_info::BlockInfo;
+use neptune_cash::protocol::consensus::block::block_selector::BlockSelector;
+use tarpc::context;
+
+use crate::http_util::block_selector_err;
+use crate::http_util::node_err;
+use crate::http_util::not_found_err;
+use crate::http_util::rpc_err;
+use crate::http_util::rpc_method_err;
+use crate::model::announcement_selector::AnnouncementSelector;
+use crate::model::app_state::AppState;
+use crate::model::app_state::AppStateInner;
+use crate::model::block_selector_extended::BlockSelectorExtended;
+use crate::model::download_format::hex_line;
+use crate::model::download_format::to_le_bytes;
+use crate::model::download_format::DownloadFormat;
+use crate::model::download_format::DownloadQuery;
+use crate::model::node_error::fetch_block_info;
+
+/// The message of a single announcement.
+///
+/// Filename: `block-<digest>-announcement-<index>.<ext>`.
+#[axum::debug_handler]
+pub async fn announcement_download(
+    Path(AnnouncementSelector {
+        block_selector,
+        index,
+    }): Path<AnnouncementSelector>,
+    Query(DownloadQuery { format }): Query<DownloadQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let s = state.load();
+    let block_info = fetch_block_info(&s, block_selector)
+        .await
+        .map_err(node_err)?
+        .ok_or_else(not_found_err)?;
+    let announcement = fetch_announcements(&s, &block_info)
+        .await?
+        .into_iter()
+        .nth(index)
+        .ok_or_else(not_found_err)?;
+
+    let body = match format {
+        DownloadFormat::Json => serde_json::to_vec(&decimal(&announcement)).map_err(json_err)?,
+        DownloadFormat::Hex => hex_line(&announcement.message).into_bytes(),
+        DownloadFormat::Bin => to_le_bytes(&announcement.encode()),
+    };
+    let filename = format!(
+        "block-{}-announcement-{index}.{}",
+        block_info.digest.to_hex(),
+        format.extension()
+    );
+
+    Ok(attachment(format, &filename, body))
+}
+
+/// All announcements of a block, in order.
+///
+/// As JSON, a list of messages; as hex, one message per line; as binary, the
+/// `BFieldCodec` encoding of the list.
+///
+/// Filename: `block-<digest>-announcements.<ext>`.
+#[axum::debug_handler]
+pub async fn block_announcements_download(
+    Path(selector): Path<BlockSelectorExtended>,
+    Query(DownloadQuery { format }): Query<DownloadQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let s = state.load();
+    let block_selector = selector
+        .resolve(&s)
+        .await
+        .map_err(block_selector_err)?
+        .ok_or_else(not_found_err)?;
+    let block_info = fetch_block_info(&s, block_selector)
+        .await
+        .map_err(node_err)?
+        .ok_or_else(not_found_err)?;
+    let announcements = fetch_announcements(&s, &block_info).await?;
+
+    let body = match format {
+        DownloadFormat::Json => {
+            serde_json::to_vec(&announcements.iter().map(decimal).collect::<Vec<_>>())
+                .map_err(json_err)?
+        }
+        DownloadFormat::Hex => announcements
+            .iter()
+            .map(|announcement| hex_line(&announcement.message) + "\n")
+            .collect::<String>()
+            .into_bytes(),
+        DownloadFormat::Bin => to_le_bytes(&announcements.encode()),
+    };
+    let filename = format!(
+        "block-{}-announcements.{}",
+        block_info.digest.to_hex(),
+        format.extension()
+    );
+
+    Ok(attachment(format, &filename, body))
+}
+
+/// The block's [`BlockInfo`]. Only available as JSON, since `BlockInfo` has no
+/// `BFieldCodec` encoding.
+///
+/// Filename: `block-<digest>-info.json`.
+#[axum::debug_handler]
+pub async fn block_info_download(
+    Path(selector): Path<BlockSelectorExtended>,
+    Query(DownloadQuery { format }): Query<DownloadQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    if format != DownloadFormat::Json {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "block info is only available in json format".to_string(),
+        )
+            .into_response());
+    }
+
+    let s = state.load();
+    let block_selector = selector
+        .resolve(&s)
+        .await
+        .map_err(block_selector_err)?
+        .ok_or_else(not_found_err)?;
+    let block_info = fetch_block_info(&s, block_selector)
+        .await
+        .map_err(node_err)?
+        .ok_or_else(not_found_err)?;
+    let body = serde_json::to_vec(&block_info).map_err(json_err)?;
+    let filename = format!("block-{}-info.json", block_info.digest.to_hex());
+
+    Ok(attachment(format, &filename, body))
+}
+
+fn attachment(format: DownloadFormat, filename: &str, body: Vec<u8>) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+fn decimal(announcement: &Announcement) -> Vec<u64> {
+    announcement.message.iter().map(|bfe| bfe.value()).collect()
+}
+
+fn json_err(e: serde_json::Error) -> Response {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+}
+
+/// The announcements of the block `block_info` describes. Asked for by digest,
+/// so that a new tip since `block_info` was fetched cannot change the block.
+async fn fetch_announcements(
+    s: &AppStateInner,
+    block_info: &BlockInfo,
+) -> Result<Vec<Announcement>, Response> {
+    s.rpc_client
+        .announcements_in_block(
+            context::current(),
+            s.token(),
+            BlockSelector::Digest(block_info.digest),
+        )
+        .await
+        .map_err(rpc_err)?
+        .map_err(rpc_method_err)?
+        .ok_or_else(not_found_err)
+}
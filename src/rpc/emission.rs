@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::Json;
+use axum::response::Response;
+use neptune_cash::api::export::BlockHeight;
+use serde::Serialize;
+
+use crate::http_util::node_err;
+use crate::model::app_state::AppState;
+use crate::model::emission::emission_schedule;
+use crate::model::emission::halving_countdown;
+use crate::model::emission::GenerationEmission;
+use crate::rpc::supply::AmountResponse;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationResponse {
+    pub generation: u64,
+    pub start_height: BlockHeight,
+    /// Inclusive.
+    pub end_height: BlockHeight,
+    pub block_subsidy: AmountResponse,
+    pub liquid_per_block: AmountResponse,
+    pub timelocked_per_block: AmountResponse,
+    /// Total supply after the generation's last block.
+    pub cumulative_supply: AmountResponse,
+}
+
+impl From<GenerationEmission> for GenerationResponse {
+    fn from(generation: GenerationEmission) -> Self {
+        Self {
+            generation: generation.generation,
+            start_height: generation.start_height,
+            end_height: generation.end_height,
+            block_subsidy: generation.block_subsidy.into(),
+            liquid_per_block: generation.liquid_per_block.into(),
+            timelocked_per_block: generation.timelocked_per_block.into(),
+            cumulative_supply: generation.cumulative_supply.into(),
+        }
+    }
+}
+
+/// The emission schedule and where the chain currently is in it. Times are in
+/// milliseconds, like block timestamps.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmissionResponse {
+    pub network: String,
+    pub tip_height: BlockHeight,
+    pub current_generation: u64,
+    pub next_halving_height: BlockHeight,
+    pub blocks_to_next_halving: u64,
+    /// Over the most recent blocks; absent if only genesis exists.
+    pub average_block_interval_ms: Option<u64>,
+    /// Unix time; absent if only genesis exists.
+    pub next_halving_eta_ms: Option<u64>,
+    pub generations: Vec<GenerationResponse>,
+}
+
+/// Route: `GET /rpc/emission`.
+#[axum::debug_handler]
+pub async fn emission(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<EmissionResponse>, Response> {
+    let s = state.load();
+
+    let countdown = halving_countdown(&s).await.map_err(node_err)?;
+
+    Ok(Json(EmissionResponse {
+        network: s.network.to_string(),
+        tip_height: countdown.tip_height,
+        current_generation: countdown.current_generation,
+        next_halving_height: countdown.next_halving_height,
+        blocks_to_next_halving: countdown.blocks_remaining,
+        average_block_interval_ms: countdown
+            .average_block_interval
+            .map(|interval| interval.to_millis()),
+        next_halving_eta_ms: countdown.eta.map(|eta| eta.to_millis()),
+        generations: emission_schedule(s.network, s.burns())
+            .into_iter()
+            .map(GenerationResponse::from)
+            .collect(),
+    }))
+}
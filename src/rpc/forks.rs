@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::Json;
+use serde::Serialize;
+
+use crate::model::app_state::AppState;
+use crate::model::forks::Reorg;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ForksResponse {
+    /// Observed reorganizations, most recent first.
+    pub reorgs: Vec<Reorg>,
+}
+
+/// Route: `GET /rpc/forks`.
+///
+/// The reorganizations observed by this explorer, served from memory. Timestamps
+/// are unix milliseconds.
+#[axum::debug_handler]
+pub async fn forks(State(state): State<Arc<AppState>>) -> Json<ForksResponse> {
+    let s = state.load();
+    let reorgs = s.fork_log.read().await.reorgs().rev().cloned().collect();
+    Json(ForksResponse { reorgs })
+}
@@ -0,0 +1,23 @@
+//! Admission-control metrics in the Prometheus text exposition format.
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::response::Response;
+
+use crate::model::app_state::AppState;
+use crate::model::rpc_limits::prometheus_metrics;
+
+/// Route: `GET /metrics`. In-flight and queued calls into neptune-core, and how
+/// many were admitted, shed or timed out, per RPC class.
+#[axum::debug_handler]
+pub async fn metrics(State(state): State<Arc<AppState>>) -> Response {
+    let metrics = state.load().rpc_client.limiter.metrics();
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        prometheus_metrics(&metrics),
+    )
+        .into_response()
+}
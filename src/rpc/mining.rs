@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::response::Json;
+use serde::Serialize;
+
+use crate::model::app_state::AppState;
+use crate::model::mining_gateway::Submission;
+use crate::model::mining_gateway::SubmissionCounts;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MiningResponse {
+    /// Whether the mining gateway is enabled; if not, all else is empty.
+    pub enabled: bool,
+
+    /// Interval in milliseconds at which each API key regains a request.
+    pub rate_limit_ms: u64,
+    /// Number of requests an API key may make at once.
+    pub rate_limit_burst: u64,
+
+    /// Accepted solutions of all miners.
+    pub accepted: u64,
+    /// Rejected solutions of all miners.
+    pub rejected: u64,
+
+    /// Accepted and rejected solutions per miner and address. Empty unless
+    /// the explorer runs with `--mining-show-miners`.
+    pub counts: Vec<SubmissionCounts>,
+    /// Recent submissions, most recent first. Empty unless the explorer runs
+    /// with `--mining-show-miners`.
+    pub recent: Vec<Submission>,
+}
+
+/// Route: `GET /rpc/mining`.
+///
+/// Proof-of-work submissions made through the mining gateway: totals, and per
+/// miner with `--mining-show-miners`. Timestamps are unix milliseconds.
+#[axum::debug_handler]
+pub async fn mining(State(state): State<Arc<AppState>>) -> Json<MiningResponse> {
+    let s = state.load();
+    let ((accepted, rejected), counts, recent) = match &s.mining_gateway {
+        Some(gateway) => {
+            let log = gateway.log.read().await;
+            match s.config.mining_show_miners {
+                true => (
+                    log.totals(),
+                    log.counts(),
+                    log.recent().rev().cloned().collect(),
+                ),
+                false => (log.totals(), vec![], vec![]),
+            }
+        }
+        None => ((0, 0), vec![], vec![]),
+    };
+    Json(MiningResponse {
+        enabled: s.mining_gateway.is_some(),
+        rate_limit_ms: s.config.mining_rate_limit_ms,
+        rate_limit_burst: s.config.mining_rate_limit_burst,
+        accepted,
+        rejected,
+        counts,
+        recent,
+    })
+}
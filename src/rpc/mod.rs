@@ -1,8 +1,21 @@
+pub mod address;
+pub mod announcement;
+pub mod announcement_search;
 pub mod block_digest;
 pub mod block_info;
+pub mod blocks;
+pub mod burns;
 pub mod circulating_supply;
+pub mod download;
+pub mod emission;
+pub mod forks;
+pub mod metrics;
+pub mod mining;
 pub mod output_status;
+pub mod plain_text_supply;
 pub mod pow_puzzle;
 pub mod provide_pow_solution;
+pub mod stats;
+pub mod supply;
 pub mod total_supply;
 pub mod utxo_digest;
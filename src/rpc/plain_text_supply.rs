@@ -0,0 +1,49 @@
+//! Supply figures as bare decimal numbers of coins in `text/plain`, the format
+//! market data aggregators such as CoinGecko and CoinMarketCap expect.
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use neptune_cash::api::export::NativeCurrencyAmount;
+
+use crate::http_util::node_err;
+use crate::model::app_state::AppState;
+use crate::model::supply_cache::cached_supply;
+use crate::model::supply_cache::SupplySnapshot;
+use crate::shared::exact_coins_string;
+
+/// Route: `GET /api/circulating`. The liquid supply at the tip.
+#[axum::debug_handler]
+pub async fn api_circulating(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    plain_text_supply(&state, |snapshot| snapshot.liquid).await
+}
+
+/// Route: `GET /api/total`. The liquid plus the time-locked supply at the tip.
+#[axum::debug_handler]
+pub async fn api_total(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    plain_text_supply(&state, |snapshot| snapshot.total).await
+}
+
+/// Route: `GET /api/max`. The supply once the emission schedule is exhausted.
+#[axum::debug_handler]
+pub async fn api_max(State(state): State<Arc<AppState>>) -> Result<Response, Response> {
+    plain_text_supply(&state, |snapshot| snapshot.max).await
+}
+
+async fn plain_text_supply(
+    state: &AppState,
+    figure: fn(&SupplySnapshot) -> NativeCurrencyAmount,
+) -> Result<Response, Response> {
+    let s = state.load();
+
+    let snapshot = cached_supply(&s).await.map_err(node_err)?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        exact_coins_string(figure(&snapshot)),
+    )
+        .into_response())
+}
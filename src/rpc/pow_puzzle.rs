@@ -1,37 +1,131 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::extract::Path;
+use axum::extract::Query;
 use axum::extract::State;
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::response::Json;
+use axum::response::Response;
 use neptune_cash::application::rpc::server::error::RpcError;
 use neptune_cash::application::rpc::server::proof_of_work_puzzle::ProofOfWorkPuzzle;
+use neptune_cash::prelude::tasm_lib::prelude::Digest;
 use neptune_cash::state::wallet::address::generation_address::GenerationReceivingAddress;
+use serde::Deserialize;
 use tarpc::context;
+use tokio::time::Instant;
 
+use crate::http_util::mining_gateway_err;
 use crate::http_util::not_found_err;
 use crate::http_util::rpc_err;
 use crate::http_util::rpc_method_err;
 use crate::model::app_state::AppState;
+use crate::model::app_state::AppStateInner;
+use crate::model::mining_gateway::authorize;
+use crate::model::mining_gateway::ApiKey;
+use crate::model::mining_gateway::MiningGateway;
 
+/// How long `/rpc/pow_puzzle/:address/wait` holds a request by default.
+pub const DEFAULT_PUZZLE_WAIT: Duration = Duration::from_secs(30);
+
+/// Upper bound on the `timeout` of `/rpc/pow_puzzle/:address/wait`.
+pub const MAX_PUZZLE_WAIT: Duration = Duration::from_secs(60);
+
+/// Route: `GET /rpc/pow_puzzle/:address`, through the mining gateway only.
+///
+/// A puzzle whose reward goes to the given generation address. The gateway
+/// remembers which miner and address each puzzle was issued for, to attribute
+/// solutions.
 #[axum::debug_handler]
 pub async fn pow_puzzle(
+    api_key: ApiKey,
     Path(address): Path<String>,
     State(state): State<Arc<AppState>>,
-) -> Result<Json<ProofOfWorkPuzzle>, impl IntoResponse> {
+) -> Result<Json<ProofOfWorkPuzzle>, Response> {
     let s = state.load();
-    let Ok(receiving_address) = GenerationReceivingAddress::from_bech32m(&address, s.network)
-    else {
-        return Err(rpc_method_err(RpcError::Failed(address)));
+    let (gateway, miner) = authorize(&s, &api_key).map_err(mining_gateway_err)?;
+    match fetch_puzzle(&s, gateway, miner, &address).await? {
+        Some(pow_puzzle) => Ok(Json(pow_puzzle)),
+        None => Err(not_found_err()),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaitQuery {
+    /// Id of the puzzle the miner is working on.
+    pub after: Option<Digest>,
+    /// Seconds to wait at most, up to [`MAX_PUZZLE_WAIT`].
+    pub timeout: Option<u64>,
+}
+
+/// Route: `GET /rpc/pow_puzzle/:address/wait?after=<puzzle_id>&timeout=<secs>`,
+/// through the mining gateway only.
+///
+/// Long-poll variant of [`pow_puzzle`]: answers as soon as the puzzle for the
+/// address differs from `after`, either immediately or once the node moves to
+/// a new tip or proposal. Without `after`, answers with the current puzzle, or
+/// waits for the node to have one. If nothing changes before the timeout, the
+/// answer is `204 No Content` and the miner should ask again.
+///
+/// Waiting requests do not poll the node; they are woken by the shared
+/// [tip watcher](crate::model::tip_watcher).
+#[axum::debug_handler]
+pub async fn pow_puzzle_wait(
+    api_key: ApiKey,
+    Path(address): Path<String>,
+    Query(query): Query<WaitQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, Response> {
+    let s = state.load();
+    let (gateway, miner) = authorize(&s, &api_key).map_err(mining_gateway_err)?;
+    let timeout = query
+        .timeout
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_PUZZLE_WAIT)
+        .min(MAX_PUZZLE_WAIT);
+    let deadline = Instant::now() + timeout;
+
+    // Subscribe before fetching, so that a change in between is not missed.
+    let mut chain_head = s.chain_head.subscribe();
+    chain_head.borrow_and_update();
+    loop {
+        let pow_puzzle = fetch_puzzle(&s, gateway, miner, &address).await?;
+        if let Some(pow_puzzle) = pow_puzzle.filter(|puzzle| Some(puzzle.id) != query.after) {
+            return Ok(Json(pow_puzzle).into_response());
+        }
+
+        match tokio::time::timeout_at(deadline, chain_head.changed()).await {
+            Ok(Ok(())) => continue,
+            // The tip watcher stopped; nothing will change.
+            Ok(Err(_)) | Err(_) => return Ok(StatusCode::NO_CONTENT.into_response()),
+        }
+    }
+}
+
+/// The node's puzzle for `address`, remembered as issued to `miner`.
+async fn fetch_puzzle(
+    s: &AppStateInner,
+    gateway: &MiningGateway,
+    miner: &str,
+    address: &str,
+) -> Result<Option<ProofOfWorkPuzzle>, Response> {
+    let Ok(receiving_address) = GenerationReceivingAddress::from_bech32m(address, s.network) else {
+        return Err(rpc_method_err(RpcError::Failed(address.to_string())));
     };
-    match s
+    let pow_puzzle = s
         .rpc_client
         .pow_puzzle_external_key(context::current(), s.token(), receiving_address.into())
         .await
         .map_err(rpc_err)?
-        .map_err(rpc_method_err)?
-    {
-        Some(pow_puzzle) => Ok(Json(pow_puzzle)),
-        None => Err(not_found_err()),
+        .map_err(rpc_method_err)?;
+
+    if let Some(pow_puzzle) = &pow_puzzle {
+        gateway
+            .log
+            .write()
+            .await
+            .issue(pow_puzzle.id, miner, &address.to_lowercase());
     }
+    Ok(pow_puzzle)
 }
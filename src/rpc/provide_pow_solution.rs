@@ -8,10 +8,14 @@ use neptune_cash::protocol::consensus::block::block_header::BlockPow;
 use serde::Deserialize;
 use serde::Serialize;
 use tarpc::context;
+use tracing::warn;
 
+use crate::http_util::mining_gateway_err;
 use crate::http_util::rpc_err;
 use crate::http_util::rpc_method_err;
 use crate::model::app_state::AppState;
+use crate::model::mining_gateway::authorize;
+use crate::model::mining_gateway::ApiKey;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PowSolution {
@@ -19,12 +23,18 @@ pub struct PowSolution {
     proposal_id: Digest,
 }
 
+/// Route: `POST /rpc/provide_pow_solution`, through the mining gateway only.
+///
+/// Hands a solution to the node and records whether it was accepted. A
+/// solution the node fails to process counts as rejected.
 #[axum::debug_handler]
 pub async fn provide_pow_solution(
+    api_key: ApiKey,
     State(state): State<Arc<AppState>>,
     Json(payload): Json<PowSolution>,
 ) -> Result<Json<bool>, Response> {
     let s = state.load();
+    let (gateway, miner) = authorize(&s, &api_key).map_err(mining_gateway_err)?;
     let result = s
         .rpc_client
         .provide_pow_solution(
@@ -34,8 +44,12 @@ pub async fn provide_pow_solution(
             payload.proposal_id,
         )
         .await
-        .map_err(rpc_err)?
-        .map_err(rpc_method_err)?;
+        .map_err(rpc_err)?;
 
-    Ok(Json(result))
+    let accepted = matches!(result, Ok(true));
+    if let Err(e) = gateway.submit(miner, payload.proposal_id, accepted).await {
+        warn!("Failed to record PoW submission: {e}");
+    }
+
+    Ok(Json(result.map_err(rpc_method_err)?))
 }
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use axum::extract::Query;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Json;
+use axum::response::Response;
+use serde::Serialize;
+
+use crate::model::app_state::AppState;
+use crate::model::chain_stats::aggregate;
+use crate::model::chain_stats::StatsBucket;
+use crate::model::chain_stats::StatsQuery;
+use crate::model::chain_stats::StatsResolution;
+use crate::rpc::supply::AmountResponse;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsBucketResponse {
+    /// Unix seconds.
+    pub start: u64,
+    pub num_blocks: usize,
+    pub average_block_interval_secs: f64,
+    pub average_difficulty: f64,
+    /// Estimated hashes per second.
+    pub hashrate: f64,
+    /// In number of `BFieldElement`s.
+    pub total_size: usize,
+    pub total_fees: AmountResponse,
+    pub num_inputs: usize,
+    pub num_outputs: usize,
+    pub num_announcements: usize,
+}
+
+impl From<StatsBucket> for StatsBucketResponse {
+    fn from(bucket: StatsBucket) -> Self {
+        Self {
+            start: bucket.start,
+            num_blocks: bucket.num_blocks,
+            average_block_interval_secs: bucket.average_block_interval_secs,
+            average_difficulty: bucket.average_difficulty,
+            hashrate: bucket.hashrate,
+            total_size: bucket.total_size,
+            total_fees: bucket.total_fees.into(),
+            num_inputs: bucket.num_inputs,
+            num_outputs: bucket.num_outputs,
+            num_announcements: bucket.num_announcements,
+        }
+    }
+}
+
+/// Chain statistics per bucket over `from..=to` (unix seconds, inclusive).
+#[derive(Debug, Clone, Serialize)]
+pub struct StatsResponse {
+    pub resolution: StatsResolution,
+    pub from: u64,
+    pub to: u64,
+    /// The height up to which blocks have been indexed; `None` while the index
+    /// is empty.
+    pub indexed_height: Option<u64>,
+    /// Buckets without blocks are omitted.
+    pub buckets: Vec<StatsBucketResponse>,
+}
+
+/// Route: `GET /rpc/stats?resolution=hour|day|week&from=..&to=..`.
+///
+/// All parameters are optional; see [`StatsQuery`]. Served from the local
+/// statistics index, without querying neptune-core.
+#[axum::debug_handler]
+pub async fn stats(
+    Query(query): Query<StatsQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<StatsResponse>, Response> {
+    let s = state.load();
+    let index = s.stats_index.read().await;
+
+    let latest_unix_seconds = index.last().map_or(0, |b| b.timestamp_ms / 1000);
+    let (from, to) = query
+        .range(latest_unix_seconds)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()).into_response())?;
+
+    Ok(Json(StatsResponse {
+        resolution: query.resolution,
+        from,
+        to,
+        indexed_height: index.last().map(|b| b.height),
+        buckets: aggregate(index.blocks(), query.resolution, from, to)
+            .into_iter()
+            .map(StatsBucketResponse::from)
+            .collect(),
+    }))
+}
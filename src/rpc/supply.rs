@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use axum::extract::Path;
+use axum::extract::State;
+use axum::response::Json;
+use axum::response::Response;
+use neptune_cash::api::export::BlockHeight;
+use neptune_cash::api::export::NativeCurrencyAmount;
+use serde::Serialize;
+use tarpc::context;
+
+use crate::http_util::node_err;
+use crate::http_util::not_found_err;
+use crate::http_util::rpc_err;
+use crate::http_util::rpc_method_err;
+use crate::model::app_state::AppState;
+use crate::model::block_at_time::block_height_at_unix_time;
+use crate::shared::exact_coins_string;
+use crate::shared::SupplyAccuracy;
+use crate::shared::SupplyBreakdown;
+
+/// An amount, exactly, as a string of nau and as a decimal string of coins.
+/// Strings, because JSON numbers lose precision beyond 2^53.
+#[derive(Debug, Clone, Serialize)]
+pub struct AmountResponse {
+    pub nau: String,
+    pub coins: String,
+}
+
+impl From<NativeCurrencyAmount> for AmountResponse {
+    fn from(amount: NativeCurrencyAmount) -> Self {
+        Self {
+            nau: amount.to_nau().to_string(),
+            coins: exact_coins_string(amount),
+        }
+    }
+}
+
+/// The monetary supply and its components at a block height, under the rules
+/// of the connected node's network. On mainnet, assumes all redemptions on the
+/// old chain have successfully been made.
+///
+/// `liquid = premine + claims_pool / 2 + mined liquid + released_timelock -
+/// burned` and `total = liquid + timelocked`: like the subsidies of the blocks
+/// skipped at the reboot it stands for, half of the claims pool is time-locked
+/// for a generation.
+#[derive(Debug, Clone, Serialize)]
+pub struct SupplyResponse {
+    pub network: String,
+    pub block_height: BlockHeight,
+    pub liquid: AmountResponse,
+    pub total: AmountResponse,
+    pub premine: AmountResponse,
+    /// Redemption claims pool; zero where claims are not paid out.
+    pub claims_pool: AmountResponse,
+    /// Cumulative known burns up to `block_height`.
+    pub burned: AmountResponse,
+    /// Mined coins and claims that are still time-locked.
+    pub timelocked: AmountResponse,
+    /// Mined coins and claims whose time-lock has (approximately) expired.
+    pub released_timelock: AmountResponse,
+    /// `exact`, `approximate` or `unsupported`, depending on the network.
+    pub accuracy: SupplyAccuracy,
+    pub accuracy_note: &'static str,
+}
+
+impl From<SupplyBreakdown> for SupplyResponse {
+    fn from(breakdown: SupplyBreakdown) -> Self {
+        Self {
+            network: breakdown.network.to_string(),
+            block_height: breakdown.block_height,
+            liquid: breakdown.liquid().into(),
+            total: breakdown.total().into(),
+            premine: breakdown.premine.into(),
+            claims_pool: breakdown.claims_pool.into(),
+            burned: breakdown.burned.into(),
+            timelocked: breakdown.timelocked().into(),
+            released_timelock: breakdown.released_timelock.into(),
+            accuracy: breakdown.accuracy,
+            accuracy_note: breakdown.accuracy.note(),
+        }
+    }
+}
+
+/// Route: `GET /rpc/supply`. The supply breakdown at the current tip, with
+/// full precision.
+#[axum::debug_handler]
+pub async fn supply(State(state): State<Arc<AppState>>) -> Result<Json<SupplyResponse>, Response> {
+    let s = state.load();
+
+    let block_height = s
+        .rpc_client
+        .block_height(context::current(), s.token())
+        .await
+        .map_err(rpc_err)?
+        .map_err(rpc_method_err)?;
+
+    Ok(Json(s.supply_breakdown(block_height).into()))
+}
+
+/// Route: `GET /rpc/supply/height/:height`. The supply breakdown after the
+/// canonical block at the given height. 404 for heights above the tip.
+#[axum::debug_handler]
+pub async fn supply_at_height(
+    Path(height): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SupplyResponse>, Response> {
+    let s = state.load();
+
+    let tip_height = s
+        .rpc_client
+        .block_height(context::current(), s.token())
+        .await
+        .map_err(rpc_err)?
+        .map_err(rpc_method_err)?;
+    if height > u64::from(tip_height) {
+        return Err(not_found_err());
+    }
+
+    Ok(Json(s.supply_breakdown(height.into()).into()))
+}
+
+/// Route: `GET /rpc/supply/at/:unix_timestamp`. The supply breakdown after the
+/// last canonical block with a timestamp at or before the given unix time (in
+/// seconds). 404 for times before the genesis block.
+#[axum::debug_handler]
+pub async fn supply_at_time(
+    Path(unix_timestamp): Path<u64>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<SupplyResponse>, Response> {
+    let s = state.load();
+
+    let block_height = block_height_at_unix_time(&s, unix_timestamp)
+        .await
+        .map_err(node_err)?
+        .ok_or_else(not_found_err)?;
+
+    Ok(Json(s.supply_breakdown(block_height).into()))
+}
@@ -14,6 +14,15 @@ use crate::shared::monetary_supplies;
 /// liquid supply. Assumes all redemptions on the old chain have successfully
 /// been made. Returned unit is in number of coins. To convert to number of
 /// nau, multiply by $4*10^{30}$.
+///
+/// Kept for compatibility. The value is rounded up to whole coins; use
+/// `/rpc/supply` for the exact amount and its components.
+///
+/// The value is the `total` of `/rpc/supply`, the same as before that was
+/// added for every height this endpoint used to answer. From height 139,505,
+/// the first block of generation 1, it used to fail: it counted only two block
+/// subsidies of time-locked coins per completed generation, which fell short
+/// of the burns.
 #[axum::debug_handler]
 pub async fn total_supply(State(state): State<Arc<AppState>>) -> Result<Json<i32>, Response> {
     let s = state.load();
@@ -25,7 +34,7 @@ pub async fn total_supply(State(state): State<Arc<AppState>>) -> Result<Json<i32
         .map_err(rpc_err)?
         .map_err(rpc_method_err)?;
 
-    let (_, total_supply) = monetary_supplies(block_height);
+    let (_, total_supply) = monetary_supplies(s.network, block_height, s.burns());
 
     Ok(Json(total_supply.ceil_num_whole_coins()))
 }
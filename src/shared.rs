@@ -1,20 +1,212 @@
 use neptune_cash::api::export::BlockHeight;
 use neptune_cash::api::export::NativeCurrencyAmount;
+use neptune_cash::api::export::Network;
 use neptune_cash::protocol::consensus::block::block_height::BLOCKS_PER_GENERATION;
 use neptune_cash::protocol::consensus::block::block_height::NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT;
 use neptune_cash::protocol::consensus::block::Block;
 use neptune_cash::protocol::consensus::block::PREMINE_MAX_SIZE;
 use num_traits::ops::checked::CheckedSub;
+use num_traits::Zero;
+use serde::Serialize;
 
-/// Return the pair (liquid supply, total supply)
+use crate::model::burn_registry::burned_at;
+use crate::model::burn_registry::BurnEntry;
+
+/// How far the supply figures for a network can be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupplyAccuracy {
+    /// The total supply follows from consensus rules and the burn registry;
+    /// only the liquid/time-locked split is approximated.
+    Exact,
+
+    /// The network follows mainnet's emission schedule, but claims and burns
+    /// are not tracked for it.
+    Approximate,
+
+    /// Blocks can be produced arbitrarily fast and the chain is routinely
+    /// reset, so the figures are little more than the nominal schedule.
+    Unsupported,
+}
+
+impl SupplyAccuracy {
+    pub fn note(&self) -> &'static str {
+        match self {
+            Self::Exact => "exact, except for the liquid/time-locked split of mined coins",
+            Self::Approximate => "approximate: redemption claims and burns are not tracked on this network",
+            Self::Unsupported => "unsupported: nominal emission schedule only, block times on this network are arbitrary",
+        }
+    }
+}
+
+/// The network-dependent parameters of the supply computation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SupplyRules {
+    pub premine: NativeCurrencyAmount,
+
+    /// The part of the genesis allocation reserved for redeeming UTXOs of the
+    /// old chain, counted only where claims are actually paid out. It stands
+    /// for the subsidies of the blocks skipped at the reboot and is released
+    /// like them: half at once, half one generation later.
+    pub claims_pool: NativeCurrencyAmount,
+
+    pub accuracy: SupplyAccuracy,
+}
+
+impl SupplyRules {
+    pub fn for_network(network: Network) -> Self {
+        match network {
+            Network::Main => Self {
+                premine: PREMINE_MAX_SIZE,
+                claims_pool: claims_pool_size(),
+                accuracy: SupplyAccuracy::Exact,
+            },
+            // The genesis block is the same on every network, but claims are
+            // refunded on mainnet only, so elsewhere the claims pool never
+            // enters circulation.
+            Network::Testnet(_) => Self {
+                premine: PREMINE_MAX_SIZE,
+                claims_pool: NativeCurrencyAmount::zero(),
+                accuracy: SupplyAccuracy::Approximate,
+            },
+            // Includes networks added to neptune-core after this was written.
+            _ => Self {
+                premine: PREMINE_MAX_SIZE,
+                claims_pool: NativeCurrencyAmount::zero(),
+                accuracy: SupplyAccuracy::Unsupported,
+            },
+        }
+    }
+}
+
+/// The size of the redemption claims pool: the subsidies of the blocks skipped
+/// at the reboot.
+pub(crate) fn claims_pool_size() -> NativeCurrencyAmount {
+    Block::block_subsidy(BlockHeight::genesis().next()).scalar_mul(
+        NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT
+            .try_into()
+            .expect("fewer than u32::MAX blocks skipped"),
+    )
+}
+
+/// The components of the monetary supply at some block height.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SupplyBreakdown {
+    pub network: Network,
+    pub block_height: BlockHeight,
+    pub premine: NativeCurrencyAmount,
+
+    /// Redemption claims pool; zero on networks where claims are not paid.
+    /// Half of it is liquid from genesis, the other half is time-locked like
+    /// mined coins and counted in `released_timelock` once released.
+    pub claims_pool: NativeCurrencyAmount,
+
+    /// Mined coins that were liquid immediately.
+    pub mined_liquid: NativeCurrencyAmount,
+
+    /// Mined coins that were time-locked when mined, released or not.
+    pub mined_timelocked: NativeCurrencyAmount,
+
+    /// The part of `mined_timelocked` and of the time-locked half of
+    /// `claims_pool` whose time-lock has (approximately) expired.
+    pub released_timelock: NativeCurrencyAmount,
+
+    /// Cumulative known burns up to `block_height`.
+    pub burned: NativeCurrencyAmount,
+
+    pub accuracy: SupplyAccuracy,
+}
+
+impl SupplyBreakdown {
+    /// Mined coins and claims that are still time-locked.
+    pub fn timelocked(&self) -> NativeCurrencyAmount {
+        (self.claims_pool.half() + self.mined_timelocked)
+            .checked_sub(&self.released_timelock)
+            .expect("no more is released than was time-locked")
+    }
+
+    /// The liquid supply, were nothing burned.
+    pub fn unburned_liquid(&self) -> NativeCurrencyAmount {
+        self.premine + self.claims_pool.half() + self.mined_liquid + self.released_timelock
+    }
+
+    /// The liquid supply. Zero if more was burned than there is, which a
+    /// [`BurnRegistry`](crate::model::burn_registry::BurnRegistry) rejects.
+    pub fn liquid(&self) -> NativeCurrencyAmount {
+        self.unburned_liquid()
+            .checked_sub(&self.burned)
+            .unwrap_or_else(NativeCurrencyAmount::zero)
+    }
+
+    /// The sum of the liquid and the still time-locked supply.
+    pub fn total(&self) -> NativeCurrencyAmount {
+        self.liquid() + self.timelocked()
+    }
+}
+
+/// Compute the components of the monetary supply on the given network at the
+/// given block height, given the known burns.
 ///
-/// Assumes all redemption claims have been rewarded.
-pub(crate) fn monetary_supplies(
+/// On mainnet, assumes all redemption claims have been rewarded.
+pub(crate) fn supply_breakdown(
+    network: Network,
     block_height: BlockHeight,
-) -> (NativeCurrencyAmount, NativeCurrencyAmount) {
-    let block_height: u64 = block_height.into();
-    let generation_0_subsidy = Block::block_subsidy(BlockHeight::genesis().next());
-    let effective_block_height = block_height + NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT;
+    burns: &[BurnEntry],
+) -> SupplyBreakdown {
+    let rules = SupplyRules::for_network(network);
+
+    // Heights are offset by the blocks skipped at the reboot, so that
+    // generations are aligned to multiples of `BLOCKS_PER_GENERATION`. The
+    // skipped blocks were never mined; their subsidies make up the claims pool.
+    let effective_block_height = u64::from(block_height) + NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT;
+
+    // Half of every block subsidy is liquid immediately, the other half is
+    // time-locked, so the two mined components are equal.
+    let mined_liquid = half_subsidies_up_to(effective_block_height)
+        .checked_sub(&half_subsidies_up_to(NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT))
+        .unwrap();
+    let mined_timelocked = mined_liquid;
+
+    // How much of timelocked miner rewards have been unlocked? Assume that the
+    // timelock is exactly one generation long. In reality the timelock is
+    // is defined in relation to timestamp and not block heights, so this is
+    // only a (good) approximation. Where claims are paid, the time-locked half
+    // of the claims pool is released with the skipped blocks it stands for.
+    let unpaid_skipped_blocks = match rules.claims_pool.is_zero() {
+        true => NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT,
+        false => 0,
+    };
+    let released_timelock = match effective_block_height.checked_sub(BLOCKS_PER_GENERATION) {
+        Some(one_generation_ago) => half_subsidies_up_to(one_generation_ago)
+            .checked_sub(&half_subsidies_up_to(
+                one_generation_ago.min(unpaid_skipped_blocks),
+            ))
+            .unwrap(),
+        None => NativeCurrencyAmount::zero(),
+    };
+
+    SupplyBreakdown {
+        network,
+        block_height,
+        premine: rules.premine,
+        claims_pool: rules.claims_pool,
+        mined_liquid,
+        mined_timelocked,
+        released_timelock,
+        burned: burned_at(burns, block_height),
+        accuracy: rules.accuracy,
+    }
+}
+
+/// The sum of half the block subsidies of effective heights 1 through
+/// `effective_block_height`.
+///
+/// Halves the subsidy before multiplying, like the node does, so the result is
+/// exact in nau.
+fn half_subsidies_up_to(effective_block_height: u64) -> NativeCurrencyAmount {
+    let blocks_per_generation: u32 = BLOCKS_PER_GENERATION
+        .try_into()
+        .expect("There are fewer than u32::MAX blocks per generation");
     let (num_generations, num_blocks_in_curr_gen): (u64, u32) = (
         effective_block_height / BLOCKS_PER_GENERATION,
         (effective_block_height % BLOCKS_PER_GENERATION)
@@ -22,49 +214,291 @@ pub(crate) fn monetary_supplies(
             .expect("There are fewer than u32::MAX blocks per generation"),
     );
 
-    let mut liquid_supply = PREMINE_MAX_SIZE;
-    let mut liquid_subsidy = generation_0_subsidy.half();
-    let mut total_supply = PREMINE_MAX_SIZE;
-    let blocks_per_generation: u32 = BLOCKS_PER_GENERATION
-        .try_into()
-        .expect("There are fewer than u32::MAX blocks per generation");
+    let mut sum = NativeCurrencyAmount::zero();
+    let mut half_subsidy = Block::block_subsidy(BlockHeight::genesis().next()).half();
     for _ in 0..num_generations {
-        liquid_supply += liquid_subsidy.scalar_mul(blocks_per_generation);
-        total_supply += liquid_subsidy.scalar_mul(2);
-        liquid_subsidy = liquid_subsidy.half();
+        sum += half_subsidy.scalar_mul(blocks_per_generation);
+        half_subsidy = half_subsidy.half();
     }
+    sum + half_subsidy.scalar_mul(num_blocks_in_curr_gen)
+}
 
-    let liquid_supply_current_generation = liquid_subsidy.scalar_mul(num_blocks_in_curr_gen);
-    liquid_supply += liquid_supply_current_generation;
-    total_supply += liquid_supply_current_generation.scalar_mul(2);
+/// The height of the first block of the given generation (block subsidy
+/// halving period). Generation 0 is shorter than the others because of the
+/// blocks skipped at the reboot.
+pub(crate) fn generation_start_height(generation: u64) -> BlockHeight {
+    match generation {
+        0 => BlockHeight::genesis(),
+        generation => {
+            (generation * BLOCKS_PER_GENERATION - NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT).into()
+        }
+    }
+}
+
+/// The generation the block at the given height belongs to.
+pub(crate) fn generation_of(block_height: BlockHeight) -> u64 {
+    (u64::from(block_height) + NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT) / BLOCKS_PER_GENERATION
+}
+
+/// An amount as a floating-point number of coins, for charts.
+pub(crate) fn coins_f64(amount: NativeCurrencyAmount) -> f64 {
+    amount.to_nau() as f64 / NativeCurrencyAmount::coins(1).to_nau() as f64
+}
+
+/// Return the pair (liquid supply, total supply)
+///
+/// On mainnet, assumes all redemption claims have been rewarded.
+pub(crate) fn monetary_supplies(
+    network: Network,
+    block_height: BlockHeight,
+    burns: &[BurnEntry],
+) -> (NativeCurrencyAmount, NativeCurrencyAmount) {
+    let breakdown = supply_breakdown(network, block_height, burns);
+    (breakdown.liquid(), breakdown.total())
+}
 
-    // List of all burns is tracked on:
-    // https://talk.neptune.cash/t/list-of-known-burns/187
-    // https://web.archive.org/web/20251210115730/https://talk.neptune.cash/t/list-of-known-burns/187
-    if block_height > 16999 {
-        let total_burn = NativeCurrencyAmount::coins_from_str("1526642.2").unwrap();
-        liquid_supply = liquid_supply.checked_sub(&total_burn).unwrap();
-        total_supply = total_supply.checked_sub(&total_burn).unwrap();
+/// The exact decimal number of coins in `amount`, without trailing zeros.
+///
+/// One nau is 2.5e-31 coins, so 32 decimals always suffice.
+pub(crate) fn exact_coins_string(amount: NativeCurrencyAmount) -> String {
+    let decimals = amount.display_n_decimals(32);
+    match decimals.split_once('.') {
+        Some((integer, fraction)) => match fraction.trim_end_matches('0') {
+            "" => integer.to_string(),
+            fraction => format!("{integer}.{fraction}"),
+        },
+        None => decimals,
     }
+}
 
-    // How much of timelocked miner rewards have been unlocked? Assume that the
-    // timelock is exactly one generation long. In reality the timelock is
-    // is defined in relation to timestamp and not block heights, so this is
-    // only a (good) approximation.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::burn_registry::BurnRegistry;
 
-    let mut released_subsidy = generation_0_subsidy.half();
-    for _ in 1..num_generations {
-        liquid_supply += released_subsidy.scalar_mul(blocks_per_generation);
-        released_subsidy = released_subsidy.half();
+    fn mainnet_burns() -> Vec<BurnEntry> {
+        BurnRegistry::bundled().burns(Network::Main).to_vec()
     }
 
-    if num_generations > 0 {
-        liquid_supply += released_subsidy.scalar_mul(num_blocks_in_curr_gen);
+    #[test]
+    fn liquid_and_timelocked_add_up_to_total() {
+        for height in [0u64, 1, 16999, 17000, 139505, 139506, 500_000] {
+            let breakdown = supply_breakdown(Network::Main, height.into(), &mainnet_burns());
+            assert_eq!(
+                breakdown.total(),
+                breakdown.liquid() + breakdown.timelocked(),
+                "height {height}"
+            );
+        }
     }
 
-    // If you want correct results for anything but main net, and claims are
-    // only being refunded on main net, the size of the claims pool must be
-    // subtracted here, if that the network is not main net.
+    #[test]
+    fn burn_applies_after_height_16999() {
+        let burns = mainnet_burns();
+        assert!(supply_breakdown(Network::Main, 16999u64.into(), &burns)
+            .burned
+            .is_zero());
+        assert_eq!(
+            NativeCurrencyAmount::coins_from_str("1526642.2").unwrap(),
+            supply_breakdown(Network::Main, 17000u64.into(), &burns).burned
+        );
+    }
+
+    #[test]
+    fn total_grows_by_full_subsidy_across_generation_boundary() {
+        // First block of generation 1, counting the blocks skipped at reboot.
+        let first_height_of_gen_1 = BLOCKS_PER_GENERATION - NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT;
+        let before = supply_breakdown(Network::Main, (first_height_of_gen_1 - 1).into(), &[]);
+        let after = supply_breakdown(Network::Main, first_height_of_gen_1.into(), &[]);
+        assert_eq!(
+            Block::block_subsidy(BlockHeight::genesis().next()),
+            after.total().checked_sub(&before.total()).unwrap()
+        );
+    }
 
-    (liquid_supply, total_supply)
+    #[test]
+    fn mainnet_supply_includes_claims_pool() {
+        // premine 831488 + claims pool 21310 * 128, half of it time-locked
+        let genesis = supply_breakdown(Network::Main, BlockHeight::genesis(), &[]);
+        assert_eq!(NativeCurrencyAmount::coins(3_559_168), genesis.total());
+        assert_eq!(NativeCurrencyAmount::coins(2_195_328), genesis.liquid());
+        assert_eq!(SupplyAccuracy::Exact, genesis.accuracy);
+
+        let at_100 = supply_breakdown(Network::Main, 100u64.into(), &[]);
+        assert_eq!(
+            NativeCurrencyAmount::coins(3_559_168 + 12_800),
+            at_100.total()
+        );
+        assert_eq!(
+            NativeCurrencyAmount::coins(2_195_328 + 6_400),
+            at_100.liquid()
+        );
+        assert_eq!(
+            NativeCurrencyAmount::coins(1_363_840 + 6_400),
+            at_100.timelocked()
+        );
+    }
+
+    #[test]
+    fn testnet_supply_excludes_claims_pool() {
+        for network in [Network::Testnet(0), Network::Testnet(1)] {
+            let at_100 = supply_breakdown(network, 100u64.into(), &[]);
+            assert!(at_100.claims_pool.is_zero());
+            assert_eq!(
+                NativeCurrencyAmount::coins(831_488 + 12_800),
+                at_100.total()
+            );
+            assert_eq!(
+                NativeCurrencyAmount::coins(831_488 + 6_400),
+                at_100.liquid()
+            );
+            assert_eq!(SupplyAccuracy::Approximate, at_100.accuracy);
+        }
+    }
+
+    #[test]
+    fn mock_networks_are_unsupported() {
+        for network in [Network::RegTest, Network::TestnetMock] {
+            let genesis = supply_breakdown(network, BlockHeight::genesis(), &[]);
+            assert_eq!(NativeCurrencyAmount::coins(831_488), genesis.total());
+            assert_eq!(SupplyAccuracy::Unsupported, genesis.accuracy);
+        }
+    }
+
+    #[test]
+    fn timelock_release_starts_one_generation_after_mining() {
+        let first_height_of_gen_1 = BLOCKS_PER_GENERATION - NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT;
+        let half_subsidy = Block::block_subsidy(BlockHeight::genesis().next()).half();
+        // Block 1 is released one generation later, at BLOCKS_PER_GENERATION + 1.
+        let testnet = Network::Testnet(0);
+        let before = supply_breakdown(testnet, BLOCKS_PER_GENERATION.into(), &[]);
+        let after = supply_breakdown(testnet, (BLOCKS_PER_GENERATION + 1).into(), &[]);
+        assert!(before.released_timelock.is_zero());
+        assert_eq!(half_subsidy, after.released_timelock);
+
+        // On mainnet, the claims pool is released first, one skipped block at
+        // a time, after the first block of generation 1.
+        let released =
+            |height: u64| supply_breakdown(Network::Main, height.into(), &[]).released_timelock;
+        assert!(released(first_height_of_gen_1).is_zero());
+        assert_eq!(half_subsidy, released(first_height_of_gen_1 + 1));
+        assert_eq!(
+            before.released_timelock + claims_pool_size().half(),
+            supply_breakdown(Network::Main, BLOCKS_PER_GENERATION.into(), &[]).released_timelock
+        );
+    }
+
+    /// `monetary_supplies` as it was before supply was computed per network:
+    /// mainnet only, with the burns hardcoded.
+    fn baseline_monetary_supplies(
+        block_height: BlockHeight,
+    ) -> (NativeCurrencyAmount, NativeCurrencyAmount) {
+        let block_height: u64 = block_height.into();
+        let generation_0_subsidy = Block::block_subsidy(BlockHeight::genesis().next());
+        let effective_block_height = block_height + NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT;
+        let (num_generations, num_blocks_in_curr_gen): (u64, u32) = (
+            effective_block_height / BLOCKS_PER_GENERATION,
+            (effective_block_height % BLOCKS_PER_GENERATION)
+                .try_into()
+                .unwrap(),
+        );
+
+        let mut liquid_supply = PREMINE_MAX_SIZE;
+        let mut liquid_subsidy = generation_0_subsidy.half();
+        let mut total_supply = PREMINE_MAX_SIZE;
+        let blocks_per_generation: u32 = BLOCKS_PER_GENERATION.try_into().unwrap();
+        for _ in 0..num_generations {
+            liquid_supply += liquid_subsidy.scalar_mul(blocks_per_generation);
+            total_supply += liquid_subsidy.scalar_mul(2);
+            liquid_subsidy = liquid_subsidy.half();
+        }
+
+        let liquid_supply_current_generation = liquid_subsidy.scalar_mul(num_blocks_in_curr_gen);
+        liquid_supply += liquid_supply_current_generation;
+        total_supply += liquid_supply_current_generation.scalar_mul(2);
+
+        if block_height > 16999 {
+            let total_burn = NativeCurrencyAmount::coins_from_str("1526642.2").unwrap();
+            liquid_supply = liquid_supply.checked_sub(&total_burn).unwrap();
+            total_supply = total_supply.checked_sub(&total_burn).unwrap();
+        }
+
+        let mut released_subsidy = generation_0_subsidy.half();
+        for _ in 1..num_generations {
+            liquid_supply += released_subsidy.scalar_mul(blocks_per_generation);
+            released_subsidy = released_subsidy.half();
+        }
+
+        if num_generations > 0 {
+            liquid_supply += released_subsidy.scalar_mul(num_blocks_in_curr_gen);
+        }
+
+        (liquid_supply, total_supply)
+    }
+
+    #[test]
+    fn mainnet_figures_match_the_baseline() {
+        // The baseline answered throughout generation 0. From its first block
+        // on, its total counted only two half subsidies per completed
+        // generation, fell below the burns, and `checked_sub` panicked.
+        let first_height_of_gen_1 = BLOCKS_PER_GENERATION - NUM_BLOCKS_SKIPPED_BECAUSE_REBOOT;
+        for height in [
+            0,
+            1,
+            100,
+            16_999,
+            17_000,
+            100_000,
+            first_height_of_gen_1 - 1,
+        ] {
+            assert_eq!(
+                baseline_monetary_supplies(height.into()),
+                monetary_supplies(Network::Main, height.into(), &mainnet_burns()),
+                "height {height}"
+            );
+        }
+        assert!(std::panic::catch_unwind(|| {
+            baseline_monetary_supplies(first_height_of_gen_1.into())
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn compatibility_figures_are_pinned() {
+        // What /rpc/circulating_supply and /rpc/total_supply answer on
+        // mainnet: at height 0, 100 blocks into generation 1, and well into
+        // it, where the time-locks of generation 0 are being released.
+        let whole_coins = |height: u64| {
+            let (liquid, total) = monetary_supplies(Network::Main, height.into(), &mainnet_burns());
+            (liquid.ceil_num_whole_coins(), total.ceil_num_whole_coins())
+        };
+        assert_eq!((2_195_328, 3_559_168), whole_coins(0));
+        assert_eq!((9_606_606, 19_895_566), whole_coins(139_605));
+        assert_eq!((25_004_526, 30_160_846), whole_coins(300_000));
+    }
+
+    #[test]
+    fn generation_boundaries_agree() {
+        for generation in 0..5 {
+            let start = generation_start_height(generation);
+            assert_eq!(generation, generation_of(start));
+            if let Some(previous) = start.previous() {
+                assert_eq!(generation - 1, generation_of(previous));
+            }
+        }
+    }
+
+    #[test]
+    fn exact_coins_string_is_lossless() {
+        assert_eq!("0", exact_coins_string(NativeCurrencyAmount::zero()));
+        assert_eq!("128", exact_coins_string(NativeCurrencyAmount::coins(128)));
+        assert_eq!(
+            "1526642.2",
+            exact_coins_string(NativeCurrencyAmount::coins_from_str("1526642.2").unwrap())
+        );
+        assert_eq!(
+            "0.00000000000000000000000000000025",
+            exact_coins_string(NativeCurrencyAmount::from_nau(1))
+        );
+    }
 }